@@ -1,47 +1,371 @@
 use color_eyre::Result;
+use std::path::{Path, PathBuf};
 
 pub mod messages;
 pub mod model;
 pub mod view;
 
 pub(crate) use crate::messages::log_message::*;
+pub(crate) use crate::model::access::*;
+pub(crate) use crate::model::adb::*;
+pub(crate) use crate::model::app::*;
+pub(crate) use crate::model::case::*;
+pub(crate) use crate::model::cef::*;
+pub(crate) use crate::model::command::*;
+pub(crate) use crate::model::context::*;
+pub(crate) use crate::model::delimited::*;
+pub(crate) use crate::model::docker::*;
+pub(crate) use crate::model::eventlog::*;
+pub(crate) use crate::model::extract::*;
+pub(crate) use crate::model::fifo::*;
+pub(crate) use crate::model::filter_expr::*;
+pub(crate) use crate::model::filters::*;
+pub(crate) use crate::model::format::*;
+pub(crate) use crate::model::gaps::*;
+pub(crate) use crate::model::history::*;
+pub(crate) use crate::model::http::*;
+pub(crate) use crate::model::journal::*;
+pub(crate) use crate::model::json::*;
+pub(crate) use crate::model::jump_list::*;
+pub(crate) use crate::model::kube::*;
+pub(crate) use crate::model::level::*;
+pub(crate) use crate::model::line_format::*;
+pub(crate) use crate::model::listener::*;
+pub(crate) use crate::model::loader::*;
+pub use crate::model::log_entry::LogEntry;
 pub(crate) use crate::model::log_model::*;
+pub(crate) use crate::model::log4j::*;
+pub(crate) use crate::model::logfmt::*;
+pub(crate) use crate::model::logger::*;
+pub(crate) use crate::model::marks::*;
+pub(crate) use crate::model::minimap::*;
+pub(crate) use crate::model::multiline::*;
+pub(crate) use crate::model::otlp::*;
+pub(crate) use crate::model::picker::*;
+pub(crate) use crate::model::positions::*;
+pub(crate) use crate::model::preset::*;
+pub(crate) use crate::model::quick_filter::*;
+pub(crate) use crate::model::repeats::*;
+pub(crate) use crate::model::rotation::*;
+pub(crate) use crate::model::s3::*;
+pub(crate) use crate::model::sampler::*;
+pub(crate) use crate::model::search::*;
+pub(crate) use crate::model::serial::*;
+pub(crate) use crate::model::settings::*;
+pub(crate) use crate::model::source::{FileSource, StreamSource};
+pub use crate::model::source::{LogSource, NewLines, SourceMetadata};
+pub(crate) use crate::model::ssh::*;
+pub(crate) use crate::model::stream::*;
+pub(crate) use crate::model::structured::*;
+pub(crate) use crate::model::syslog::*;
+pub(crate) use crate::model::tab_search::*;
+pub(crate) use crate::model::template::*;
+pub(crate) use crate::model::time_range::*;
+pub(crate) use crate::model::timestamp::*;
+pub(crate) use crate::model::w3c::*;
+pub(crate) use crate::model::watcher::*;
+pub(crate) use crate::view::ansi::*;
 pub(crate) use crate::view::log_view::*;
+pub(crate) use crate::view::picker_view::*;
 pub(crate) use crate::view::tui;
 
+/// Where the logs of a single tab come from.
+pub(crate) enum Input {
+    /// Files on disk. Holds several files when a glob matched a rotated log.
+    Files(Vec<String>),
+    /// The systemd journal, optionally limited to one unit.
+    Journal(Option<String>),
+    /// The logs of a docker container.
+    Docker(String),
+    /// The logs of a kubernetes pod, or of the pods matching a selector.
+    Kube(KubeTarget),
+    /// A file on a remote host, followed over ssh.
+    Ssh(SshTarget),
+    /// A log served over http or https.
+    Http(String),
+    /// A syslog listener on the given transport and address.
+    Syslog(Transport, String),
+    /// A Windows event log channel or `.evtx` file.
+    EventLog(String),
+    /// A tcp listener collecting lines from any number of clients.
+    Tcp(String),
+    /// An OTLP/HTTP logs receiver.
+    Otlp(String),
+    /// An object in S3 or an S3 compatible store.
+    S3(S3Object),
+    /// A serial port, like a UART on an embedded board.
+    Serial(SerialTarget),
+    /// `adb logcat`, optionally for a specific device serial.
+    Adb(Option<String>),
+    /// Lines piped into the viewer.
+    Stdin,
+    /// A directory to pick files from inside the viewer.
+    Directory(String),
+    /// A source implemented outside of this crate.
+    Source(Box<dyn LogSource>),
+}
+
 pub struct Config {
-    file_path: String,
+    /// The inputs to open, one per tab.
+    inputs: Vec<Input>,
+    merge: bool,
+    parse_options: ParseOptions,
+    /// Saved filters, from the config file.
+    presets: Vec<Preset>,
+    /// The preset applied to every tab on start.
+    preset: Option<Preset>,
+    config_path: Option<PathBuf>,
+    /// Whether case counts when matching, from the command line or else the
+    /// config file.
+    case: CaseMode,
+    /// The lines shown around the ones the filters let through.
+    context: Context,
+    /// How fuzzy searches are matched, from the command line or else the
+    /// config file.
+    fuzzy: Fuzzy,
+    /// What searches do once they're kept, from the config file.
+    search_style: SearchStyle,
+    /// How long the log goes quiet for a gap, from the config file.
+    gap_threshold: GapThreshold,
+    /// Rows kept between the selection and the edges, from the config file.
+    scrolloff: usize,
+    /// Whether files open at the bottom with no filters, rather than where
+    /// they were left.
+    fresh: bool,
 }
 
 impl Config {
     pub fn new(args: &[String]) -> Result<Self, &'static str> {
-        if args.len() < 2 {
+        let mut inputs = vec![];
+        let mut merge = false;
+        let mut fresh = false;
+        let mut parse_options = ParseOptions::default();
+        let mut config_path = None;
+        let mut preset_name = None;
+        let mut context = Context::default();
+        let mut case = None;
+        let mut threshold = None;
+        let mut scorer = None;
+        let mut args = args[1..].iter().peekable();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--merge" => merge = true,
+                "--fresh" => fresh = true,
+                "--config" => {
+                    let path = args.next().ok_or("--config needs a file path.")?;
+                    config_path = Some(path.as_str());
+                }
+                "--preset" => {
+                    let name = args.next().ok_or("--preset needs a preset name.")?;
+                    preset_name = Some(name.as_str());
+                }
+                "-" => inputs.push(Input::Stdin),
+                "--level-column" => {
+                    let column = args.next().ok_or("--level-column needs a column name.")?;
+                    parse_options.level_column = Some(column.clone());
+                }
+                "--level-token" => {
+                    let position = args.next().ok_or("--level-token needs a token position.")?;
+                    parse_options.level_rule = LevelRule::token(position)?;
+                }
+                "--level-regex" => {
+                    let pattern = args.next().ok_or("--level-regex needs a regex.")?;
+                    parse_options.level_rule = LevelRule::regex(pattern)?;
+                }
+                "--format" => {
+                    let name = args.next().ok_or("--format needs a format name.")?;
+                    parse_options.format = LogFormat::from_name(name)?;
+                }
+                "--line-format" => {
+                    let pattern = args.next().ok_or("--line-format needs a regex.")?;
+                    parse_options.line_format = Some(LineFormat::parse(pattern)?);
+                }
+                "--extract" => {
+                    let pattern = args.next().ok_or("--extract needs a regex.")?;
+                    parse_options.extractors.push(FieldExtractor::new(pattern)?);
+                }
+                "--sample" => {
+                    let rate = args
+                        .next()
+                        .and_then(|rate| rate.parse().ok())
+                        .filter(|rate| *rate > 1)
+                        .ok_or("--sample needs how many lines to keep one of, like 100.")?;
+                    parse_options.sample_rate = Some(rate);
+                }
+                "-A" | "-B" | "-C" => {
+                    let lines = args
+                        .next()
+                        .and_then(|lines| lines.parse().ok())
+                        .ok_or("-A, -B and -C need a number of lines.")?;
+                    if arg != "-A" {
+                        context.before = lines;
+                    }
+                    if arg != "-B" {
+                        context.after = lines;
+                    }
+                }
+                "--case" => {
+                    case = Some(
+                        args.next()
+                            .and_then(|name| CaseMode::from_name(name))
+                            .ok_or("--case needs smart, sensitive or insensitive.")?,
+                    );
+                }
+                "--fuzzy-threshold" => {
+                    threshold = Some(
+                        args.next()
+                            .and_then(|threshold| threshold.parse().ok())
+                            .and_then(Fuzzy::valid_threshold)
+                            .ok_or("--fuzzy-threshold needs a number from 0 to 1, like 0.6.")?,
+                    );
+                }
+                "--fuzzy-scorer" => {
+                    scorer = Some(
+                        args.next()
+                            .and_then(|name| FuzzyScorer::from_name(name))
+                            .ok_or("--fuzzy-scorer needs trigram or subsequence.")?,
+                    );
+                }
+                "--encoding" => {
+                    let label = args.next().ok_or("--encoding needs an encoding name.")?;
+                    let encoding = encoding_rs::Encoding::for_label(label.as_bytes())
+                        .ok_or("Unknown --encoding, try utf-8, utf-16le or latin1.")?;
+                    parse_options.encoding = Some(encoding);
+                }
+                "--log4j-pattern" => {
+                    let pattern = args
+                        .next()
+                        .ok_or("--log4j-pattern needs a layout pattern.")?;
+                    parse_options.line_format = Some(parse_log4j_pattern(pattern)?);
+                }
+                "--journal" => {
+                    // The unit is optional, so only take the next argument if
+                    // it isn't another flag.
+                    let unit = args.next_if(|next| !next.starts_with('-')).cloned();
+                    inputs.push(Input::Journal(unit));
+                }
+                "--docker" => {
+                    let container = args.next().ok_or("--docker needs a container name.")?;
+                    inputs.push(Input::Docker(container.clone()));
+                }
+                "--kube" => {
+                    let target = args.next().ok_or("--kube needs a <namespace>/<pod>.")?;
+                    inputs.push(Input::Kube(KubeTarget::parse(target)?));
+                }
+                url if SshTarget::is_ssh(url) => inputs.push(Input::Ssh(SshTarget::parse(url)?)),
+                "--listen-syslog" => {
+                    let addr = args.next().ok_or("--listen-syslog needs an address.")?;
+                    let (transport, addr) = parse_listen_addr(addr)?;
+                    inputs.push(Input::Syslog(transport, addr));
+                }
+                "--listen" => {
+                    let addr = args.next().ok_or("--listen needs an address.")?;
+                    inputs.push(Input::Tcp(parse_tcp_addr(addr)?));
+                }
+                "--listen-otlp" => {
+                    let addr = args
+                        .next()
+                        .ok_or("--listen-otlp needs a port or address.")?;
+                    inputs.push(Input::Otlp(parse_otlp_addr(addr)?));
+                }
+                "--adb" => {
+                    let device = args.next_if(|next| !next.starts_with('-')).cloned();
+                    inputs.push(Input::Adb(device));
+                }
+                "--serial" => {
+                    let target = args.next().ok_or("--serial needs a device path.")?;
+                    inputs.push(Input::Serial(SerialTarget::parse(target)?));
+                }
+                "--eventlog" => {
+                    if !cfg!(windows) {
+                        return Err("--eventlog is only available on Windows.");
+                    }
+                    let channel = args
+                        .next()
+                        .ok_or("--eventlog needs a channel or .evtx file.")?;
+                    inputs.push(Input::EventLog(channel.clone()));
+                }
+                uri if S3Object::is_s3(uri) => inputs.push(Input::S3(S3Object::parse(uri)?)),
+                url if is_url(url) => inputs.push(Input::Http(url.to_string())),
+                dir if Path::new(dir).is_dir() => {
+                    if inputs
+                        .iter()
+                        .any(|input| matches!(input, Input::Directory(_)))
+                    {
+                        return Err("Only one directory can be opened at a time.");
+                    }
+                    inputs.push(Input::Directory(dir.to_string()));
+                }
+                glob if is_glob(glob) => inputs.push(Input::Files(expand_rotated(glob)?)),
+                _ => inputs.push(Input::Files(vec![arg.clone()])),
+            }
+        }
+
+        if inputs.is_empty() {
             return Err("Must provide a file path.");
         }
-        let file_path = args[1].clone();
+
+        let settings = Settings::load(config_path)?;
+        parse_options.level_aliases = settings.level_aliases;
+        parse_options.extractors.extend(settings.extractors);
+        let preset = match preset_name {
+            Some(name) => Some(
+                settings
+                    .presets
+                    .iter()
+                    .find(|preset| preset.name == name)
+                    .cloned()
+                    .ok_or("--preset names a preset that isn't in the config file.")?,
+            ),
+            None => None,
+        };
 
         Ok(Config {
-            file_path,
+            inputs,
+            merge,
+            parse_options,
+            presets: settings.presets,
+            preset,
+            config_path: settings.path,
+            case: case.unwrap_or(settings.case),
+            fuzzy: Fuzzy {
+                threshold: threshold.unwrap_or(settings.fuzzy.threshold),
+                scorer: scorer.unwrap_or(settings.fuzzy.scorer),
+            },
+            search_style: settings.search_style,
+            gap_threshold: settings.gap_threshold,
+            scrolloff: settings.scrolloff,
+            fresh,
+            context,
         })
     }
+
+    /// Opens a tab reading from the given source, for sources that aren't
+    /// built in.
+    pub fn add_source(&mut self, source: impl LogSource + 'static) {
+        self.inputs.push(Input::Source(Box::new(source)));
+    }
 }
 
 pub fn run(config: Config) -> Result<()> {
+    // Open everything before taking over the terminal so errors are readable.
+    let mut app = App::new(config)?;
     tui::install_panic_hook();
     let mut terminal = tui::init_terminal()?;
-    let mut model = Model::new(config)?;
 
-    while model.running != RunningState::Done {
+    while app.running != RunningState::Done {
         // render the current view
-        terminal.draw(|frame| view(frame, &mut model))?;
+        terminal.draw(|frame| view(frame, &mut app))?;
 
-        let mut current_msg = handle_event(&mut model)?;
+        let mut current_msg = handle_event(&mut app)?;
 
         while current_msg.is_some() {
-            current_msg = update(&mut model, current_msg.unwrap());
+            current_msg = update_app(&mut app, current_msg.unwrap());
         }
     }
 
     tui::restore_terminal()?;
+    app.save_positions();
     Ok(())
 }
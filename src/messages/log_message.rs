@@ -1,17 +1,245 @@
-use crate::Filter;
+use crate::{Jump, Level, MarkKey, Prompt, ScreenPlace};
 
+#[derive(Debug, Clone)]
 pub(crate) enum Message {
     MoveUp,
     MoveDown,
     MoveTop,
     MoveBottom,
     AddChar(char),
+    /// Inserts text pasted into the search or prompt at the cursor.
+    Paste(String),
+    /// Deletes the character under the cursor.
+    DeleteForward,
+    /// Deletes the word before the cursor.
+    DeleteWord,
+    /// Deletes everything before the cursor.
+    DeleteToStart,
+    MoveCursorStart,
+    MoveCursorEnd,
+    MoveCursorWordLeft,
+    MoveCursorWordRight,
     Delete,
     MoveCursorLeft,
     MoveCursorRight,
     MoveUpPage,
     MoveDownPage,
+    MoveUpHalfPage,
+    MoveDownHalfPage,
+    /// Selects the line at the top, middle or bottom of the screen.
+    SelectOnScreen(ScreenPlace),
+    /// Waits for where to scroll the selected line to.
+    AwaitAlign,
+    /// Scrolls the selected line to the top, middle or bottom of the screen.
+    AlignSelection(ScreenPlace),
+    /// Adds a digit to the number typed before a key.
+    AddCountDigit(usize),
+    /// Goes the number typed before it percent of the way through the lines
+    /// listed.
+    GotoPercent,
+    /// Scrolls the view with the mouse wheel.
+    WheelUp,
+    WheelDown,
+    /// Selects the line on the row of the view that was clicked.
+    SelectRow(usize),
+    /// Starts or stops dragging the divider between the list and the
+    /// preview.
+    Resizing(bool),
+    /// Gives the list this percent of the width, as the divider's dragged.
+    ResizePanes(u16),
+    /// Scrolls the lines sideways, to read the end of long ones.
+    ScrollLeft,
+    ScrollRight,
+    ScrollStart,
+    /// Scrolls to the end of the longest line shown.
+    ScrollEnd,
     ToggleSearch,
-    ApplyFilter(Filter),
+    /// Opens the search to go up the log with `n`, like `?` in less.
+    SearchBackward,
+    /// Leaves the search bar with the search kept, showing every line again
+    /// to jump between the ones it matches.
+    SubmitSearch,
+    /// Searches for what was submitted to the search again and jumps on to
+    /// its next match, for `.` to repeat a search.
+    RepeatSearch(String),
+    /// Starts a new search among the lines the current one matches.
+    NarrowSearch,
+    /// Goes back to the search narrowed down from, to edit it.
+    PopNarrowing,
+    NextMatch,
+    PrevMatch,
+    /// Searches for the word of the selected line most likely to be an
+    /// identifier, like `*` and `#` in vim, and jumps to where it's next or
+    /// last found.
+    SearchWord(Jump),
+    /// Does the last change to the filters, or the last jump, again.
+    RepeatAction,
+    /// Repeats the last move to the next or previous match, level or gap,
+    /// the other way round when it's reversed.
+    RepeatMotion {
+        reverse: bool,
+    },
+    /// Keeps highlighting what the search matches in a color of its own, or
+    /// stops if it's already pinned.
+    PinSearch,
+    /// Moves on to the next place the preview search occurs in the preview.
+    NextPreviewMatch,
+    PrevPreviewMatch,
+    OpenPrompt(Prompt),
+    SubmitPrompt,
+    /// Applies what was submitted to a prompt again, for `.` to repeat a
+    /// filter typed into one.
+    RepeatPrompt(Prompt, String),
+    /// Matches the search another way: fuzzy, exact or as a regex.
+    CycleSearchKind,
+    /// Needs lines to match a fuzzy search more closely.
+    TightenFuzzy,
+    LoosenFuzzy,
+    /// Scores fuzzy searches the other way: by runs of three letters, or by
+    /// the letters in order.
+    CycleFuzzyScorer,
+    /// Brings back an older entry of the history into the prompt or search.
+    HistoryOlder,
+    HistoryNewer,
+    /// Brings back an older entry of the history holding what was typed.
+    HistoryMatching,
+    CancelPrompt,
+    ToggleFollow,
+    RefreshLogs,
+    ClearLogs,
+    ToggleGroups,
+    ToggleColors,
+    /// Numbers the lines by where they are in their file, then also by where
+    /// they are among the lines listed, then only while searching.
+    CycleGutter,
+    OpenFormatPicker,
+    PickFormat,
+    CloseFormatPicker,
+    ToggleFilterMenu,
+    ToggleLevel(Level),
+    ClearLevels,
+    OpenPresets,
+    PickPreset,
+    ClosePresets,
+    /// Saves the current filters under the name, sent once its prompt is
+    /// submitted.
+    SavePreset(String),
+    /// Searches every tab, sent once its prompt is submitted.
+    SearchTabs(String),
+    /// Pins the selected line above the others, or unpins it.
+    TogglePinLine,
+    /// Waits for the letter of a mark to set or go back to.
+    AwaitMark(MarkKey),
+    SetMark(char),
+    JumpToMark(char),
+    /// Waits for the level of the line, or the gap, to jump to.
+    AwaitJump(Jump),
+    /// Selects the next or previous line of the level, leaving the filters
+    /// as they are.
+    JumpToLevel(Level, Jump),
+    /// Selects the next or previous line logged after the log went quiet.
+    JumpToGap(Jump),
+    /// Selects where the next or previous stretch of errors the minimap
+    /// shows starts.
+    JumpToHot(Jump),
+    /// Shows where the errors are in the whole log at the right edge.
+    ToggleMinimap,
+    /// Selects the worst line of the stretch a row of the minimap stands
+    /// for, when it's clicked.
+    SelectMinimapRow(usize),
+    /// Goes back to the line jumped away from, like vim's `Ctrl-o`.
+    JumpBack,
+    /// Goes forward again through the lines gone back from.
+    JumpForward,
+    /// Drops a key waiting for the one after it, like the `m` of a mark.
+    CancelPendingKeys,
+    /// Goes back to the mark selected in the list of marks.
+    PickMark,
+    DeleteMark,
+    CloseMarks,
+    /// Switches between a kept search filtering the lines and jumping
+    /// between its matches.
+    ToggleSearchStyle,
+    /// Jumps the tab of the selected line found by searching every tab to it.
+    OpenTabMatch,
+    CloseTabSearch,
+    OpenLoggerPicker,
+    CloseLoggerPicker,
+    /// Shows only the loggers picked this way, or stops showing only it.
+    IncludeLogger,
+    /// Hides the selected logger, or shows it again.
+    MuteLogger,
+    ClearLoggers,
+    /// Hides the selected line, along with any repeats of it.
+    HideLine,
+    /// Hides every line like the selected one, apart from numbers and IDs.
+    HideLikeLine,
+    /// Undoes the last change to the filters.
+    PopFilter,
+    QuickFilter,
+    ClearQuickFilter,
+    /// Moves every tab on to the next way of treating case when matching.
+    ToggleCase,
+    /// Keeps fewer of the lines coming in, until it's back to keeping all.
+    CycleSampling,
+    /// Collapses repeated lines, then lines like the one above, then neither.
+    CycleDedup,
+    /// Opens up the lines grouped under the selected entry, or the collapsed
+    /// repeats on it, or folds them away again.
+    ToggleFold,
+    RaiseMinLevel,
+    LowerMinLevel,
+    /// Opens the log of the tab again in a new tab, to filter it another
+    /// way.
+    DuplicateTab,
+    NextTab,
+    PrevTab,
+    OpenPicker,
+    OpenPicked,
+    ClosePicker,
     Quit,
 }
+
+impl Message {
+    /// Whether `.` does this again, as a change to the filters or a jump.
+    pub(crate) fn repeatable(&self) -> bool {
+        matches!(
+            self,
+            Message::HideLine
+                | Message::HideLikeLine
+                | Message::QuickFilter
+                | Message::ClearQuickFilter
+                | Message::ToggleLevel(_)
+                | Message::ClearLevels
+                | Message::RaiseMinLevel
+                | Message::LowerMinLevel
+                | Message::PopFilter
+                | Message::PinSearch
+                | Message::RepeatSearch(_)
+                | Message::RepeatPrompt(..)
+                | Message::TogglePinLine
+                | Message::NextMatch
+                | Message::PrevMatch
+                | Message::SearchWord(_)
+                | Message::JumpToMark(_)
+                | Message::JumpToLevel(..)
+                | Message::JumpToGap(_)
+                | Message::JumpToHot(_)
+        )
+    }
+
+    /// The same move the other way round, for the moves `;` and `,` repeat.
+    pub(crate) fn reversed(&self) -> Option<Message> {
+        match self {
+            Message::NextMatch => Some(Message::PrevMatch),
+            Message::PrevMatch => Some(Message::NextMatch),
+            Message::JumpToLevel(level, jump) => {
+                Some(Message::JumpToLevel(*level, jump.reversed()))
+            }
+            Message::JumpToGap(jump) => Some(Message::JumpToGap(jump.reversed())),
+            Message::JumpToHot(jump) => Some(Message::JumpToHot(jump.reversed())),
+            Message::SearchWord(jump) => Some(Message::SearchWord(jump.reversed())),
+            _ => None,
+        }
+    }
+}
@@ -1 +1,57 @@
+pub mod access;
+pub mod adb;
+pub mod app;
+pub mod case;
+pub mod cef;
+pub mod command;
+pub mod context;
+pub mod delimited;
+pub mod docker;
+pub mod eventlog;
+pub mod extract;
+pub mod fifo;
+pub mod filter_expr;
+pub mod filters;
+pub mod format;
+pub mod gaps;
+pub mod history;
+pub mod http;
+pub mod journal;
+pub mod json;
+pub mod jump_list;
+pub mod kube;
+pub mod level;
+pub mod line_format;
+pub mod listener;
+pub mod loader;
+pub mod log4j;
+pub mod log_entry;
 pub mod log_model;
+pub mod logfmt;
+pub mod logger;
+pub mod marks;
+pub mod minimap;
+pub mod multiline;
+pub mod otlp;
+pub mod picker;
+pub mod positions;
+pub mod preset;
+pub mod quick_filter;
+pub mod repeats;
+pub mod rotation;
+pub mod s3;
+pub mod sampler;
+pub mod search;
+pub mod serial;
+pub mod settings;
+pub mod source;
+pub mod ssh;
+pub mod stream;
+pub mod structured;
+pub mod syslog;
+pub mod tab_search;
+pub mod template;
+pub mod time_range;
+pub mod timestamp;
+pub mod w3c;
+pub mod watcher;
@@ -0,0 +1,96 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::StructuredLine;
+
+/// The common and combined log formats written by apache and nginx, with the
+/// request time some setups add at the end.
+static ACCESS_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r#"^(?P<ip>\S+) \S+ (?P<user>\S+) \[(?P<time>[^\]]+)\] "#,
+        r#""(?P<method>[A-Z]+) (?P<path>\S+)[^"]*" (?P<status>\d{3}) (?P<bytes>\S+)"#,
+        r#"(?: "(?P<referer>[^"]*)" "(?P<agent>[^"]*)")?(?: (?P<latency>[\d.]+))?\s*$"#,
+    ))
+    .expect("access log regex is valid")
+});
+
+/// Makes up a level for an http status: server errors are errors, client
+/// errors are warnings and everything else is info.
+pub(crate) fn status_level(status: &str) -> &'static str {
+    match status.as_bytes().first() {
+        Some(b'5') => "ERROR",
+        Some(b'4') => "WARNING",
+        _ => "INFO",
+    }
+}
+
+/// Parses a line of an apache or nginx access log. These have no level, so
+/// one is made up from the status.
+pub(crate) fn parse_access(line: &str) -> Option<StructuredLine> {
+    let captures = ACCESS_LINE.captures(line)?;
+    let capture = |name: &str| captures.name(name).map(|value| value.as_str());
+
+    let level = status_level(capture("status")?);
+
+    // `-` is what the formats write for a missing value.
+    let fields = [
+        "status", "ip", "user", "bytes", "latency", "referer", "agent",
+    ]
+    .into_iter()
+    .filter_map(|name| match capture(name)? {
+        "-" | "" => None,
+        value => Some((name.to_string(), value.to_string())),
+    })
+    .collect();
+
+    Some(StructuredLine {
+        timestamp: capture("time").map(String::from),
+        level: Some(level.to_string()),
+        message: Some(format!("{} {}", capture("method")?, capture("path")?)),
+        fields,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimestampFormat;
+
+    #[test]
+    fn parses_a_combined_log_line() {
+        let line = parse_access(concat!(
+            r#"203.0.113.7 - alice [03/May/2024:16:02:10 +0200] "GET /orders?id=7 HTTP/1.1" "#,
+            r#"503 512 "-" "curl/8.5.0" 0.042"#,
+        ))
+        .unwrap();
+        assert_eq!(line.level.as_deref(), Some("ERROR"));
+        assert_eq!(line.message.as_deref(), Some("GET /orders?id=7"));
+        let fields: Vec<(&str, &str)> = line
+            .fields
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("status", "503"),
+                ("ip", "203.0.113.7"),
+                ("user", "alice"),
+                ("bytes", "512"),
+                ("latency", "0.042"),
+                ("agent", "curl/8.5.0"),
+            ]
+        );
+        let time = TimestampFormat::Clf.parse(line.timestamp.as_deref().unwrap());
+        assert_eq!(time.unwrap().to_string(), "2024-05-03 14:02:10");
+    }
+
+    #[test]
+    fn makes_up_a_level_from_the_status() {
+        let line =
+            parse_access(r#"10.0.0.1 - - [03/May/2024:14:02:10 +0000] "POST /login HTTP/2" 404 -"#);
+        assert_eq!(line.unwrap().level.as_deref(), Some("WARNING"));
+        assert_eq!(status_level("302"), "INFO");
+        assert!(parse_access("GET /orders 200").is_none());
+    }
+}
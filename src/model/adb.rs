@@ -0,0 +1,211 @@
+use color_eyre::Result;
+use std::process::{Command, Stdio};
+
+use crate::{LineStream, Resume, decode_line};
+
+/// Follows `adb logcat`, restarting it if the device drops off.
+///
+/// After a restart logcat resumes from the last line we saw rather than
+/// dumping its whole buffer again. Clearing the tab clears the device's
+/// buffers too, so what was cleared doesn't come back on a reconnect.
+pub(crate) fn adb_stream(device: Option<String>) -> Result<LineStream> {
+    let mut clear = adb(device.as_deref());
+    clear
+        .args(["logcat", "-c"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let stream = LineStream::spawn_resuming(AdbResume {
+        device,
+        last_seen: None,
+        replaying: None,
+    })?;
+    Ok(stream.clearing_with(clear))
+}
+
+/// Runs adb against the given device, or the only one connected.
+fn adb(device: Option<&str>) -> Command {
+    let mut command = Command::new("adb");
+    if let Some(device) = device {
+        command.args(["-s", device]);
+    }
+    command
+}
+
+/// The timestamp of the last lines taken, and how many there were with it.
+#[derive(Debug, Clone, PartialEq)]
+struct LastSeen {
+    stamp: String,
+    count: usize,
+}
+
+/// Resumes logcat after the last line seen.
+///
+/// `-T` sends the lines logged since a time, including the ones at that
+/// time, so a reconnect drops the lines up to and including the last one
+/// seen.
+struct AdbResume {
+    device: Option<String>,
+    last_seen: Option<LastSeen>,
+    /// The lines still being sent again this run.
+    replaying: Option<LastSeen>,
+}
+
+impl Resume for AdbResume {
+    fn command(&mut self) -> Command {
+        let mut command = adb(self.device.as_deref());
+        command.args(["logcat", "-v", "threadtime"]);
+        if let Some(seen) = &self.last_seen {
+            command.args(["-T", &seen.stamp]);
+        }
+        self.replaying = self.last_seen.clone();
+        command
+    }
+
+    fn take(&mut self, raw: &[u8]) -> Option<String> {
+        let line = decode_line(raw);
+        let Some(stamp) = timestamp(&line) else {
+            return format_line(&line);
+        };
+
+        if let Some(replayed) = &mut self.replaying {
+            // Within a year the timestamps sort as text.
+            if stamp < replayed.stamp {
+                return None;
+            }
+            if stamp == replayed.stamp && replayed.count > 0 {
+                replayed.count -= 1;
+                return None;
+            }
+            self.replaying = None;
+        }
+
+        match &mut self.last_seen {
+            Some(seen) if seen.stamp == stamp => seen.count += 1,
+            _ => self.last_seen = Some(LastSeen { stamp, count: 1 }),
+        }
+        format_line(&line)
+    }
+}
+
+/// Rewrites a threadtime line with its priority spelled out as one of the
+/// level names the filters look for.
+///
+/// `05-03 14:02:10.123  1234  5678 E Tag: msg` becomes
+/// `05-03 14:02:10.123 1234 5678 ERROR Tag: msg`.
+fn format_line(line: &str) -> Option<String> {
+    // Logcat marks where each ring buffer starts, which isn't a log line.
+    if line.starts_with("--------- beginning of") {
+        return None;
+    }
+
+    let Some((date, rest)) = next_token(line) else {
+        return Some(line.to_string());
+    };
+    let fields = next_token(rest).and_then(|(time, rest)| {
+        let (pid, rest) = next_token(rest)?;
+        let (tid, rest) = next_token(rest)?;
+        let (priority, message) = next_token(rest)?;
+        Some((time, pid, tid, priority_level(priority)?, message))
+    });
+
+    match fields {
+        Some((time, pid, tid, level, message)) => {
+            Some(format!("{date} {time} {pid} {tid} {level} {message}"))
+        }
+        None => Some(line.to_string()),
+    }
+}
+
+/// Splits off the first whitespace separated token, leaving the rest intact.
+fn next_token(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    if text.is_empty() {
+        return None;
+    }
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    Some((&text[..end], text[end..].trim_start()))
+}
+
+/// The `05-03 14:02:10.123` a threadtime line starts with, as `-T` takes it.
+fn timestamp(line: &str) -> Option<String> {
+    let (date, rest) = next_token(line)?;
+    let (time, _) = next_token(rest)?;
+    let is_date = date.len() == 5 && date.as_bytes()[2] == b'-';
+    (is_date && time.contains(':')).then(|| format!("{date} {time}"))
+}
+
+/// Maps a logcat priority letter onto the level names used by the filters.
+fn priority_level(priority: &str) -> Option<&'static str> {
+    match priority {
+        "V" | "D" => Some("DEBUG"),
+        "I" => Some("INFO"),
+        "W" => Some("WARNING"),
+        "E" => Some("ERROR"),
+        "F" | "A" => Some("CRITICAL"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take(resume: &mut AdbResume, lines: &[&str]) -> Vec<String> {
+        lines
+            .iter()
+            .filter_map(|line| resume.take(format!("{line}\n").as_bytes()))
+            .collect()
+    }
+
+    fn since(command: &Command) -> Option<String> {
+        let args: Vec<_> = command.get_args().filter_map(|arg| arg.to_str()).collect();
+        let at = args.iter().position(|arg| *arg == "-T")?;
+        Some(args[at + 1].to_string())
+    }
+
+    #[test]
+    fn formats_the_priority_as_a_level() {
+        assert_eq!(
+            format_line("05-03 14:02:10.123  1234  5678 E Tag: msg").as_deref(),
+            Some("05-03 14:02:10.123 1234 5678 ERROR Tag: msg")
+        );
+        assert_eq!(format_line("--------- beginning of main"), None);
+        assert_eq!(format_line("plain").as_deref(), Some("plain"));
+    }
+
+    #[test]
+    fn resumes_after_the_last_line_seen() {
+        let mut resume = AdbResume {
+            device: Some(String::from("emulator-5554")),
+            last_seen: None,
+            replaying: None,
+        };
+        assert_eq!(since(&resume.command()), None);
+        let first = [
+            "--------- beginning of main",
+            "05-03 14:02:10.123  1 2 I Tag: a",
+            "05-03 14:02:11.000  1 2 I Tag: b",
+            "05-03 14:02:11.000  1 2 I Tag: b",
+        ];
+        assert_eq!(take(&mut resume, &first).len(), 3);
+
+        assert_eq!(
+            since(&resume.command()).as_deref(),
+            Some("05-03 14:02:11.000")
+        );
+        let again = [
+            "--------- beginning of main",
+            "05-03 14:02:11.000  1 2 I Tag: b",
+            "05-03 14:02:11.000  1 2 I Tag: b",
+            "05-03 14:02:11.000  1 2 W Tag: c",
+            "05-03 14:02:12.500  1 2 E Tag: d",
+        ];
+        assert_eq!(
+            take(&mut resume, &again),
+            [
+                "05-03 14:02:11.000 1 2 WARNING Tag: c",
+                "05-03 14:02:12.500 1 2 ERROR Tag: d",
+            ]
+        );
+    }
+}
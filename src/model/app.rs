@@ -0,0 +1,511 @@
+use color_eyre::Result;
+use std::{path::PathBuf, slice};
+
+use crate::{
+    CaseMode, Config, Context, FilePicker, Fuzzy, GapThreshold, History, Input, Matcher, MergeMode,
+    Message, Model, ParseOptions, Preset, Prompt, RunningState, SearchMode, SearchStyle, TabSearch,
+    adb_stream, close_prompt, docker_stream, eventlog_stream, fifo_stream, http_stream,
+    is_stream_file, journal_stream, kube_stream, otlp_stream, s3_stream, serial_stream, ssh_stream,
+    stdin_stream, syslog_stream, tcp_stream, update,
+};
+
+/// Top level state holding one `Model` per opened file.
+#[derive(Debug, Default)]
+pub(crate) struct App {
+    pub(crate) tabs: Vec<Model>,
+    pub(crate) active_tab: usize,
+    pub(crate) running: RunningState,
+    /// Set when a directory was given, to choose files from it.
+    pub(crate) picker: Option<FilePicker>,
+    /// Whether the picker is drawn over the tabs and gets the keys.
+    pub(crate) show_picker: bool,
+    /// Passed on to files opened from the picker.
+    parse_options: ParseOptions,
+    /// Saved filters, from the config file.
+    pub(crate) presets: Vec<Preset>,
+    /// The config file presets are saved to.
+    config_path: Option<PathBuf>,
+    /// The selected preset while the preset menu is open.
+    pub(crate) preset_menu: Option<usize>,
+    /// The lines found by searching every tab, listed over the tabs until
+    /// one is picked.
+    pub(crate) tab_search: Option<TabSearch>,
+    /// Searches and filters typed in before, shared by the tabs.
+    history: History,
+    /// Whether case counts when matching, the same for every tab.
+    case: CaseMode,
+    /// The context tabs start out with.
+    context: Context,
+    /// How tabs start out matching fuzzy searches.
+    fuzzy: Fuzzy,
+    /// What searches in new tabs do once they're kept.
+    search_style: SearchStyle,
+    /// How long the log goes quiet for a gap in new tabs.
+    gap_threshold: GapThreshold,
+    /// How many rows new tabs keep between the selection and the edges.
+    scrolloff: usize,
+    /// Whether files open as if for the first time, rather than where they
+    /// were left.
+    fresh: bool,
+}
+
+impl App {
+    pub(crate) fn new(config: Config) -> Result<App> {
+        let mut tabs = vec![];
+        let mut merged_paths = vec![];
+        let mut picker = None;
+        let options = &config.parse_options;
+
+        for input in config.inputs {
+            match input {
+                // Pipes never end, so they're streamed and can't be merged.
+                Input::Files(paths) if paths.len() == 1 && is_stream_file(&paths[0]) => {
+                    let stream = fifo_stream(&paths[0]);
+                    tabs.push(Model::from_stream(paths[0].clone(), stream, options)?);
+                }
+                Input::Files(paths) if config.merge => merged_paths.extend(paths),
+                Input::Files(paths) => {
+                    tabs.push(Model::new(&paths, MergeMode::Concatenate, options)?);
+                }
+                Input::Journal(unit) => {
+                    let name = match &unit {
+                        Some(unit) => format!("journal:{unit}"),
+                        None => String::from("journal"),
+                    };
+                    tabs.push(Model::from_stream(
+                        name,
+                        journal_stream(unit.as_deref())?,
+                        options,
+                    )?);
+                }
+                Input::Docker(container) => {
+                    let stream = docker_stream(&container)?;
+                    tabs.push(Model::from_stream(
+                        format!("docker:{container}"),
+                        stream,
+                        options,
+                    )?);
+                }
+                Input::Kube(target) => {
+                    let name = target.name();
+                    tabs.push(Model::from_stream(name, kube_stream(target)?, options)?);
+                }
+                Input::Ssh(target) => {
+                    let name = target.name();
+                    tabs.push(Model::from_stream(name, ssh_stream(target)?, options)?);
+                }
+                Input::Http(url) => {
+                    let stream = http_stream(&url)?;
+                    tabs.push(Model::from_stream(url, stream, options)?);
+                }
+                Input::Syslog(transport, addr) => {
+                    let stream = syslog_stream(&transport, &addr)?;
+                    tabs.push(Model::from_stream(
+                        format!("syslog:{addr}"),
+                        stream,
+                        options,
+                    )?);
+                }
+                Input::EventLog(channel) => {
+                    let stream = eventlog_stream(&channel)?;
+                    tabs.push(Model::from_stream(
+                        format!("eventlog:{channel}"),
+                        stream,
+                        options,
+                    )?);
+                }
+                Input::S3(object) => {
+                    let name = object.uri();
+                    tabs.push(Model::from_stream(name, s3_stream(object), options)?);
+                }
+                Input::Serial(target) => {
+                    let mut model =
+                        Model::from_stream(target.name(), serial_stream(&target)?, options)?;
+                    // A device is watched live, so start out following it.
+                    model.follow = true;
+                    tabs.push(model);
+                }
+                Input::Adb(device) => {
+                    let name = match &device {
+                        Some(device) => format!("adb:{device}"),
+                        None => String::from("adb"),
+                    };
+                    tabs.push(Model::from_stream(name, adb_stream(device)?, options)?);
+                }
+                Input::Stdin => {
+                    tabs.push(Model::from_stream(
+                        String::from("stdin"),
+                        stdin_stream(),
+                        options,
+                    )?);
+                }
+                Input::Source(source) => tabs.push(Model::from_source(source, options)?),
+                Input::Directory(dir) => picker = Some(FilePicker::new(&dir)?),
+                Input::Otlp(addr) => {
+                    let stream = otlp_stream(&addr)?;
+                    tabs.push(Model::from_stream(format!("otlp:{addr}"), stream, options)?);
+                }
+                Input::Tcp(addr) => {
+                    let stream = tcp_stream(&addr)?;
+                    tabs.push(Model::from_stream(format!("tcp:{addr}"), stream, options)?);
+                }
+            }
+        }
+
+        if !merged_paths.is_empty() {
+            tabs.insert(
+                0,
+                Model::new(&merged_paths, MergeMode::Chronological, options)?,
+            );
+        }
+
+        for tab in &mut tabs {
+            tab.case = config.case;
+            tab.fuzzy = config.fuzzy;
+            tab.search_style = config.search_style;
+            tab.gap_threshold = config.gap_threshold;
+            tab.scrolloff = config.scrolloff;
+            tab.context = config.context;
+            if !config.fresh {
+                tab.restore_saved_position();
+            }
+            if let Some(preset) = &config.preset {
+                preset.apply(tab);
+            }
+        }
+
+        Ok(App {
+            tabs,
+            active_tab: 0,
+            running: RunningState::default(),
+            show_picker: picker.is_some(),
+            picker,
+            parse_options: config.parse_options,
+            presets: config.presets,
+            config_path: config.config_path,
+            preset_menu: None,
+            tab_search: None,
+            history: History::load(),
+            case: config.case,
+            fuzzy: config.fuzzy,
+            search_style: config.search_style,
+            gap_threshold: config.gap_threshold,
+            scrolloff: config.scrolloff,
+            fresh: config.fresh,
+            context: config.context,
+        })
+    }
+
+    pub(crate) fn model_mut(&mut self) -> &mut Model {
+        &mut self.tabs[self.active_tab]
+    }
+
+    /// Saves where each file was left, to open it there next time.
+    pub(crate) fn save_positions(&self) {
+        for tab in &self.tabs {
+            if let Some(position) = tab.saved_position() {
+                position.save(&tab.log_paths[0]);
+            }
+        }
+    }
+
+    fn select_tab(&mut self, idx: usize) {
+        // Nothing is open yet when the app starts out in the picker.
+        if let Some(model) = self.tabs.get_mut(self.active_tab) {
+            model.clear_pending_keys();
+        }
+        self.active_tab = idx;
+    }
+
+    /// Opens the file selected in the picker in a new tab, or switches to it
+    /// if it's already open.
+    fn open_picked(&mut self) {
+        let Some(picker) = self.picker.as_mut() else {
+            return;
+        };
+        let Some(path) = picker.selected_path().map(String::from) else {
+            return;
+        };
+
+        if let Some(idx) = self
+            .tabs
+            .iter()
+            .position(|tab| tab.log_paths == [path.as_str()])
+        {
+            self.select_tab(idx);
+            self.show_picker = false;
+            return;
+        }
+
+        match Model::new(
+            slice::from_ref(&path),
+            MergeMode::Concatenate,
+            &self.parse_options,
+        ) {
+            Ok(mut model) => {
+                model.case = self.case;
+                model.fuzzy = self.fuzzy;
+                model.search_style = self.search_style;
+                model.gap_threshold = self.gap_threshold;
+                model.scrolloff = self.scrolloff;
+                model.context = self.context;
+                if !self.fresh {
+                    model.restore_saved_position();
+                }
+                self.tabs.push(model);
+                self.select_tab(self.tabs.len() - 1);
+                self.show_picker = false;
+            }
+            Err(error) => picker.error = Some(format!("couldn't open {path}: {error}")),
+        }
+    }
+}
+
+/// Handles the messages sent while the preset menu is shown.
+fn update_presets(app: &mut App, selected: usize, msg: Message) -> Option<Message> {
+    match msg {
+        Message::MoveUp => app.preset_menu = Some(selected.saturating_sub(1)),
+        Message::MoveDown => {
+            let last = app.presets.len().saturating_sub(1);
+            app.preset_menu = Some((selected + 1).min(last));
+        }
+        Message::PickPreset => {
+            app.preset_menu = None;
+            if let Some(preset) = app.presets.get(selected) {
+                preset.apply(&mut app.tabs[app.active_tab]);
+            }
+        }
+        Message::ClosePresets => app.preset_menu = None,
+        // Saving asks for a name in the prompt, outside of the menu.
+        msg @ Message::OpenPrompt(Prompt::PresetName) => {
+            app.preset_menu = None;
+            return update(app.model_mut(), msg);
+        }
+        // The log keeps refreshing behind the menu.
+        msg @ Message::RefreshLogs => return update(app.model_mut(), msg),
+        Message::Quit => app.running = RunningState::Done,
+        _ => {}
+    }
+    None
+}
+
+/// Saves the active tab's filters as a preset. Problems are shown in the
+/// prompt the name was typed into, which stays open.
+fn save_preset(app: &mut App, name: String) {
+    let error = if name.is_empty() {
+        Some(String::from("a preset needs a name"))
+    } else if app.presets.iter().any(|preset| preset.name == name) {
+        Some(String::from("a preset by that name exists"))
+    } else if let Some(path) = &app.config_path {
+        let preset = Preset::from_model(&name, &app.tabs[app.active_tab]);
+        match preset.save(path) {
+            Ok(()) => {
+                app.presets.push(preset);
+                None
+            }
+            Err(error) => Some(format!("couldn't save to {}: {error}", path.display())),
+        }
+    } else {
+        Some(String::from("no config directory to save to"))
+    };
+
+    let model = app.model_mut();
+    match error {
+        Some(error) => model.prompt_error = Some(error),
+        None => close_prompt(model),
+    }
+}
+
+/// Searches every tab for what was typed into the active tab's prompt, the
+/// way the active tab searches. The prompt stays open with an error if the
+/// search can't be matched.
+fn search_tabs(app: &mut App, search: String) {
+    let model = &mut app.tabs[app.active_tab];
+    if search.is_empty() {
+        model.prompt_error = Some(String::from("type something to search for"));
+        return;
+    }
+    let date = model.start_date();
+    match Matcher::new(&search, model.search_kind, app.case, model.fuzzy, date) {
+        Ok(matcher) => {
+            close_prompt(model);
+            app.history.add("tabs", &search);
+            app.tab_search = Some(TabSearch::run(&search, &matcher, &mut app.tabs));
+        }
+        Err(error) => model.prompt_error = Some(error),
+    }
+}
+
+/// Handles the messages sent while the lines found in every tab are listed.
+fn update_tab_search(app: &mut App, msg: Message) -> Option<Message> {
+    let search = app.tab_search.as_mut()?;
+    match msg {
+        Message::MoveUp => search.move_up(),
+        Message::MoveDown => search.move_down(),
+        Message::MoveTop => search.move_top(),
+        Message::MoveBottom => search.move_bottom(),
+        Message::OpenTabMatch => {
+            if let Some(found) = search.selected() {
+                let (tab, source, number) = (found.tab, found.entry.source, found.entry.number);
+                app.tab_search = None;
+                // The tabs share the screen, so one that's never been drawn
+                // goes by the height of the one that was.
+                let height = app.model_mut().view_height();
+                app.select_tab(tab);
+                let model = app.model_mut();
+                if model.view_height() == 0 {
+                    model.set_view_height(height);
+                }
+                model.select_line(source, number);
+            }
+        }
+        Message::CloseTabSearch => app.tab_search = None,
+        // The active tab's logs keep refreshing behind the list.
+        msg @ Message::RefreshLogs => return update(app.model_mut(), msg),
+        Message::Quit => app.running = RunningState::Done,
+        _ => {}
+    }
+    None
+}
+
+/// Handles the messages sent while the picker is shown.
+fn update_picker(app: &mut App, msg: Message) -> Option<Message> {
+    let picker = app.picker.as_mut()?;
+    match msg {
+        Message::MoveUp => picker.move_up(),
+        Message::MoveDown => picker.move_down(),
+        Message::MoveTop => picker.move_top(),
+        Message::MoveBottom => picker.move_bottom(),
+        Message::OpenPicked => app.open_picked(),
+        // There's nothing to go back to before a file has been opened.
+        Message::ClosePicker if app.tabs.is_empty() => return Some(Message::Quit),
+        Message::ClosePicker => app.show_picker = false,
+        Message::Quit => app.running = RunningState::Done,
+        _ => {}
+    }
+    None
+}
+
+/// Handles the messages that concern the whole app and passes everything else
+/// on to the model of the active tab.
+pub(crate) fn update_app(app: &mut App, msg: Message) -> Option<Message> {
+    if app.show_picker {
+        return update_picker(app, msg);
+    }
+    if let Some(selected) = app.preset_menu {
+        return update_presets(app, selected, msg);
+    }
+    if app.tab_search.is_some() {
+        return update_tab_search(app, msg);
+    }
+
+    // A `g` or a count typed before a key the app handles itself would
+    // otherwise be left waiting for the next key in the tab.
+    let consumed = matches!(
+        msg,
+        Message::Quit
+            | Message::NextTab
+            | Message::PrevTab
+            | Message::DuplicateTab
+            | Message::OpenPresets
+            | Message::HistoryOlder
+            | Message::HistoryNewer
+            | Message::HistoryMatching
+            | Message::ToggleCase
+            | Message::SavePreset(_)
+            | Message::SearchTabs(_)
+            | Message::OpenPicker
+    );
+    if consumed && !app.tabs.is_empty() {
+        app.model_mut().clear_pending_keys();
+    }
+
+    match msg {
+        Message::Quit => {
+            app.running = RunningState::Done;
+            None
+        }
+        Message::NextTab => {
+            app.select_tab((app.active_tab + 1) % app.tabs.len());
+            None
+        }
+        Message::PrevTab => {
+            app.select_tab((app.active_tab + app.tabs.len() - 1) % app.tabs.len());
+            None
+        }
+        Message::DuplicateTab => {
+            if let Some(Ok(model)) = app.tabs[app.active_tab].reopen() {
+                app.tabs.insert(app.active_tab + 1, model);
+                app.select_tab(app.active_tab + 1);
+            }
+            None
+        }
+        Message::OpenPresets => {
+            app.preset_menu = Some(0);
+            None
+        }
+        Message::HistoryOlder | Message::HistoryNewer => {
+            let older = matches!(msg, Message::HistoryOlder);
+            app.tabs[app.active_tab].recall(&app.history, older);
+            None
+        }
+        Message::HistoryMatching => {
+            app.tabs[app.active_tab].recall_matching(&app.history);
+            None
+        }
+        // What's typed is remembered once it's applied, which closes it.
+        Message::SubmitPrompt
+        | Message::ToggleSearch
+        | Message::SearchBackward
+        | Message::SubmitSearch
+        | Message::NarrowSearch => {
+            let model = app.model_mut();
+            let typed = model
+                .history_kind()
+                .map(|kind| (kind, model.input().to_string()));
+            let next = update(model, msg);
+            if let Some((kind, text)) = typed
+                && app.model_mut().search_mode == SearchMode::None
+            {
+                app.history.add(kind, &text);
+            }
+            next
+        }
+        // The word is remembered as if it had been typed into the search.
+        Message::SearchWord(_) => {
+            let model = &mut app.tabs[app.active_tab];
+            let search = model.search_input.clone();
+            let next = update(model, msg);
+            if model.search_input != search {
+                app.history.add("search", &model.search_input);
+            }
+            next
+        }
+        Message::ToggleCase => {
+            app.case = app.case.next();
+            for tab in &mut app.tabs {
+                tab.case = app.case;
+                tab.reset_view();
+            }
+            None
+        }
+        Message::SavePreset(name) => {
+            save_preset(app, name);
+            None
+        }
+        Message::SearchTabs(search) => {
+            search_tabs(app, search);
+            None
+        }
+        Message::OpenPicker => {
+            if let Some(picker) = app.picker.as_mut() {
+                if let Err(error) = picker.refresh() {
+                    picker.error = Some(format!("couldn't list {}: {error}", picker.dir));
+                }
+                app.show_picker = true;
+            }
+            None
+        }
+        msg => update(app.model_mut(), msg),
+    }
+}
@@ -0,0 +1,75 @@
+use regex::{Regex, RegexBuilder};
+
+/// Whether letter case counts when the search and the plain text filters,
+/// the regex, exclude and quick filters, are matched against lines.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CaseMode {
+    /// Ignore case unless what's typed has a capital letter in it, so
+    /// `error` finds `ERROR` but `ERROR` only finds itself.
+    #[default]
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+impl CaseMode {
+    /// Reads the mode as it's written in the config file or on the command
+    /// line.
+    pub(crate) fn from_name(name: &str) -> Option<CaseMode> {
+        match name {
+            "smart" => Some(CaseMode::Smart),
+            "sensitive" => Some(CaseMode::Sensitive),
+            "insensitive" => Some(CaseMode::Insensitive),
+            _ => None,
+        }
+    }
+
+    /// The mode after this one, to toggle through them.
+    pub(crate) fn next(self) -> CaseMode {
+        match self {
+            CaseMode::Smart => CaseMode::Sensitive,
+            CaseMode::Sensitive => CaseMode::Insensitive,
+            CaseMode::Insensitive => CaseMode::Smart,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            CaseMode::Smart => "smart case",
+            CaseMode::Sensitive => "match case",
+            CaseMode::Insensitive => "ignore case",
+        }
+    }
+
+    /// Whether case is ignored when matching what was typed.
+    pub(crate) fn ignores_case(self, typed: &str) -> bool {
+        match self {
+            CaseMode::Sensitive => false,
+            CaseMode::Insensitive => true,
+            // Escapes like `\W` in a regex aren't capitals someone typed.
+            CaseMode::Smart => !typed.split('\\').enumerate().any(|(i, part)| {
+                part.chars()
+                    .skip(usize::from(i > 0))
+                    .any(char::is_uppercase)
+            }),
+        }
+    }
+
+    /// The regex as it was typed, ignoring case when the mode says to.
+    pub(crate) fn regex(self, regex: &Regex) -> Regex {
+        match self.ignores_case(regex.as_str()) {
+            true => RegexBuilder::new(regex.as_str())
+                .case_insensitive(true)
+                .build()
+                .unwrap_or_else(|_| regex.clone()),
+            false => regex.clone(),
+        }
+    }
+
+    pub(crate) fn contains(self, line: &str, word: &str) -> bool {
+        match self.ignores_case(word) {
+            true => line.to_lowercase().contains(&word.to_lowercase()),
+            false => line.contains(word),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use crate::StructuredLine;
+
+/// Parses a line in ArcSight's Common Event Format, optionally behind a syslog
+/// header:
+///
+/// ```text
+/// CEF:0|Vendor|Product|1.0|100|Port scan|7|src=10.0.0.1 msg=Scan detected
+/// ```
+pub(crate) fn parse_cef(line: &str) -> Option<StructuredLine> {
+    let start = line.find("CEF:")?;
+    let header = split_header(&line[start + "CEF:".len()..], 7)?;
+    let [
+        _,
+        vendor,
+        product,
+        version,
+        signature,
+        name,
+        severity,
+        extension,
+    ] = header.as_slice()
+    else {
+        return None;
+    };
+
+    let extension = parse_extension(extension);
+    let mut fields = vec![
+        ("deviceVendor".to_string(), vendor.clone()),
+        ("deviceProduct".to_string(), product.clone()),
+        ("deviceVersion".to_string(), version.clone()),
+        ("signatureId".to_string(), signature.clone()),
+        ("severity".to_string(), severity.clone()),
+    ];
+    fields.extend(extension);
+
+    Some(StructuredLine {
+        timestamp: event_time(&fields, &line[..start]),
+        level: severity_level(severity).map(String::from),
+        message: Some(name.clone()),
+        fields,
+    })
+}
+
+/// Parses a line in IBM QRadar's Log Event Extended Format, optionally behind
+/// a syslog header. LEEF 1.0 separates attributes with tabs, 2.0 names its
+/// delimiter in the header:
+///
+/// ```text
+/// LEEF:2.0|Vendor|Product|1.0|Login|^|src=10.0.0.1^sev=5^usrName=bob
+/// ```
+pub(crate) fn parse_leef(line: &str) -> Option<StructuredLine> {
+    let start = line.find("LEEF:")?;
+    let rest = &line[start + "LEEF:".len()..];
+    let version = rest.split('|').next()?;
+
+    let (header, delimiter) = if version.starts_with('2') {
+        let header = split_header(rest, 6)?;
+        let delimiter = leef_delimiter(&header[5])?;
+        (header, delimiter)
+    } else {
+        (split_header(rest, 5)?, '\t')
+    };
+    let [_, vendor, product, product_version, event_id, ..] = header.as_slice() else {
+        return None;
+    };
+    let attributes = header.last()?;
+
+    let mut fields = vec![
+        ("deviceVendor".to_string(), vendor.clone()),
+        ("deviceProduct".to_string(), product.clone()),
+        ("deviceVersion".to_string(), product_version.clone()),
+        ("eventId".to_string(), event_id.clone()),
+    ];
+    fields.extend(
+        attributes
+            .split(delimiter)
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.to_string())),
+    );
+
+    let severity = fields
+        .iter()
+        .find(|(key, _)| key == "sev")
+        .and_then(|(_, sev)| severity_level(sev));
+    Some(StructuredLine {
+        timestamp: event_time(&fields, &line[..start]),
+        level: severity.map(String::from),
+        message: Some(event_id.clone()),
+        fields,
+    })
+}
+
+/// Splits the `|` separated header into its fields and what follows them,
+/// honouring `\|` escapes.
+fn split_header(text: &str, fields: usize) -> Option<Vec<String>> {
+    let mut parts = vec![];
+    let mut part = String::new();
+    let mut chars = text.char_indices();
+
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => part.extend(chars.next().map(|(_, escaped)| escaped)),
+            '|' => {
+                parts.push(std::mem::take(&mut part));
+                if parts.len() == fields {
+                    parts.push(text[idx + 1..].to_string());
+                    return Some(parts);
+                }
+            }
+            c => part.push(c),
+        }
+    }
+    None
+}
+
+/// Splits CEF's extension into its pairs. Values can hold spaces, so a value
+/// runs until the next ` key=`.
+fn parse_extension(extension: &str) -> Vec<(String, String)> {
+    let mut starts = vec![];
+    let bytes = extension.as_bytes();
+    for (idx, _) in extension.match_indices('=') {
+        if idx > 0 && bytes[idx - 1] == b'\\' {
+            continue;
+        }
+        let key_start = extension[..idx].rfind(' ').map_or(0, |space| space + 1);
+        starts.push((key_start, idx));
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, &(key_start, eq))| {
+            let value_end = starts
+                .get(n + 1)
+                .map_or(extension.len(), |&(next, _)| next.saturating_sub(1));
+            let value = extension[eq + 1..value_end.max(eq + 1)]
+                .replace("\\=", "=")
+                .replace("\\\\", "\\");
+            (extension[key_start..eq].to_string(), value)
+        })
+        .collect()
+}
+
+/// LEEF 2.0 names its delimiter as a character or as hex, like `x5E`.
+fn leef_delimiter(text: &str) -> Option<char> {
+    match text.strip_prefix('x').or(text.strip_prefix("0x")) {
+        Some(hex) if !hex.is_empty() => char::from_u32(u32::from_str_radix(hex, 16).ok()?),
+        _ => text.chars().next(),
+    }
+}
+
+/// Takes the time from the event's own fields, or else from the syslog header
+/// in front of it.
+fn event_time(fields: &[(String, String)], prefix: &str) -> Option<String> {
+    fields
+        .iter()
+        .find(|(key, _)| key == "rt" || key == "devTime")
+        .map(|(_, time)| time.clone())
+        .or_else(|| Some(prefix.trim().to_string()).filter(|prefix| !prefix.is_empty()))
+}
+
+/// Maps a 0 to 10 severity, or the names CEF allows instead, onto the level
+/// names used by the filters.
+fn severity_level(severity: &str) -> Option<&'static str> {
+    match severity.trim().to_ascii_lowercase().as_str() {
+        "low" => Some("INFO"),
+        "medium" => Some("WARNING"),
+        "high" => Some("ERROR"),
+        "very-high" | "very high" => Some("CRITICAL"),
+        number => match number.parse::<u8>().ok()? {
+            0..=3 => Some("INFO"),
+            4..=6 => Some("WARNING"),
+            7 | 8 => Some("ERROR"),
+            _ => Some("CRITICAL"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field<'a>(line: &'a StructuredLine, key: &str) -> Option<&'a str> {
+        line.fields
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn parses_a_cef_line_behind_a_syslog_header() {
+        let line = parse_cef(concat!(
+            r"May  3 14:02:10 fw01 CEF:0|Acme|Fire\|wall|1.0|100|Port scan|7|",
+            r"src=10.0.0.1 msg=Scan detected on eth0 cs1=a\=b",
+        ))
+        .unwrap();
+        assert_eq!(line.timestamp.as_deref(), Some("May  3 14:02:10 fw01"));
+        assert_eq!(line.level.as_deref(), Some("ERROR"));
+        assert_eq!(line.message.as_deref(), Some("Port scan"));
+        assert_eq!(field(&line, "deviceProduct"), Some("Fire|wall"));
+        assert_eq!(field(&line, "src"), Some("10.0.0.1"));
+        assert_eq!(field(&line, "msg"), Some("Scan detected on eth0"));
+        assert_eq!(field(&line, "cs1"), Some("a=b"));
+    }
+
+    #[test]
+    fn takes_the_time_and_named_severity_of_the_event() {
+        let line =
+            parse_cef("CEF:0|Acme|IDS|2|7|Login|High|rt=May 03 2024 14:02:10 suser=bob").unwrap();
+        assert_eq!(line.timestamp.as_deref(), Some("May 03 2024 14:02:10"));
+        assert_eq!(line.level.as_deref(), Some("ERROR"));
+        assert!(parse_cef("CEF:0|Acme|IDS|2").is_none());
+    }
+
+    #[test]
+    fn parses_leef_lines() {
+        let line =
+            parse_leef("LEEF:2.0|Acme|Auth|1.0|Login|^|src=10.0.0.1^sev=5^usrName=bob").unwrap();
+        assert_eq!(line.message.as_deref(), Some("Login"));
+        assert_eq!(line.level.as_deref(), Some("WARNING"));
+        assert_eq!(field(&line, "usrName"), Some("bob"));
+
+        let line = parse_leef("LEEF:2.0|Acme|Auth|1.0|Login|x7C|sev=9|devTime=14:02:10").unwrap();
+        assert_eq!(line.level.as_deref(), Some("CRITICAL"));
+        assert_eq!(line.timestamp.as_deref(), Some("14:02:10"));
+
+        let line = parse_leef("LEEF:1.0|Acme|Auth|1.0|Logout|src=10.0.0.1\tusrName=bob").unwrap();
+        assert_eq!(field(&line, "usrName"), Some("bob"));
+        assert_eq!(line.level, None);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{Context, FilterExpr};
+
+/// What can be typed into the `:` command bar.
+#[derive(Debug)]
+pub(crate) enum Command {
+    /// Filters on a query, like
+    /// `:q level>=ERROR and ts>14:00 and msg contains "deadlock"`, or stops
+    /// filtering on one when it's left empty.
+    Query(Option<FilterExpr>),
+    /// Shows lines around the ones the filters let through, like
+    /// `:context 3`, or `:context 2 5` for 2 before and 5 after.
+    Context(Context),
+    /// Moves to a line of the file, like `:15234`, to a share of the way
+    /// through it, like `:50%`, or to a time, like `:ts 14:02`.
+    Goto(Goto),
+    /// Lists the marked lines, with `:marks`.
+    Marks,
+}
+
+/// Where in the file `:` moves to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Goto {
+    /// The number of the line, counting from 1.
+    Line(usize),
+    /// How far through the file, from 0 to 100.
+    Percent(usize),
+    /// The first line logged at or after the time, as it was typed, since
+    /// a time without a date is on the day the log starts.
+    Time(String),
+}
+
+impl Goto {
+    fn parse(text: &str) -> Result<Goto, String> {
+        let invalid = || format!("expected a line number or a percentage, not {text:?}");
+        match text.strip_suffix('%') {
+            Some(percent) => match percent.parse().map_err(|_| invalid())? {
+                percent @ 0..=100 => Ok(Goto::Percent(percent)),
+                _ => Err(String::from("a percentage goes up to 100")),
+            },
+            None => text.parse().map(Goto::Line).map_err(|_| invalid()),
+        }
+    }
+}
+
+impl Command {
+    pub(crate) fn parse(text: &str) -> Result<Command, String> {
+        let text = text.trim();
+        let (name, args) = text
+            .split_once(char::is_whitespace)
+            .map_or((text, ""), |(name, args)| (name, args.trim()));
+
+        match name {
+            "q" | "query" if args.is_empty() => Ok(Command::Query(None)),
+            "q" | "query" => Ok(Command::Query(Some(FilterExpr::parse(args)?))),
+            "context" => Ok(Command::Context(Context::parse(args)?)),
+            "marks" => Ok(Command::Marks),
+            "ts" if args.is_empty() => Err(String::from("expected a time, like 14:02")),
+            "ts" => Ok(Command::Goto(Goto::Time(args.to_string()))),
+            name if name.starts_with(|c: char| c.is_ascii_digit()) => {
+                Ok(Command::Goto(Goto::parse(text)?))
+            }
+            "" => Err(String::from("type a command")),
+            name => Err(format!(
+                "unknown command {name:?}, try q, context, ts, marks or a line number"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_queries() {
+        assert!(matches!(Command::parse("q"), Ok(Command::Query(None))));
+        assert!(matches!(
+            Command::parse(" query level>=ERROR and msg contains \"deadlock\""),
+            Ok(Command::Query(Some(query))) if query.as_str() == "level>=ERROR and msg contains \"deadlock\""
+        ));
+    }
+
+    fn goto(text: &str) -> Goto {
+        match Command::parse(text) {
+            Ok(Command::Goto(goto)) => goto,
+            other => panic!("{text:?} parsed as {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_line_numbers_and_percentages() {
+        assert_eq!(goto(" 15234 "), Goto::Line(15234));
+        assert_eq!(goto("50%"), Goto::Percent(50));
+        assert_eq!(goto("100%"), Goto::Percent(100));
+        assert_eq!(
+            Goto::parse("101%"),
+            Err(String::from("a percentage goes up to 100"))
+        );
+        assert_eq!(
+            Goto::parse("12ab"),
+            Err(String::from(
+                "expected a line number or a percentage, not \"12ab\""
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_a_time_to_go_to() {
+        assert_eq!(goto("ts  14:02"), Goto::Time(String::from("14:02")));
+        assert_eq!(
+            Command::parse("ts").unwrap_err(),
+            "expected a time, like 14:02"
+        );
+    }
+
+    #[test]
+    fn parses_the_context_around_matches() {
+        assert!(matches!(
+            Command::parse("context 2 5"),
+            Ok(Command::Context(Context {
+                before: 2,
+                after: 5
+            }))
+        ));
+        assert_eq!(
+            Command::parse("context 1 2 3").unwrap_err(),
+            "expected the lines before and after"
+        );
+    }
+
+    #[test]
+    fn parses_listing_the_marks() {
+        assert!(matches!(Command::parse("marks"), Ok(Command::Marks)));
+        assert!(matches!(Command::parse(" marks "), Ok(Command::Marks)));
+    }
+
+    #[test]
+    fn reports_bad_commands() {
+        let error = |text| Command::parse(text).unwrap_err();
+        assert_eq!(error("  "), "type a command");
+        assert_eq!(error("q a &&"), "unexpected end");
+        assert_eq!(
+            error("quit"),
+            "unknown command \"quit\", try q, context, ts, marks or a line number"
+        );
+    }
+}
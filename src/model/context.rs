@@ -0,0 +1,141 @@
+use crate::LogEntry;
+
+/// How many lines before and after each line the filters let through are
+/// shown along with it, like grep's `-B` and `-A`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Context {
+    pub(crate) before: usize,
+    pub(crate) after: usize,
+}
+
+impl Context {
+    /// Reads `N` for as many lines on both sides, or `B A` for each side.
+    /// Nothing at all turns it off.
+    pub(crate) fn parse(text: &str) -> Result<Context, String> {
+        let counts = text
+            .split_whitespace()
+            .map(|count| {
+                count
+                    .parse()
+                    .map_err(|_| format!("expected a number of lines, not {count:?}"))
+            })
+            .collect::<Result<Vec<usize>, String>>()?;
+        match counts[..] {
+            [] => Ok(Context::default()),
+            [lines] => Ok(Context {
+                before: lines,
+                after: lines,
+            }),
+            [before, after] => Ok(Context { before, after }),
+            _ => Err(String::from("expected the lines before and after")),
+        }
+    }
+
+    pub(crate) fn is_empty(self) -> bool {
+        self.before == 0 && self.after == 0
+    }
+
+    /// Describes the context for the title of the logs.
+    pub(crate) fn describe(self) -> Option<String> {
+        match (self.before, self.after) {
+            (0, 0) => None,
+            (before, after) if before == after => Some(format!("context -C{before}")),
+            (before, after) => Some(format!("context -B{before} -A{after}")),
+        }
+    }
+
+    /// Takes the lines at the matched positions of the logs, in order, along
+    /// with the ones around them marked as context.
+    pub(crate) fn around(self, logs: &[LogEntry], matched: &[usize]) -> Vec<LogEntry> {
+        let mut shown = vec![];
+        // Past the last line taken, so overlapping context isn't taken twice.
+        let mut next = 0;
+        for (i, &idx) in matched.iter().enumerate() {
+            let start = idx.saturating_sub(self.before).max(next);
+            shown.extend(logs[start..idx].iter().map(as_context));
+            shown.push(logs[idx].clone());
+
+            let upcoming = matched.get(i + 1).copied().unwrap_or(logs.len());
+            let end = (idx + 1 + self.after).min(upcoming).min(logs.len());
+            shown.extend(logs[idx + 1..end].iter().map(as_context));
+            next = end;
+        }
+        shown
+    }
+}
+
+fn as_context(entry: &LogEntry) -> LogEntry {
+    LogEntry {
+        context: true,
+        ..entry.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shown(context: Context, matched: &[usize]) -> Vec<String> {
+        let logs: Vec<LogEntry> = (0..10)
+            .map(|number| LogEntry {
+                number,
+                ..LogEntry::new(format!("line {number}"), 0)
+            })
+            .collect();
+        context
+            .around(&logs, matched)
+            .iter()
+            .map(|entry| match entry.context {
+                true => format!("-{}", entry.number),
+                false => format!("{}", entry.number),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_the_lines_on_each_side() {
+        assert_eq!(
+            Context::parse("2"),
+            Ok(Context {
+                before: 2,
+                after: 2
+            })
+        );
+        assert_eq!(
+            Context::parse("1 3"),
+            Ok(Context {
+                before: 1,
+                after: 3
+            })
+        );
+        assert!(Context::parse("").unwrap().is_empty());
+        assert!(Context::parse("a").is_err());
+        assert!(Context::parse("1 2 3").is_err());
+        assert_eq!(
+            Context::parse("1 3").unwrap().describe().as_deref(),
+            Some("context -B1 -A3")
+        );
+    }
+
+    #[test]
+    fn shows_the_lines_around_each_match() {
+        let context = Context {
+            before: 1,
+            after: 2,
+        };
+        assert_eq!(shown(context, &[3]), ["-2", "3", "-4", "-5"]);
+        assert_eq!(shown(context, &[0, 9]), ["0", "-1", "-2", "-8", "9"]);
+    }
+
+    #[test]
+    fn takes_overlapping_context_once() {
+        let context = Context {
+            before: 2,
+            after: 2,
+        };
+        assert_eq!(
+            shown(context, &[2, 4, 5]),
+            ["-0", "-1", "2", "-3", "4", "5", "-6", "-7"]
+        );
+    }
+}
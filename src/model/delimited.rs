@@ -0,0 +1,144 @@
+use std::path::Path;
+
+use crate::{LEVEL_KEYS, LogEntry, StructuredLine, normalize_level};
+
+/// Returns the delimiter of a CSV or TSV file, going by its extension.
+pub(crate) fn delimiter_for(path: &str) -> Option<char> {
+    let ext = Path::new(path)
+        .extension()?
+        .to_string_lossy()
+        .to_lowercase();
+    match ext.as_str() {
+        "csv" => Some(','),
+        "tsv" => Some('\t'),
+        _ => None,
+    }
+}
+
+/// Reads a delimited file whose first row names the columns. Each row keeps
+/// its cells as fields in column order, with its level taken from the level
+/// column so the filters work on it.
+///
+/// The level column is the one named, or else the first with a name like
+/// `level` or `severity`.
+pub(crate) fn parse_delimited(
+    text: &str,
+    delimiter: char,
+    level_column: Option<&str>,
+    source: usize,
+) -> (Vec<String>, Vec<LogEntry>) {
+    let mut lines = text.lines();
+    let header = lines
+        .next()
+        .map(|line| split_row(line, delimiter))
+        .unwrap_or_default();
+    let entries = parse_rows(&header, lines, delimiter, level_column, source);
+    (header, entries)
+}
+
+/// Reads rows appended to a delimited file whose header was already read.
+pub(crate) fn parse_delimited_rows(
+    header: &[String],
+    text: &str,
+    delimiter: char,
+    level_column: Option<&str>,
+    source: usize,
+) -> Vec<LogEntry> {
+    parse_rows(header, text.lines(), delimiter, level_column, source)
+}
+
+fn parse_rows<'a>(
+    header: &[String],
+    lines: impl Iterator<Item = &'a str>,
+    delimiter: char,
+    level_column: Option<&str>,
+    source: usize,
+) -> Vec<LogEntry> {
+    let level_idx = header.iter().position(|column| match level_column {
+        Some(name) => column.eq_ignore_ascii_case(name),
+        None => LEVEL_KEYS
+            .iter()
+            .any(|key| column.eq_ignore_ascii_case(key)),
+    });
+
+    lines
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let cells = split_row(line, delimiter);
+            let level = level_idx
+                .and_then(|idx| cells.get(idx))
+                .map(|level| normalize_level(level).map_or(level.clone(), String::from));
+            let fields = header.iter().cloned().zip(cells).collect();
+            LogEntry::from_structured(
+                StructuredLine {
+                    level,
+                    fields,
+                    ..StructuredLine::default()
+                },
+                source,
+            )
+        })
+        .collect()
+}
+
+/// Splits a row on the delimiter, honouring double quoted cells with `""` for
+/// an escaped quote.
+fn split_row(line: &str, delimiter: char) -> Vec<String> {
+    let mut cells = vec![];
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => cells.push(std::mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell);
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(entries: &[LogEntry]) -> Vec<Option<&str>> {
+        entries
+            .iter()
+            .map(|entry| entry.structured.as_ref()?.level.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn picks_the_delimiter_by_extension() {
+        assert_eq!(delimiter_for("export.CSV"), Some(','));
+        assert_eq!(delimiter_for("/var/log/app.tsv"), Some('\t'));
+        assert_eq!(delimiter_for("app.log"), None);
+    }
+
+    #[test]
+    fn reads_rows_with_quoted_cells() {
+        let text = "time,Severity,message\n\
+                    14:02:10,warn,\"slow, \"\"orders\"\" query\"\n\
+                    \n\
+                    14:02:11,audit,login\n";
+        let (header, entries) = parse_delimited(text, ',', None, 0);
+        assert_eq!(header, ["time", "Severity", "message"]);
+        assert_eq!(levels(&entries), [Some("WARNING"), Some("audit")]);
+        let fields = &entries[0].structured.as_ref().unwrap().fields;
+        assert_eq!(fields[2].1, r#"slow, "orders" query"#);
+    }
+
+    #[test]
+    fn takes_the_level_from_the_column_named() {
+        let text = "status\tseverity\n500\terror\n";
+        let (_, entries) = parse_delimited(text, '\t', Some("STATUS"), 0);
+        assert_eq!(levels(&entries), [Some("500")]);
+    }
+}
@@ -0,0 +1,387 @@
+use color_eyre::{Result, eyre::eyre};
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::TcpStream,
+};
+
+use crate::{LineSink, LineStream, decode_line, uri_encode};
+
+/// How many lines of history to load before following new ones.
+const DOCKER_HISTORY: &str = "10000";
+
+/// Marker written after the timestamp of lines the container sent to stderr.
+const STDERR_MARKER: &str = "[stderr]";
+
+/// Where the docker daemon listens unless `DOCKER_HOST` says otherwise.
+const DEFAULT_DOCKER_HOST: &str = "unix:///var/run/docker.sock";
+
+/// The stream ids in the header of each frame of a multiplexed log stream.
+const STDOUT: u8 = 1;
+const STDERR: u8 = 2;
+
+/// A connection to the docker daemon.
+trait Connection: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Connection for T {}
+
+/// Follows the logs of a container through the docker Engine API, splitting
+/// the multiplexed log stream back into stdout and stderr.
+///
+/// The request is made here so a missing container or daemon is reported
+/// before the TUI starts.
+pub(crate) fn docker_stream(container: &str) -> Result<LineStream> {
+    let mut connection = connect()?;
+    let path = format!(
+        "/containers/{}/logs?follow=1&stdout=1&stderr=1&timestamps=1&tail={DOCKER_HISTORY}",
+        uri_encode(container)
+    );
+    write!(
+        connection,
+        "GET {path} HTTP/1.1\r\nHost: docker\r\nConnection: close\r\n\r\n"
+    )?;
+
+    let mut reader = BufReader::new(connection);
+    let head = read_head(&mut reader)?;
+    let body = Body::new(reader, head.chunked);
+    if head.status != 200 {
+        return Err(eyre!(
+            "docker couldn't send the logs of {container}: {}",
+            error_message(body)
+        ));
+    }
+
+    Ok(LineStream::spawn_thread(move |sink| {
+        // Containers started with a tty send their output as is, without
+        // splitting it into stdout and stderr.
+        if head.multiplexed {
+            demultiplex(body, |stream, line| push(&sink, stream, line));
+        } else {
+            read_raw(body, &sink);
+        }
+        sink.set_status(Some(String::from("container stopped")));
+    }))
+}
+
+/// Connects to the daemon at `DOCKER_HOST`, over a unix socket or plain tcp.
+fn connect() -> Result<Box<dyn Connection>> {
+    let host = env::var("DOCKER_HOST").unwrap_or_else(|_| DEFAULT_DOCKER_HOST.to_string());
+    match host.split_once("://") {
+        Some(("unix", path)) => connect_unix(path),
+        Some(("tcp", addr)) => Ok(Box::new(TcpStream::connect(addr)?)),
+        _ => Err(eyre!(
+            "DOCKER_HOST {host} isn't a unix:// or tcp:// address."
+        )),
+    }
+}
+
+#[cfg(unix)]
+fn connect_unix(path: &str) -> Result<Box<dyn Connection>> {
+    let socket = std::os::unix::net::UnixStream::connect(path)
+        .map_err(|error| eyre!("couldn't reach the docker daemon at {path}: {error}"))?;
+    Ok(Box::new(socket))
+}
+
+#[cfg(not(unix))]
+fn connect_unix(_path: &str) -> Result<Box<dyn Connection>> {
+    Err(eyre!("docker over a unix socket needs a unix system."))
+}
+
+/// What the response's status line and headers say about its body.
+#[derive(Debug, PartialEq)]
+struct Head {
+    status: u16,
+    chunked: bool,
+    /// Set when the body is split into stdout and stderr frames.
+    multiplexed: bool,
+}
+
+fn read_head(reader: &mut impl BufRead) -> io::Result<Head> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let status = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not an http response"))?;
+
+    let mut head = Head {
+        status,
+        chunked: false,
+        multiplexed: true,
+    };
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            return Ok(head);
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_ascii_lowercase();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "transfer-encoding" => head.chunked = value.contains("chunked"),
+            "content-type" => head.multiplexed = value != "application/vnd.docker.raw-stream",
+            _ => {}
+        }
+    }
+}
+
+/// The body of the response, taking the chunked transfer encoding off when
+/// it's used.
+struct Body<R> {
+    reader: R,
+    chunked: bool,
+    /// What's left of the current chunk.
+    left: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Body<R> {
+    fn new(reader: R, chunked: bool) -> Self {
+        Body {
+            reader,
+            chunked,
+            left: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> Read for Body<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.chunked {
+            return self.reader.read(buf);
+        }
+        if self.done {
+            return Ok(0);
+        }
+        if self.left == 0 {
+            let mut size = String::new();
+            self.reader.read_line(&mut size)?;
+            // A blank line is the end of the chunk before this one.
+            if size.trim().is_empty() {
+                size.clear();
+                self.reader.read_line(&mut size)?;
+            }
+            let size = size.trim().split(';').next().unwrap_or_default();
+            self.left = usize::from_str_radix(size, 16)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "bad chunk size"))?;
+            if self.left == 0 {
+                self.done = true;
+                return Ok(0);
+            }
+        }
+        let len = buf.len().min(self.left);
+        let read = self.reader.read(&mut buf[..len])?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.left -= read;
+        Ok(read)
+    }
+}
+
+/// The message of an error response, which the daemon sends as JSON.
+fn error_message(mut body: impl Read) -> String {
+    let mut text = String::new();
+    let _ = body.read_to_string(&mut text);
+    serde_json::from_str::<serde_json::Value>(&text)
+        .ok()
+        .and_then(|error| error.get("message")?.as_str().map(String::from))
+        .unwrap_or_else(|| text.trim().to_string())
+}
+
+/// Splits a multiplexed log stream into its stdout and stderr lines.
+///
+/// Each frame starts with an 8 byte header: the stream it's from, three
+/// bytes of padding, then the length of what follows as a big endian u32.
+/// A frame doesn't have to end on a line, so each stream keeps its own
+/// partial line. `push` is handed each line with the stream it's from, and
+/// stops the reading when it returns false.
+fn demultiplex(mut body: impl Read, mut push: impl FnMut(u8, String) -> bool) {
+    let (mut stdout, mut stderr) = (Lines::default(), Lines::default());
+    while let Ok(Some((stream, payload))) = read_frame(&mut body) {
+        let lines = match stream {
+            STDERR => &mut stderr,
+            _ => &mut stdout,
+        };
+        if !lines.push(&payload, |line| push(stream, line)) {
+            return;
+        }
+    }
+}
+
+/// Reads the output of a container with a tty, which is all stdout.
+fn read_raw(body: impl Read, sink: &LineSink) {
+    let mut reader = BufReader::new(body);
+    let mut raw = vec![];
+    while matches!(reader.read_until(b'\n', &mut raw), Ok(read) if read > 0) {
+        if !push(sink, STDOUT, decode_line(&raw)) {
+            return;
+        }
+        raw.clear();
+    }
+}
+
+/// Reads the next frame of a multiplexed stream, or `None` at its end.
+fn read_frame(body: &mut impl Read) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut header = [0; 8];
+    match body.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    }
+    let len = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    let mut payload = vec![0; len as usize];
+    body.read_exact(&mut payload)?;
+    Ok(Some((header[0], payload)))
+}
+
+/// Adds a line to the log, marking it when it came from stderr. Returns
+/// false once the stream was dropped.
+fn push(sink: &LineSink, stream: u8, line: String) -> bool {
+    match stream {
+        STDERR => sink.push(mark_stderr(&line)),
+        _ => sink.push(line),
+    }
+    !sink.is_stopped()
+}
+
+/// The part of a line received so far, until its newline comes in.
+#[derive(Debug, Default)]
+struct Lines {
+    partial: Vec<u8>,
+}
+
+impl Lines {
+    /// Adds what was received, handing each line it finishes to `push`.
+    /// Stops early when `push` returns false.
+    fn push(&mut self, bytes: &[u8], mut push: impl FnMut(String) -> bool) -> bool {
+        for piece in bytes.split_inclusive(|&byte| byte == b'\n') {
+            self.partial.extend_from_slice(piece);
+            if piece.ends_with(b"\n") {
+                let line = decode_line(&self.partial);
+                self.partial.clear();
+                if !push(line) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Adds the stderr marker, keeping the timestamp at the start of the line.
+fn mark_stderr(line: &str) -> String {
+    match line.split_once(' ') {
+        Some((timestamp, message)) => format!("{timestamp} {STDERR_MARKER} {message}"),
+        None => format!("{STDERR_MARKER} {line}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(stream: u8, payload: &str) -> Vec<u8> {
+        let mut frame = vec![stream, 0, 0, 0];
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend_from_slice(payload.as_bytes());
+        frame
+    }
+
+    /// The lines of each stream, tagged with the stream they came from.
+    fn demux(bytes: &[u8]) -> Vec<(u8, String)> {
+        let mut lines = vec![];
+        demultiplex(bytes, |stream, line| {
+            lines.push((stream, line));
+            true
+        });
+        lines
+    }
+
+    #[test]
+    fn splits_frames_into_stdout_and_stderr_lines() {
+        let bytes = [
+            frame(STDOUT, "2024-05-03T14:02:10Z started\n"),
+            frame(STDERR, "2024-05-03T14:02:11Z failed\n"),
+        ]
+        .concat();
+        assert_eq!(
+            demux(&bytes),
+            [
+                (STDOUT, String::from("2024-05-03T14:02:10Z started")),
+                (STDERR, String::from("2024-05-03T14:02:11Z failed")),
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_lines_split_across_frames_of_their_own_stream() {
+        let bytes = [
+            frame(STDOUT, "a\nb"),
+            frame(STDERR, "x"),
+            frame(STDOUT, "c\n"),
+            frame(STDERR, "y\r\n"),
+        ]
+        .concat();
+        assert_eq!(
+            demux(&bytes),
+            [
+                (STDOUT, String::from("a")),
+                (STDOUT, String::from("bc")),
+                (STDERR, String::from("xy")),
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_a_truncated_frame() {
+        let mut bytes = frame(STDOUT, "a\n");
+        bytes.extend_from_slice(&frame(STDOUT, "b\n")[..5]);
+        let mut body = bytes.as_slice();
+        assert!(read_frame(&mut body).unwrap().is_some());
+        assert_eq!(read_frame(&mut body).unwrap(), None);
+    }
+
+    #[test]
+    fn reads_a_chunked_body() {
+        let chunked = "4\r\nabcd\r\n3;ext\r\nefg\r\n0\r\n\r\n";
+        let mut text = String::new();
+        Body::new(chunked.as_bytes(), true)
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "abcdefg");
+    }
+
+    #[test]
+    fn reads_the_head_of_the_response() {
+        let response = "HTTP/1.1 200 OK\r\nContent-Type: application/vnd.docker.raw-stream\r\n\
+                        Transfer-Encoding: chunked\r\n\r\nbody";
+        let mut reader = response.as_bytes();
+        assert_eq!(
+            read_head(&mut reader).unwrap(),
+            Head {
+                status: 200,
+                chunked: true,
+                multiplexed: false,
+            }
+        );
+        assert_eq!(reader, b"body");
+    }
+
+    #[test]
+    fn reads_the_message_of_an_error() {
+        let body = r#"{"message":"No such container: api"}"#;
+        assert_eq!(error_message(body.as_bytes()), "No such container: api");
+    }
+
+    #[test]
+    fn marks_stderr_after_the_timestamp() {
+        assert_eq!(
+            mark_stderr("2024-05-03T14:02:11Z failed"),
+            "2024-05-03T14:02:11Z [stderr] failed"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use color_eyre::Result;
+use std::process::Command;
+
+use crate::LineStream;
+
+/// How many of the newest events of a channel to load. Exported files are
+/// read in full.
+const EVENTLOG_HISTORY: &str = "10000";
+
+/// Separates the fields printed by the powershell script.
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+/// Reads a Windows event log channel, like `Application` or `System`, or an
+/// exported `.evtx` file through powershell's `Get-WinEvent`.
+pub(crate) fn eventlog_stream(channel: &str) -> Result<LineStream> {
+    let mut command = Command::new("powershell");
+    command.args([
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        &script(channel),
+    ]);
+    LineStream::spawn(command, format_event)
+}
+
+/// The powershell script printing the events, one per line.
+fn script(channel: &str) -> String {
+    // A file is read from its oldest event on, as it streams. The newest
+    // events of a channel come back first, so they're sorted to read like a
+    // log.
+    let events = if channel.to_lowercase().ends_with(".evtx") {
+        format!("Get-WinEvent -Path {} -Oldest", ps_quote(channel))
+    } else {
+        format!(
+            "Get-WinEvent -LogName {} -MaxEvents {EVENTLOG_HISTORY} | Sort-Object TimeCreated",
+            ps_quote(channel)
+        )
+    };
+
+    // Messages span several lines, which are joined so each event is one row.
+    format!(
+        "{events} | ForEach-Object {{ '{{0:o}}{sep}{{1}}{sep}{{2}}{sep}{{3}}' -f $_.TimeCreated, \
+         $_.Level, $_.ProviderName, ($_.Message -replace '\\r?\\n', ' ') }}",
+        sep = FIELD_SEPARATOR,
+    )
+}
+
+/// Rebuilds an event printed by the script as a log line, writing its level as
+/// one of the names the filters look for.
+fn format_event(line: &str) -> Option<String> {
+    let mut fields = line.splitn(4, FIELD_SEPARATOR);
+    let timestamp = fields.next()?;
+    let level = event_level(fields.next()?.trim().parse().unwrap_or(4));
+    let provider = fields.next()?;
+    let message = fields.next().unwrap_or_default();
+
+    Some(format!("{timestamp} {level} {provider}: {message}"))
+}
+
+/// Maps an event record level onto the level names used by the filters.
+fn event_level(level: u8) -> &'static str {
+    match level {
+        1 => "CRITICAL",
+        2 => "ERROR",
+        3 => "WARNING",
+        5 => "DEBUG",
+        // 0 is "LogAlways", which is informational too.
+        _ => "INFO",
+    }
+}
+
+fn ps_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_a_whole_file_but_the_newest_events_of_a_channel() {
+        let file = script(r"C:\logs\app's.EVTX");
+        assert!(file.starts_with(r"Get-WinEvent -Path 'C:\logs\app''s.EVTX' -Oldest |"));
+        assert!(!file.contains("-MaxEvents"));
+
+        let channel = script("Application");
+        assert!(channel.starts_with(
+            "Get-WinEvent -LogName 'Application' -MaxEvents 10000 | Sort-Object TimeCreated |"
+        ));
+    }
+
+    #[test]
+    fn formats_an_event_with_its_level() {
+        let event = [
+            "2024-05-03T14:02:10.0000000+02:00",
+            "2",
+            "Service",
+            "it failed",
+        ]
+        .join(&FIELD_SEPARATOR.to_string());
+        assert_eq!(
+            format_event(&event).as_deref(),
+            Some("2024-05-03T14:02:10.0000000+02:00 ERROR Service: it failed")
+        );
+        assert_eq!(event_level(0), "INFO");
+        assert_eq!(event_level(1), "CRITICAL");
+    }
+}
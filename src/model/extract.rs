@@ -0,0 +1,68 @@
+use regex::Regex;
+
+/// Pulls fields out of any line a regex matches, one per named capture, like
+/// `ms` from `latency=(?P<ms>\d+)ms`. They can be filtered on like the
+/// fields of structured lines, so `ms > 500` works on plain text logs.
+#[derive(Debug, Clone)]
+pub(crate) struct FieldExtractor {
+    regex: Regex,
+}
+
+impl FieldExtractor {
+    pub(crate) fn new(pattern: &str) -> Result<FieldExtractor, &'static str> {
+        let regex = Regex::new(pattern).map_err(|_| "An extract regex isn't valid.")?;
+        if regex.capture_names().flatten().next().is_none() {
+            return Err("An extract regex needs a named capture, like (?P<ms>\\d+).");
+        }
+        Ok(FieldExtractor { regex })
+    }
+
+    /// Adds the captures of the line's first match to the fields.
+    pub(crate) fn extract(&self, line: &str, fields: &mut Vec<(String, String)>) {
+        let Some(captures) = self.regex.captures(line) else {
+            return;
+        };
+        for name in self.regex.capture_names().flatten() {
+            if let Some(value) = captures.name(name) {
+                fields.push((name.to_string(), value.as_str().to_string()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LogEntry, ParseOptions};
+
+    #[test]
+    fn pulls_a_field_out_of_each_named_capture() {
+        let extractor =
+            FieldExtractor::new(r"latency=(?P<ms>\d+)ms(?: user=(?P<user>\w+))?").unwrap();
+        let mut fields = vec![];
+        extractor.extract("GET /orders latency=512ms", &mut fields);
+        extractor.extract("GET /cart latency=20ms user=bob", &mut fields);
+        extractor.extract("GET /health", &mut fields);
+        let fields: Vec<(&str, &str)> = fields
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(fields, [("ms", "512"), ("ms", "20"), ("user", "bob")]);
+    }
+
+    #[test]
+    fn needs_a_named_capture() {
+        assert!(FieldExtractor::new(r"latency=(\d+)ms").is_err());
+        assert!(FieldExtractor::new(r"(?P<ms>\d+").is_err());
+    }
+
+    #[test]
+    fn extracts_the_fields_of_each_line_read() {
+        let options = ParseOptions {
+            extractors: vec![FieldExtractor::new(r"took (?P<ms>\d+)ms").unwrap()],
+            ..ParseOptions::default()
+        };
+        let entry = options.apply(LogEntry::new(String::from("INFO took 340ms"), 0));
+        assert_eq!(entry.extracted, [(String::from("ms"), String::from("340"))]);
+    }
+}
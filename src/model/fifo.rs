@@ -0,0 +1,77 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read},
+};
+
+use crate::{LineSink, LineStream};
+
+/// Returns true for named pipes and character devices, which never end and
+/// can't be read in one go like a regular file.
+#[cfg(unix)]
+pub(crate) fn is_stream_file(path: &str) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    fs::metadata(path).is_ok_and(|meta| {
+        let file_type = meta.file_type();
+        file_type.is_fifo() || file_type.is_char_device()
+    })
+}
+
+#[cfg(not(unix))]
+pub(crate) fn is_stream_file(_path: &str) -> bool {
+    false
+}
+
+/// Reads lines from a named pipe or character device as they're written.
+///
+/// Opening a pipe blocks until something opens it for writing, so that
+/// happens on the background thread too. Once a writer closes the pipe it's
+/// opened again to wait for the next one.
+pub(crate) fn fifo_stream(path: &str) -> LineStream {
+    let path = path.to_string();
+    LineStream::spawn_thread(move |sink| {
+        while !sink.is_stopped() {
+            let Ok(file) = File::open(&path) else {
+                return;
+            };
+            read_until_closed(file, &sink);
+
+            if !is_fifo(&path) {
+                return;
+            }
+        }
+    })
+}
+
+/// Reads the lines piped into the viewer, like the output of
+/// `cargo test 2>&1 | log_viewer -`. Keys are still read from the terminal.
+pub(crate) fn stdin_stream() -> LineStream {
+    LineStream::spawn_thread(|sink| read_until_closed(io::stdin(), &sink))
+}
+
+fn read_until_closed(file: impl Read, sink: &LineSink) {
+    let mut reader = BufReader::new(file);
+    let mut line = vec![];
+    while !sink.is_stopped() {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&line);
+                sink.push(text.trim_end_matches(['\n', '\r']).to_string());
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &str) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    fs::metadata(path).is_ok_and(|meta| meta.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_path: &str) -> bool {
+    false
+}
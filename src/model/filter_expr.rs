@@ -0,0 +1,491 @@
+use std::cmp::Ordering;
+
+use chrono::{NaiveDateTime, NaiveTime};
+use regex::Regex;
+
+use crate::{Level, LogEntry};
+
+/// A filter built from comparisons on the parts of a line, like
+/// `level>=WARN && (msg ~ "timeout" || logger == "db") && !msg ~ "retry"`.
+/// `and`, `or` and `not` can be written out instead, as in
+/// `level>=ERROR and ts>14:00 and msg contains "deadlock"`.
+///
+/// The left side of a comparison names what's compared: `level`, `msg` (the
+/// message of structured lines, the whole of plain ones), `line`, `time` (or
+/// `ts`), or any field of a structured line or pulled out by `--extract`. A word or a quoted string on its
+/// own matches lines holding it.
+#[derive(Debug, Clone)]
+pub(crate) struct FilterExpr {
+    expr: Expr,
+    /// What was typed, to show it and to edit it again.
+    text: String,
+}
+
+impl FilterExpr {
+    pub(crate) fn parse(text: &str) -> Result<FilterExpr, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, next: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected {}", token.describe()));
+        }
+        Ok(FilterExpr {
+            expr,
+            text: text.trim().to_string(),
+        })
+    }
+
+    /// Parses a single comparison on a field, like `status>=500`.
+    pub(crate) fn parse_field(text: &str) -> Result<FilterExpr, String> {
+        let filter = FilterExpr::parse(text)?;
+        match filter.expr {
+            Expr::Compare(..) => Ok(filter),
+            _ => Err(String::from(
+                "expected a field comparison, like status>=500",
+            )),
+        }
+    }
+
+    pub(crate) fn matches(&self, entry: &LogEntry) -> bool {
+        self.expr.matches(entry)
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// The line holds the text.
+    Contains(String),
+    Compare(String, Op, Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Match,
+    NotMatch,
+    Contains,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// The right side of a comparison, read ahead of time as what it's compared
+/// with needs it.
+#[derive(Debug, Clone)]
+enum Value {
+    Text(String),
+    Regex(Regex),
+    Level(Level),
+    /// A time of day, compared with the time lines were logged at whatever
+    /// the day.
+    Time(NaiveTime),
+    DateTime(NaiveDateTime),
+}
+
+impl Expr {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        match self {
+            Expr::And(left, right) => left.matches(entry) && right.matches(entry),
+            Expr::Or(left, right) => left.matches(entry) || right.matches(entry),
+            Expr::Not(expr) => !expr.matches(entry),
+            Expr::Contains(text) => entry.line.contains(text.as_str()),
+            // A line without the field doesn't equal anything, so it's
+            // always kept by `!=` and `!~`.
+            Expr::Compare(field, Op::Ne, value) => !compare(entry, field, Op::Eq, value),
+            Expr::Compare(field, Op::NotMatch, value) => !compare(entry, field, Op::Match, value),
+            Expr::Compare(field, op, value) => compare(entry, field, *op, value),
+        }
+    }
+}
+
+fn compare(entry: &LogEntry, field: &str, op: Op, value: &Value) -> bool {
+    if let Value::Level(wanted) = value {
+        let Some(level) = entry.level else {
+            return false;
+        };
+        return ordering_holds(level.cmp(wanted), op);
+    }
+    if let Value::Time(_) | Value::DateTime(_) = value {
+        let Some(timestamp) = entry.timestamp else {
+            return false;
+        };
+        let ordering = match value {
+            Value::Time(time) => timestamp.time().cmp(time),
+            Value::DateTime(wanted) => timestamp.cmp(wanted),
+            _ => unreachable!(),
+        };
+        return ordering_holds(ordering, op);
+    }
+
+    let Some(actual) = field_value(entry, field) else {
+        return false;
+    };
+    match value {
+        Value::Regex(regex) => regex.is_match(actual),
+        Value::Text(text) if op == Op::Contains => actual.contains(text.as_str()),
+        Value::Text(text) => {
+            // Numbers compare by value, so `status >= 500` works.
+            let ordering = match (actual.parse::<f64>(), text.parse::<f64>()) {
+                (Ok(actual), Ok(wanted)) => actual.partial_cmp(&wanted),
+                _ => Some(actual.cmp(text.as_str())),
+            };
+            ordering.is_some_and(|ordering| ordering_holds(ordering, op))
+        }
+        Value::Level(_) | Value::Time(_) | Value::DateTime(_) => unreachable!(),
+    }
+}
+
+fn is_time_field(field: &str) -> bool {
+    matches!(field, "time" | "timestamp" | "ts")
+}
+
+/// Reads a time of day, like `14:00`, or a date and time, like
+/// `2024-05-03T14:00:30`.
+fn parse_time(text: &str) -> Result<Value, String> {
+    const TIMES: [&str; 2] = ["%H:%M:%S%.f", "%H:%M"];
+    for format in TIMES {
+        if let Ok(time) = NaiveTime::parse_from_str(text, format) {
+            return Ok(Value::Time(time));
+        }
+        for separator in [" ", "T"] {
+            let format = format!("%Y-%m-%d{separator}{format}");
+            if let Ok(timestamp) = NaiveDateTime::parse_from_str(text, &format) {
+                return Ok(Value::DateTime(timestamp));
+            }
+        }
+    }
+    Err(format!(
+        "invalid time {text:?}, try 14:00 or 2024-05-03T14:00"
+    ))
+}
+
+fn ordering_holds(ordering: Ordering, op: Op) -> bool {
+    match op {
+        Op::Eq => ordering.is_eq(),
+        Op::Lt => ordering.is_lt(),
+        Op::Le => ordering.is_le(),
+        Op::Gt => ordering.is_gt(),
+        Op::Ge => ordering.is_ge(),
+        Op::Ne | Op::Match | Op::NotMatch | Op::Contains => false,
+    }
+}
+
+fn field_value<'a>(entry: &'a LogEntry, field: &str) -> Option<&'a str> {
+    let structured = entry.structured.as_ref();
+    match field {
+        "level" => entry.level.map(Level::name),
+        "line" => Some(&entry.line),
+        "msg" | "message" => match structured {
+            Some(structured) => structured.message.as_deref(),
+            None => Some(&entry.line),
+        },
+        "time" | "timestamp" | "ts" => structured?.timestamp.as_deref(),
+        field => structured
+            .into_iter()
+            .flat_map(|structured| &structured.fields)
+            .chain(&entry.extracted)
+            .find(|(key, _)| key == field)
+            .map(|(_, value)| value.as_str()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Op(Op),
+    Word(String),
+    Quoted(String),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Open => String::from("'('"),
+            Token::Close => String::from("')'"),
+            Token::And => String::from("'&&'"),
+            Token::Or => String::from("'||'"),
+            Token::Not => String::from("'!'"),
+            Token::Op(_) => String::from("comparison"),
+            Token::Word(word) => format!("'{word}'"),
+            Token::Quoted(text) => format!("{text:?}"),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '&' if chars.next_if_eq(&'&').is_some() => Token::And,
+            '|' if chars.next_if_eq(&'|').is_some() => Token::Or,
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ne),
+            '!' if chars.next_if_eq(&'~').is_some() => Token::Op(Op::NotMatch),
+            '!' => Token::Not,
+            '=' => {
+                chars.next_if_eq(&'=');
+                Token::Op(Op::Eq)
+            }
+            '~' => Token::Op(Op::Match),
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Le),
+            '<' => Token::Op(Op::Lt),
+            '>' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ge),
+            '>' => Token::Op(Op::Gt),
+            '"' | '\'' => {
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some('\\') => quoted.extend(chars.next()),
+                        Some(next) => quoted.push(next),
+                        None => return Err(String::from("unclosed quote")),
+                    }
+                }
+                Token::Quoted(quoted)
+            }
+            '&' | '|' => return Err(format!("expected {c}{c}")),
+            c => {
+                let mut word = String::from(c);
+                while let Some(next) = chars.next_if(|&next| !ends_word(next)) {
+                    word.push(next);
+                }
+                match word.to_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "contains" => Token::Op(Op::Contains),
+                    _ => Token::Word(word),
+                }
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn ends_word(c: char) -> bool {
+    c.is_whitespace() || "()&|!=~<>\"'".contains(c)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn take(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    fn take_if(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.take_if(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.take_if(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.take() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.take_if(&Token::Close) {
+                    true => Ok(expr),
+                    false => Err(String::from("missing ')'")),
+                }
+            }
+            Some(Token::Word(word)) => self.comparison(word),
+            Some(Token::Quoted(text)) => Ok(Expr::Contains(text)),
+            Some(token) => Err(format!("unexpected {}", token.describe())),
+            None => Err(String::from("unexpected end")),
+        }
+    }
+
+    fn comparison(&mut self, field: String) -> Result<Expr, String> {
+        let Some(Token::Op(op)) = self.peek().cloned() else {
+            return Ok(Expr::Contains(field));
+        };
+        self.next += 1;
+        let text = match self.take() {
+            Some(Token::Word(text) | Token::Quoted(text)) => text,
+            _ => return Err(format!("missing a value to compare {field} with")),
+        };
+
+        let value = match op {
+            Op::Match | Op::NotMatch => {
+                Value::Regex(Regex::new(&text).map_err(|_| format!("invalid regex {text:?}"))?)
+            }
+            Op::Contains => Value::Text(text),
+            _ if field == "level" => {
+                Value::Level(Level::parse(&text).ok_or(format!("unknown level {text:?}"))?)
+            }
+            _ if is_time_field(&field) => parse_time(&text)?,
+            _ => Value::Text(text),
+        };
+        Ok(Expr::Compare(field, op, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(line: &str) -> LogEntry {
+        LogEntry::new(line.to_string(), 0)
+    }
+
+    fn matches(text: &str, entry: &LogEntry) -> bool {
+        FilterExpr::parse(text).unwrap().matches(entry)
+    }
+
+    #[test]
+    fn tokenizes_operators_and_words() {
+        assert_eq!(
+            tokenize("level>=WARN && (msg ~ \"time out\" || !a!=b)").unwrap(),
+            [
+                Token::Word(String::from("level")),
+                Token::Op(Op::Ge),
+                Token::Word(String::from("WARN")),
+                Token::And,
+                Token::Open,
+                Token::Word(String::from("msg")),
+                Token::Op(Op::Match),
+                Token::Quoted(String::from("time out")),
+                Token::Or,
+                Token::Not,
+                Token::Word(String::from("a")),
+                Token::Op(Op::Ne),
+                Token::Word(String::from("b")),
+                Token::Close,
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_tokens() {
+        assert_eq!(tokenize("msg ~ \"open").unwrap_err(), "unclosed quote");
+        assert_eq!(tokenize("a & b").unwrap_err(), "expected &&");
+        assert_eq!(tokenize("a | b").unwrap_err(), "expected ||");
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert!(matches("a || b && c", &entry("a")));
+        assert!(!matches("a || b && c", &entry("b")));
+        assert!(matches("(a || b) && c", &entry("b c")));
+        assert!(!matches("!(a || b)", &entry("a")));
+        assert_eq!(FilterExpr::parse(" a  ").unwrap().as_str(), "a");
+    }
+
+    #[test]
+    fn compares_numbers_by_value() {
+        let mut line = entry("GET /health");
+        line.extracted = vec![(String::from("status"), String::from("503"))];
+        assert!(matches("status>=500", &line));
+        assert!(!matches("status<60", &line));
+        assert!(matches("status!=404", &line));
+        // A line without the field is kept by `!=` and left out otherwise.
+        assert!(matches("user!=bob", &line));
+        assert!(!matches("user==bob", &line));
+    }
+
+    #[test]
+    fn compares_levels_by_severity() {
+        let mut line = entry("disk full");
+        line.level = Some(Level::Error);
+        assert!(matches("level>=warn", &line));
+        assert!(!matches("level<error", &line));
+        assert!(matches("msg ~ \"^disk\" && !msg !~ full", &line));
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        let error = |text| FilterExpr::parse(text).unwrap_err();
+        assert_eq!(error("a)"), "unexpected ')'");
+        assert_eq!(error("(a"), "missing ')'");
+        assert_eq!(error("a &&"), "unexpected end");
+        assert_eq!(error("status>="), "missing a value to compare status with");
+        assert_eq!(error("level>=LOUD"), "unknown level \"LOUD\"");
+        assert_eq!(error("msg ~ \"(\""), "invalid regex \"(\"");
+    }
+
+    #[test]
+    fn tokenizes_spelled_out_operators() {
+        assert_eq!(
+            tokenize("a AND not b or msg contains 'it\\'s'").unwrap(),
+            [
+                Token::Word(String::from("a")),
+                Token::And,
+                Token::Not,
+                Token::Word(String::from("b")),
+                Token::Or,
+                Token::Word(String::from("msg")),
+                Token::Op(Op::Contains),
+                Token::Quoted(String::from("it's")),
+            ]
+        );
+    }
+
+    #[test]
+    fn compares_times() {
+        let mut line = entry("disk full");
+        line.timestamp =
+            NaiveDateTime::parse_from_str("2024-05-03 14:02:10", "%Y-%m-%d %H:%M:%S").ok();
+        assert!(matches("ts>14:00 and msg contains full", &line));
+        assert!(!matches("time>=14:02:11", &line));
+        assert!(matches("time<2024-05-03T14:03", &line));
+        assert!(!matches("ts>14:00", &entry("no timestamp")));
+        assert!(
+            FilterExpr::parse("ts>noon")
+                .unwrap_err()
+                .starts_with("invalid time \"noon\"")
+        );
+    }
+
+    #[test]
+    fn field_filters_take_a_single_comparison() {
+        assert!(FilterExpr::parse_field("status>=500").is_ok());
+        assert!(FilterExpr::parse_field("status>=500 && a").is_err());
+        assert!(FilterExpr::parse_field("timeout").is_err());
+    }
+}
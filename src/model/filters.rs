@@ -0,0 +1,259 @@
+use std::collections::BTreeSet;
+
+use regex::Regex;
+
+use crate::{FilterExpr, Level, TimeRange};
+
+/// Everything that decides which lines are shown, apart from the search.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Filters {
+    /// The levels shown, or every line when it's empty.
+    pub(crate) levels: BTreeSet<Level>,
+    /// Hide lines less severe than this, along with lines without a level.
+    pub(crate) min_level: Option<Level>,
+    /// Only show lines matching this regex.
+    pub(crate) regex: Option<Regex>,
+    /// Hide lines matching any of these regexes.
+    pub(crate) exclude: Vec<Regex>,
+    /// Only show lines logged in this interval.
+    pub(crate) time_range: Option<TimeRange>,
+    /// Only show lines matching this expression.
+    pub(crate) expression: Option<FilterExpr>,
+    /// Only show lines whose fields match all of these comparisons.
+    pub(crate) fields: Vec<FilterExpr>,
+    /// Only show lines holding this word, taken from a line in the log.
+    pub(crate) quick: Option<String>,
+    /// Only show lines written by these loggers, or every logger when it's
+    /// empty.
+    pub(crate) logger_include: BTreeSet<String>,
+    /// Hide lines written by these loggers.
+    pub(crate) logger_exclude: BTreeSet<String>,
+    /// Lines hidden one at a time while reading, along with any repeats.
+    pub(crate) hidden: BTreeSet<String>,
+    /// Hide lines of the same kind as these, by their `line_template`.
+    pub(crate) hidden_templates: BTreeSet<String>,
+}
+
+impl Filters {
+    /// Describes each filter in use, like `ERROR`, `/timeout/` or
+    /// `[svc=payments]`.
+    pub(crate) fn labels(&self) -> Vec<String> {
+        let mut labels = vec![];
+        if !self.levels.is_empty() {
+            let levels: Vec<&str> = self.levels.iter().map(|level| level.name()).collect();
+            labels.push(levels.join(","));
+        }
+        if let Some(level) = self.min_level {
+            labels.push(format!("{}+", level.name()));
+        }
+        if let Some(regex) = &self.regex {
+            labels.push(format!("/{}/", regex.as_str()));
+        }
+        for regex in &self.exclude {
+            labels.push(format!("!/{}/", regex.as_str()));
+        }
+        if let Some(range) = &self.time_range {
+            labels.push(range.as_str().to_string());
+        }
+        if let Some(expression) = &self.expression {
+            labels.push(format!("({})", expression.as_str()));
+        }
+        for filter in &self.fields {
+            labels.push(format!("[{}]", filter.as_str()));
+        }
+        for logger in &self.logger_include {
+            labels.push(format!("+{logger}"));
+        }
+        for logger in &self.logger_exclude {
+            labels.push(format!("-{logger}"));
+        }
+        if let Some(word) = &self.quick {
+            labels.push(format!("{word:?}"));
+        }
+        for line in &self.hidden {
+            labels.push(format!("x {:?}", shorten(line)));
+        }
+        for template in &self.hidden_templates {
+            labels.push(format!("x ~{:?}", shorten(template)));
+        }
+        labels
+    }
+}
+
+/// Filters are the same when they let the same lines through, the regexes
+/// and expressions among them being told apart by what was typed.
+impl PartialEq for Filters {
+    fn eq(&self, other: &Filters) -> bool {
+        self.levels == other.levels
+            && self.min_level == other.min_level
+            && self.regex.as_ref().map(Regex::as_str) == other.regex.as_ref().map(Regex::as_str)
+            && (self.exclude.iter().map(Regex::as_str)).eq(other.exclude.iter().map(Regex::as_str))
+            && self.time_range == other.time_range
+            && self.expression.as_ref().map(FilterExpr::as_str)
+                == other.expression.as_ref().map(FilterExpr::as_str)
+            && (self.fields.iter().map(FilterExpr::as_str))
+                .eq(other.fields.iter().map(FilterExpr::as_str))
+            && self.quick == other.quick
+            && self.logger_include == other.logger_include
+            && self.logger_exclude == other.logger_exclude
+            && self.hidden == other.hidden
+            && self.hidden_templates == other.hidden_templates
+    }
+}
+
+/// Cuts a hidden line short enough to go in the chain.
+fn shorten(line: &str) -> String {
+    const MAX_CHARS: usize = 24;
+    match line.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// A change made to the filters, kept along with the filters from before it
+/// so it can be undone.
+#[derive(Debug)]
+pub(crate) struct FilterStage {
+    /// What the change set, like `/timeout/`, or what it cleared.
+    pub(crate) label: String,
+    previous: Filters,
+}
+
+/// Adds a stage for the change from the filters before to the ones after, if
+/// anything changed. A change to what the last stage set, like picking
+/// another level, updates that stage instead so the chain doesn't fill up
+/// with every step.
+pub(crate) fn record_stage(stages: &mut Vec<FilterStage>, before: Filters, after: &Filters) {
+    let old = before.labels();
+    let new = after.labels();
+    let added: Vec<&str> = new
+        .iter()
+        .filter(|label| !old.contains(label))
+        .map(String::as_str)
+        .collect();
+    let removed: Vec<&str> = old
+        .iter()
+        .filter(|label| !new.contains(label))
+        .map(String::as_str)
+        .collect();
+    if added.is_empty() && removed.is_empty() {
+        return;
+    }
+
+    let label = match added.is_empty() {
+        true => format!("cleared {}", removed.join(" ")),
+        false => added.join(" "),
+    };
+
+    if let Some(last) = stages.last_mut()
+        && removed.contains(&last.label.as_str())
+        && !added.is_empty()
+    {
+        last.label = label;
+        return;
+    }
+    stages.push(FilterStage {
+        label,
+        previous: before,
+    });
+}
+
+/// Undoes the last change made to the filters.
+pub(crate) fn pop_stage(stages: &mut Vec<FilterStage>, filters: &mut Filters) {
+    if let Some(stage) = stages.pop() {
+        *filters = stage.previous;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(stages: &[FilterStage]) -> Vec<&str> {
+        stages.iter().map(|stage| stage.label.as_str()).collect()
+    }
+
+    fn change(
+        stages: &mut Vec<FilterStage>,
+        filters: &mut Filters,
+        edit: impl FnOnce(&mut Filters),
+    ) {
+        let before = filters.clone();
+        edit(filters);
+        record_stage(stages, before, filters);
+    }
+
+    #[test]
+    fn records_a_stage_for_each_change() {
+        let (mut stages, mut filters) = (vec![], Filters::default());
+        change(&mut stages, &mut filters, |_| {});
+        assert!(stages.is_empty());
+
+        change(&mut stages, &mut filters, |filters| {
+            filters.regex = Regex::new("timeout").ok();
+        });
+        change(&mut stages, &mut filters, |filters| {
+            filters.quick = Some(String::from("req-20"));
+        });
+        assert_eq!(labels(&stages), ["/timeout/", "\"req-20\""]);
+
+        change(&mut stages, &mut filters, |filters| filters.regex = None);
+        assert_eq!(
+            labels(&stages),
+            ["/timeout/", "\"req-20\"", "cleared /timeout/"]
+        );
+    }
+
+    #[test]
+    fn updates_the_last_stage_when_it_changes_what_that_set() {
+        let (mut stages, mut filters) = (vec![], Filters::default());
+        change(&mut stages, &mut filters, |filters| {
+            filters.min_level = Some(Level::Warning);
+        });
+        change(&mut stages, &mut filters, |filters| {
+            filters.min_level = Some(Level::Error);
+        });
+        assert_eq!(labels(&stages), ["ERROR+"]);
+
+        // Undoing it goes back to before the first of the two.
+        pop_stage(&mut stages, &mut filters);
+        assert!(stages.is_empty());
+        assert_eq!(filters.min_level, None);
+    }
+
+    #[test]
+    fn undoes_the_changes_in_turn() {
+        let (mut stages, mut filters) = (vec![], Filters::default());
+        change(&mut stages, &mut filters, |filters| {
+            filters.levels.insert(Level::Error);
+        });
+        change(&mut stages, &mut filters, |filters| {
+            filters.regex = Regex::new("timeout").ok();
+        });
+
+        pop_stage(&mut stages, &mut filters);
+        assert!(filters.regex.is_none());
+        assert_eq!(filters.labels(), ["ERROR"]);
+        pop_stage(&mut stages, &mut filters);
+        pop_stage(&mut stages, &mut filters);
+        assert!(filters.labels().is_empty());
+    }
+
+    #[test]
+    fn labels_hidden_lines_cut_short() {
+        let (mut stages, mut filters) = (vec![], Filters::default());
+        change(&mut stages, &mut filters, |filters| {
+            filters
+                .hidden
+                .insert(String::from("a line long enough to be cut short"));
+            filters.hidden_templates.insert(String::from("retry in *s"));
+        });
+        assert_eq!(
+            filters.labels(),
+            ["x \"a line long enough to be…\"", "x ~\"retry in *s\""]
+        );
+
+        pop_stage(&mut stages, &mut filters);
+        assert!(filters.hidden.is_empty() && filters.hidden_templates.is_empty());
+    }
+}
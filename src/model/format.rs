@@ -0,0 +1,152 @@
+use crate::{
+    StructuredLine, parse_access, parse_cef, parse_json, parse_leef, parse_logfmt,
+    parse_syslog_line,
+};
+
+/// How many lines are looked at to guess the format of a log.
+const SNIFF_LINES: usize = 50;
+
+/// The built in formats a log can be read as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LogFormat {
+    /// Lines are shown as they are.
+    Plain,
+    Json,
+    Logfmt,
+    /// Apache and nginx access logs.
+    Access,
+    Syslog,
+    Cef,
+    Leef,
+}
+
+impl LogFormat {
+    /// The structured formats, in the order they're tried when the format
+    /// isn't set. Stricter formats go first so a CEF line, which also holds
+    /// `key=value` pairs, isn't read as logfmt.
+    pub(crate) const STRUCTURED: [LogFormat; 6] = [
+        LogFormat::Json,
+        LogFormat::Cef,
+        LogFormat::Leef,
+        LogFormat::Logfmt,
+        LogFormat::Access,
+        LogFormat::Syslog,
+    ];
+
+    /// The choices offered by the format picker, with `None` for trying
+    /// every format on each line.
+    pub(crate) const CHOICES: [Option<LogFormat>; 8] = [
+        None,
+        Some(LogFormat::Plain),
+        Some(LogFormat::Json),
+        Some(LogFormat::Logfmt),
+        Some(LogFormat::Access),
+        Some(LogFormat::Syslog),
+        Some(LogFormat::Cef),
+        Some(LogFormat::Leef),
+    ];
+
+    /// Parses the argument of `--format`. `auto` gives `None`.
+    pub(crate) fn from_name(name: &str) -> Result<Option<LogFormat>, &'static str> {
+        match name {
+            "auto" => Ok(None),
+            "plain" => Ok(Some(LogFormat::Plain)),
+            "json" => Ok(Some(LogFormat::Json)),
+            "logfmt" => Ok(Some(LogFormat::Logfmt)),
+            "access" => Ok(Some(LogFormat::Access)),
+            "syslog" => Ok(Some(LogFormat::Syslog)),
+            "cef" => Ok(Some(LogFormat::Cef)),
+            "leef" => Ok(Some(LogFormat::Leef)),
+            _ => Err("--format takes auto, plain, json, logfmt, access, syslog, cef or leef."),
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            LogFormat::Plain => "plain text",
+            LogFormat::Json => "JSON",
+            LogFormat::Logfmt => "logfmt",
+            LogFormat::Access => "access log",
+            LogFormat::Syslog => "syslog",
+            LogFormat::Cef => "CEF",
+            LogFormat::Leef => "LEEF",
+        }
+    }
+
+    pub(crate) fn parse(self, line: &str) -> Option<StructuredLine> {
+        match self {
+            LogFormat::Plain => None,
+            LogFormat::Json => parse_json(line),
+            LogFormat::Logfmt => parse_logfmt(line),
+            LogFormat::Access => parse_access(line),
+            LogFormat::Syslog => parse_syslog_line(line),
+            LogFormat::Cef => parse_cef(line),
+            LogFormat::Leef => parse_leef(line),
+        }
+    }
+
+    /// Guesses the format from the first lines of a log: the structured
+    /// format most of them are in, or plain text. Returns `None` when there
+    /// are no lines to go by yet.
+    pub(crate) fn detect<'a>(lines: impl Iterator<Item = &'a str>) -> Option<LogFormat> {
+        let lines: Vec<&str> = lines
+            .filter(|line| !line.trim().is_empty())
+            .take(SNIFF_LINES)
+            .collect();
+        if lines.is_empty() {
+            return None;
+        }
+
+        // Reversed so ties go to the stricter format, since the last of the
+        // largest is picked.
+        let (format, matches) = LogFormat::STRUCTURED
+            .into_iter()
+            .rev()
+            .map(|format| {
+                let matches = lines
+                    .iter()
+                    .filter(|line| format.parse(line).is_some())
+                    .count();
+                (format, matches)
+            })
+            .max_by_key(|(_, matches)| *matches)?;
+
+        match matches * 2 > lines.len() {
+            true => Some(format),
+            false => Some(LogFormat::Plain),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_format_most_lines_are_in() {
+        let lines = [
+            r#"{"level":"info","msg":"started"}"#,
+            "",
+            "panic: runtime error",
+            r#"{"level":"error","msg":"failed"}"#,
+        ];
+        assert_eq!(LogFormat::detect(lines.into_iter()), Some(LogFormat::Json));
+        assert_eq!(LogFormat::detect(["", "  "].into_iter()), None);
+    }
+
+    #[test]
+    fn falls_back_to_plain_text() {
+        let lines = ["starting up", "level=info msg=ready", "listening on :8080"];
+        assert_eq!(LogFormat::detect(lines.into_iter()), Some(LogFormat::Plain));
+    }
+
+    #[test]
+    fn prefers_the_stricter_format_on_a_tie() {
+        // Every token of it is a pair, so it's logfmt too.
+        let line = "CEF:0|Acme|IDS|2|7|Login|3|src=10.0.0.1 dst=10.0.0.2";
+        assert!(LogFormat::Logfmt.parse(line).is_some());
+        assert_eq!(LogFormat::detect([line].into_iter()), Some(LogFormat::Cef));
+        assert_eq!(LogFormat::from_name("auto"), Ok(None));
+        assert!(LogFormat::from_name("xml").is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::TimeDelta;
+
+use crate::LogEntry;
+
+/// How many seconds the log has to go quiet for a gap, unless the config
+/// file says otherwise.
+const GAP_SECONDS: i64 = 30;
+
+/// How long the log has to go without a line for `]g` and `[g` to stop
+/// there, since a gap often marks a restart or a hang.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct GapThreshold(TimeDelta);
+
+impl Default for GapThreshold {
+    fn default() -> GapThreshold {
+        GapThreshold(TimeDelta::seconds(GAP_SECONDS))
+    }
+}
+
+impl GapThreshold {
+    /// Reads a number of seconds from the config file.
+    pub(crate) fn from_seconds(seconds: i64) -> Option<GapThreshold> {
+        (seconds > 0).then(|| GapThreshold(TimeDelta::seconds(seconds)))
+    }
+
+    /// Which of the lines were logged at least this long after the last
+    /// line with a timestamp before them.
+    pub(crate) fn gaps(self, logs: &[LogEntry]) -> Vec<bool> {
+        let mut last = None;
+        logs.iter()
+            .map(|entry| {
+                let Some(timestamp) = entry.timestamp else {
+                    return false;
+                };
+                let gap = last.is_some_and(|last| timestamp - last >= self.0);
+                last = Some(timestamp);
+                gap
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn logged(times: &[&str]) -> Vec<LogEntry> {
+        times
+            .iter()
+            .map(|time| LogEntry {
+                timestamp: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").ok(),
+                ..LogEntry::new(time.to_string(), 0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_where_the_log_went_quiet() {
+        let logs = logged(&[
+            "2024-05-03 14:00:00",
+            "2024-05-03 14:00:10",
+            "    at main.rs:3",
+            "2024-05-03 14:00:40",
+            "2024-05-03 14:00:41",
+            "2024-05-03 14:01:11",
+        ]);
+        assert_eq!(
+            GapThreshold::default().gaps(&logs),
+            [false, false, false, true, false, true]
+        );
+        let minute = GapThreshold::from_seconds(60).unwrap();
+        assert!(minute.gaps(&logs).iter().all(|gap| !gap));
+    }
+
+    #[test]
+    fn needs_a_threshold_above_zero() {
+        assert!(GapThreshold::from_seconds(0).is_none());
+        assert!(GapThreshold::from_seconds(-5).is_none());
+    }
+}
@@ -0,0 +1,115 @@
+use std::{fs, path::PathBuf};
+
+use crate::data_dir;
+
+/// How many entries are kept, over every kind.
+const MAX_ENTRIES: usize = 1000;
+
+/// The searches and filters typed in before, kept between runs so they can
+/// be brought back with Up and Down.
+///
+/// It's saved to `history` in the data directory, one entry per line after
+/// the kind of prompt it was typed into, like `regex\ttimeout|refused`.
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    /// From oldest to newest.
+    entries: Vec<(String, String)>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// Reads the saved history. A missing or unreadable file just means
+    /// starting out empty.
+    pub(crate) fn load() -> History {
+        let Some(path) = data_dir().map(|dir| dir.join("history")) else {
+            return History::default();
+        };
+        let entries = fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(kind, text)| (kind.to_string(), text.to_string()))
+            .collect();
+        History {
+            entries,
+            path: Some(path),
+        }
+    }
+
+    /// Adds an entry as the newest of its kind and saves the history. Failing
+    /// to save isn't worth interrupting anyone over, so it's ignored.
+    pub(crate) fn add(&mut self, kind: &str, text: &str) {
+        let text = text.trim();
+        if text.is_empty() || text.contains('\n') {
+            return;
+        }
+        self.entries
+            .retain(|(old_kind, old_text)| old_kind != kind || old_text != text);
+        self.entries.push((kind.to_string(), text.to_string()));
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+
+        if let Some(path) = &self.path {
+            let lines: String = self
+                .entries
+                .iter()
+                .map(|(kind, text)| format!("{kind}\t{text}\n"))
+                .collect();
+            let _ = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(path, lines));
+        }
+    }
+
+    /// The newest entry of the kind before the given position, or the newest
+    /// of all when there's no position yet.
+    pub(crate) fn older(&self, kind: &str, before: Option<usize>) -> Option<(usize, &str)> {
+        let end = before.unwrap_or(self.entries.len());
+        self.entries[..end]
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, (entry_kind, _))| entry_kind == kind)
+            .map(|(pos, (_, text))| (pos, text.as_str()))
+    }
+
+    /// The newest entry of the kind before the given position holding the
+    /// letters of the query in order, ignoring case and spaces, so `tmo pay`
+    /// finds `timeout payments`.
+    pub(crate) fn older_matching(
+        &self,
+        kind: &str,
+        query: &str,
+        before: Option<usize>,
+    ) -> Option<(usize, &str)> {
+        let end = before.unwrap_or(self.entries.len());
+        let query: Vec<char> = query
+            .to_lowercase()
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        self.entries[..end]
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, (entry_kind, _))| entry_kind == kind)
+            .find(|(_, (_, text))| {
+                let text = text.to_lowercase();
+                let mut letters = text.chars();
+                query.iter().all(|c| letters.any(|letter| letter == *c))
+            })
+            .map(|(pos, (_, text))| (pos, text.as_str()))
+    }
+
+    /// The oldest entry of the kind after the given position.
+    pub(crate) fn newer(&self, kind: &str, after: usize) -> Option<(usize, &str)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .skip(after + 1)
+            .find(|(_, (entry_kind, _))| entry_kind == kind)
+            .map(|(pos, (_, text))| (pos, text.as_str()))
+    }
+}
@@ -0,0 +1,209 @@
+use color_eyre::Result;
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    thread,
+    time::Duration,
+};
+use ureq::{Body, http::Response};
+
+use crate::{LineSink, LineStream, decode_line};
+
+/// How often to check the URL for lines appended since the last fetch.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Returns true if the argument should be fetched over http.
+pub(crate) fn is_url(arg: &str) -> bool {
+    arg.starts_with("http://") || arg.starts_with("https://")
+}
+
+/// Streams the body of the URL, then keeps polling it with range requests so
+/// a growing remote file keeps updating.
+///
+/// The first request is made here so a bad URL fails before the TUI starts.
+pub(crate) fn http_stream(url: &str) -> Result<LineStream> {
+    let response = ureq::get(url).call()?;
+    let url = url.to_string();
+
+    Ok(LineStream::spawn_thread(move |sink| {
+        let mut progress = Progress::default();
+        read_response(&mut progress, response, &sink);
+
+        while !sink.is_stopped() {
+            thread::sleep(POLL_INTERVAL);
+
+            // The status is left to `Progress` to make sense of, since a 416
+            // means the file shrank. Failures are retried on the next poll.
+            let request = ureq::get(&url)
+                .config()
+                .http_status_as_error(false)
+                .build()
+                .header("Range", progress.range());
+            if let Ok(response) = request.call() {
+                read_response(&mut progress, response, &sink);
+            }
+        }
+    }))
+}
+
+fn read_response(progress: &mut Progress, response: Response<Body>, sink: &LineSink) {
+    let status = response.status().as_u16();
+    let body = BufReader::new(response.into_body().into_reader());
+    progress.read(status, body, |line| {
+        sink.push(line);
+        !sink.is_stopped()
+    });
+}
+
+/// How far into the remote file the lines have been read, kept from one poll
+/// to the next.
+#[derive(Debug, Default, PartialEq)]
+struct Progress {
+    /// The bytes up to the end of the last line pushed.
+    offset: u64,
+    /// A last line that had no newline at the last poll, held back in case
+    /// the rest of it was still being written.
+    partial: Vec<u8>,
+}
+
+impl Progress {
+    /// The range to ask for. It starts a byte early, so a file that hasn't
+    /// changed still sends one and a 416 means it's shorter than before.
+    fn range(&self) -> String {
+        format!("bytes={}-", self.offset.saturating_sub(1))
+    }
+
+    /// Pushes each complete line of a response to the range asked for, until
+    /// `push` returns false.
+    ///
+    /// A last line without a newline is pushed once a poll finds it no
+    /// longer, taken as finished. Anything written on to it after that
+    /// comes as a line of its own.
+    fn read(&mut self, status: u16, mut body: impl BufRead, mut push: impl FnMut(String) -> bool) {
+        let skip = match status {
+            206 => self.offset.min(1),
+            // Servers that ignore the range send the whole file again.
+            200 => self.offset,
+            // The file was truncated or replaced, so it's read again from
+            // the start on the next poll.
+            416 => {
+                *self = Progress::default();
+                return;
+            }
+            _ => return,
+        };
+        match io::copy(&mut (&mut body).take(skip), &mut io::sink()) {
+            Ok(skipped) if skipped == skip => {}
+            Ok(_) => {
+                *self = Progress::default();
+                return;
+            }
+            Err(_) => return,
+        }
+
+        let mut line = vec![];
+        loop {
+            line.clear();
+            match body.read_until(b'\n', &mut line) {
+                Ok(read) if line.ends_with(b"\n") => {
+                    self.offset += read as u64;
+                    if !push(decode_line(&line)) {
+                        return;
+                    }
+                }
+                Ok(_) => break,
+                Err(_) => return,
+            }
+        }
+
+        if !line.is_empty() && line == self.partial {
+            self.offset += line.len() as u64;
+            self.partial.clear();
+            push(decode_line(&line));
+        } else {
+            self.partial = line;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(progress: &mut Progress, status: u16, body: &str) -> Vec<String> {
+        let mut lines = vec![];
+        progress.read(status, body.as_bytes(), |line| {
+            lines.push(line);
+            true
+        });
+        lines
+    }
+
+    #[test]
+    fn reads_complete_lines() {
+        let mut progress = Progress::default();
+        assert_eq!(read(&mut progress, 200, "a\r\nb\n"), ["a", "b"]);
+        assert_eq!(progress.offset, 5);
+        assert_eq!(progress.range(), "bytes=4-");
+    }
+
+    #[test]
+    fn skips_the_byte_before_the_range() {
+        let mut progress = Progress::default();
+        read(&mut progress, 200, "a\nb\n");
+        assert_eq!(read(&mut progress, 206, "\nc\n"), ["c"]);
+        assert_eq!(read(&mut progress, 206, "\n"), Vec::<String>::new());
+        assert_eq!(progress.offset, 6);
+    }
+
+    #[test]
+    fn skips_what_was_read_when_the_range_is_ignored() {
+        let mut progress = Progress::default();
+        read(&mut progress, 200, "a\nb\n");
+        assert_eq!(read(&mut progress, 200, "a\nb\nc\n"), ["c"]);
+        assert_eq!(progress.offset, 6);
+    }
+
+    #[test]
+    fn holds_back_a_last_line_until_it_stops_growing() {
+        let mut progress = Progress::default();
+        assert_eq!(read(&mut progress, 200, "a\nb"), ["a"]);
+        assert_eq!(read(&mut progress, 206, "\nbc"), Vec::<String>::new());
+        assert_eq!(read(&mut progress, 206, "\nbc"), ["bc"]);
+        assert_eq!(progress.offset, 4);
+        assert!(progress.partial.is_empty());
+        assert_eq!(read(&mut progress, 206, "cd\n"), ["d"]);
+    }
+
+    #[test]
+    fn finishes_a_held_back_line_with_its_newline() {
+        let mut progress = Progress::default();
+        read(&mut progress, 200, "a\nb");
+        assert_eq!(read(&mut progress, 206, "\nb\n"), ["b"]);
+        assert_eq!(progress.offset, 4);
+        assert!(progress.partial.is_empty());
+    }
+
+    #[test]
+    fn starts_over_when_the_file_shrinks() {
+        let mut progress = Progress::default();
+        read(&mut progress, 200, "a\nb\n");
+        assert_eq!(read(&mut progress, 416, ""), Vec::<String>::new());
+        assert_eq!(progress, Progress::default());
+
+        read(&mut progress, 200, "a\nb\n");
+        assert_eq!(read(&mut progress, 200, "c\n"), Vec::<String>::new());
+        assert_eq!(progress, Progress::default());
+        assert_eq!(read(&mut progress, 206, "c\n"), ["c"]);
+    }
+
+    #[test]
+    fn ignores_other_statuses() {
+        let mut progress = Progress::default();
+        read(&mut progress, 200, "a\n");
+        assert_eq!(
+            read(&mut progress, 503, "unavailable\n"),
+            Vec::<String>::new()
+        );
+        assert_eq!(progress.offset, 2);
+    }
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Local};
+use color_eyre::Result;
+use serde_json::Value;
+use std::process::Command;
+
+use crate::{LineStream, severity_level};
+
+/// How many entries of history to load before following new ones.
+const JOURNAL_HISTORY: &str = "10000";
+
+/// Follows the systemd journal, optionally limited to a single unit.
+pub(crate) fn journal_stream(unit: Option<&str>) -> Result<LineStream> {
+    let mut command = Command::new("journalctl");
+    command.args([
+        "--follow",
+        "--no-pager",
+        "--output=json",
+        "--lines",
+        JOURNAL_HISTORY,
+    ]);
+    if let Some(unit) = unit {
+        command.args(["--unit", unit]);
+    }
+
+    LineStream::spawn(command, format_entry)
+}
+
+/// Turns a journal entry into a plain log line, writing its priority as one of
+/// the level names the filters look for. Journal priorities are syslog
+/// severities.
+fn format_entry(json: &str) -> Option<String> {
+    let entry: Value = serde_json::from_str(json).ok()?;
+    let field = |name: &str| entry.get(name).and_then(Value::as_str);
+
+    let timestamp = field("__REALTIME_TIMESTAMP")
+        .and_then(|micros| micros.parse().ok())
+        .and_then(DateTime::from_timestamp_micros)
+        .map(|ts| {
+            ts.with_timezone(&Local)
+                .format("%Y-%m-%dT%H:%M:%S%.3f%:z")
+                .to_string()
+        })
+        .unwrap_or_default();
+    let level = severity_level(field("PRIORITY").and_then(|p| p.parse().ok()).unwrap_or(6));
+    let identifier = field("SYSLOG_IDENTIFIER")
+        .or(field("_COMM"))
+        .unwrap_or("unknown");
+    let message = field("MESSAGE").unwrap_or_default();
+
+    match field("_PID") {
+        Some(pid) => Some(format!(
+            "{timestamp} {level} {identifier}[{pid}]: {message}"
+        )),
+        None => Some(format!("{timestamp} {level} {identifier}: {message}")),
+    }
+}
@@ -0,0 +1,212 @@
+use serde_json::{Map, Value};
+
+use crate::{
+    LEVEL_KEYS, MESSAGE_KEYS, StructuredLine, TIMESTAMP_KEYS, normalize_level, severity_level,
+};
+
+/// Parses a line holding a single JSON object, as written by most structured
+/// loggers.
+pub(crate) fn parse_json(line: &str) -> Option<StructuredLine> {
+    if !line.trim_start().starts_with('{') {
+        return None;
+    }
+    let Value::Object(mut object) = serde_json::from_str(line).ok()? else {
+        return None;
+    };
+    if object.contains_key("short_message") {
+        return Some(parse_gelf(object));
+    }
+
+    let timestamp = take_first(&mut object, &TIMESTAMP_KEYS);
+    let level = take_first_value(&mut object, &LEVEL_KEYS).map(|level| match level {
+        Value::Number(number) => {
+            // Bunyan and pino write their version next to a numeric level.
+            object.shift_remove("v");
+            number
+                .as_u64()
+                .map_or(number.to_string(), |level| numeric_level(level).to_string())
+        }
+        level => {
+            let level = value_text(level);
+            normalize_level(&level).map_or(level, String::from)
+        }
+    });
+    let message = take_first(&mut object, &MESSAGE_KEYS);
+
+    let mut fields = vec![];
+    for (key, value) in object {
+        match value {
+            // Errors are split into their parts so the preview shows the
+            // stack as a trace, one frame per row.
+            Value::Object(error) if ERROR_KEYS.contains(&key.as_str()) => fields.extend(
+                error
+                    .into_iter()
+                    .map(|(part, value)| (format!("{key}.{part}"), value_text(value))),
+            ),
+            value => fields.push((key, value_text(value))),
+        }
+    }
+
+    Some(StructuredLine {
+        timestamp,
+        level,
+        message,
+        fields,
+    })
+}
+
+/// Parses a Graylog GELF message. Its level is a syslog severity and the
+/// fields added by the app are prefixed with an underscore.
+fn parse_gelf(mut object: Map<String, Value>) -> StructuredLine {
+    let timestamp = take_first(&mut object, &["timestamp"]);
+    let level = take_first(&mut object, &["level"]).map(|level| match level.parse() {
+        Ok(severity) => severity_level(severity).to_string(),
+        Err(_) => normalize_level(&level).map_or(level, String::from),
+    });
+    let message = take_first(&mut object, &["short_message"]);
+    object.shift_remove("version");
+
+    let fields = object
+        .into_iter()
+        .map(|(key, value)| {
+            let key = key.strip_prefix('_').map_or(key.clone(), String::from);
+            (key, value_text(value))
+        })
+        .collect();
+
+    StructuredLine {
+        timestamp,
+        level,
+        message,
+        fields,
+    }
+}
+
+/// Keys loggers put a serialized error under, like bunyan's `err`.
+const ERROR_KEYS: [&str; 3] = ["err", "error", "exception"];
+
+/// Maps a numeric level onto the level names used by the filters. Small
+/// numbers are taken for syslog severities, anything else for the levels of
+/// bunyan and pino, which go from 10 for trace to 60 for fatal.
+fn numeric_level(level: u64) -> &'static str {
+    match level {
+        0..=7 => severity_level(level as u8),
+        8..=20 => "DEBUG",
+        21..=30 => "INFO",
+        31..=40 => "WARNING",
+        41..=50 => "ERROR",
+        _ => "CRITICAL",
+    }
+}
+
+/// Removes the first of the keys present in the object and returns its value.
+fn take_first(object: &mut Map<String, Value>, keys: &[&str]) -> Option<String> {
+    take_first_value(object, keys).map(value_text)
+}
+
+fn take_first_value(object: &mut Map<String, Value>, keys: &[&str]) -> Option<Value> {
+    keys.iter().find_map(|key| object.shift_remove(*key))
+}
+
+/// Strings are shown without their quotes, anything else as compact JSON.
+fn value_text(value: Value) -> String {
+    match value {
+        Value::String(text) => text,
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(line: &StructuredLine) -> Vec<(&str, &str)> {
+        line.fields
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn splits_a_json_line_into_its_parts() {
+        let line = parse_json(
+            r#"{"ts":"2024-05-03T14:02:10Z","level":"warn","msg":"slow query","ms":350,"tags":["db"]}"#,
+        )
+        .unwrap();
+        assert_eq!(line.timestamp.as_deref(), Some("2024-05-03T14:02:10Z"));
+        assert_eq!(line.level.as_deref(), Some("WARNING"));
+        assert_eq!(line.message.as_deref(), Some("slow query"));
+        assert_eq!(fields(&line), [("ms", "350"), ("tags", r#"["db"]"#)]);
+        assert_eq!(
+            line.display(),
+            r#"2024-05-03T14:02:10Z WARNING slow query ms=350 tags=["db"]"#
+        );
+    }
+
+    #[test]
+    fn keeps_a_level_it_cant_map() {
+        let line = parse_json(r#"{"severity":"audit","message":"login"}"#).unwrap();
+        assert_eq!(line.level.as_deref(), Some("audit"));
+        assert_eq!(line.timestamp, None);
+    }
+
+    #[test]
+    fn leaves_lines_that_arent_an_object() {
+        assert!(parse_json("plain text").is_none());
+        assert!(parse_json("[1, 2]").is_none());
+        assert!(parse_json("{not json").is_none());
+    }
+
+    #[test]
+    fn reads_a_gelf_message() {
+        let line = parse_json(concat!(
+            r#"{"version":"1.1","host":"web-1","short_message":"disk almost full","#,
+            r#""timestamp":1714744930.25,"level":4,"_disk":"/dev/sda1"}"#,
+        ))
+        .unwrap();
+        assert_eq!(line.timestamp.as_deref(), Some("1714744930.25"));
+        assert_eq!(line.level.as_deref(), Some("WARNING"));
+        assert_eq!(line.message.as_deref(), Some("disk almost full"));
+        assert_eq!(fields(&line), [("host", "web-1"), ("disk", "/dev/sda1")]);
+    }
+
+    #[test]
+    fn reads_bunyan_and_pino_lines() {
+        let line = parse_json(concat!(
+            r#"{"name":"api","hostname":"web-1","pid":7,"level":50,"#,
+            r#""err":{"message":"boom","name":"TypeError","stack":"TypeError: boom\n    at f (a.js:1)"},"#,
+            r#""msg":"request failed","time":"2024-05-03T14:02:10.000Z","v":0}"#,
+        ))
+        .unwrap();
+        assert_eq!(line.level.as_deref(), Some("ERROR"));
+        assert_eq!(
+            fields(&line),
+            [
+                ("name", "api"),
+                ("hostname", "web-1"),
+                ("pid", "7"),
+                ("err.message", "boom"),
+                ("err.name", "TypeError"),
+                ("err.stack", "TypeError: boom\n    at f (a.js:1)"),
+            ]
+        );
+        let levels: Vec<Option<String>> = [10, 30, 40, 60]
+            .iter()
+            .map(|level| {
+                parse_json(&format!(r#"{{"level":{level},"msg":"m"}}"#))
+                    .unwrap()
+                    .level
+            })
+            .collect();
+        assert_eq!(
+            levels,
+            [
+                Some("DEBUG"),
+                Some("INFO"),
+                Some("WARNING"),
+                Some("CRITICAL")
+            ]
+            .map(|level| level.map(String::from))
+        );
+    }
+}
@@ -0,0 +1,99 @@
+/// Most places the jump list keeps, dropping the oldest past it.
+const MAX_JUMPS: usize = 100;
+
+/// The lines jumped away from, walked back and forward with `Ctrl-o` and
+/// `Ctrl-i` like vim's jump list. Each is kept by the file it's in and its
+/// number there, like marks, so it's found again whatever the filters show.
+#[derive(Debug, Default)]
+pub(crate) struct JumpList {
+    places: Vec<(usize, usize)>,
+    /// Where in the places the selection is, at their end until walking
+    /// back through them.
+    pos: usize,
+}
+
+impl JumpList {
+    /// Keeps the line jumped away from. Any places walked back past are
+    /// dropped, as a browser drops its forward history.
+    pub(crate) fn record(&mut self, from: (usize, usize)) {
+        self.places.truncate(self.pos);
+        if self.places.last() != Some(&from) {
+            self.places.push(from);
+        }
+        if self.places.len() > MAX_JUMPS {
+            self.places.remove(0);
+        }
+        self.pos = self.places.len();
+    }
+
+    /// The place before the current one. Walking back from the end keeps
+    /// the current line, so walking forward comes back to it.
+    pub(crate) fn back(&mut self, current: (usize, usize)) -> Option<(usize, usize)> {
+        if self.pos == self.places.len() {
+            if self.places.last() == Some(&current) {
+                self.pos -= 1;
+            } else {
+                self.places.push(current);
+            }
+        }
+        self.pos = self.pos.checked_sub(1)?;
+        Some(self.places[self.pos])
+    }
+
+    /// The place after the current one, if the list has been walked back.
+    pub(crate) fn forward(&mut self) -> Option<(usize, usize)> {
+        if self.pos + 1 >= self.places.len() {
+            return None;
+        }
+        self.pos += 1;
+        Some(self.places[self.pos])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walks_back_and_forward_to_the_current_line() {
+        let mut jumps = JumpList::default();
+        jumps.record((0, 1));
+        jumps.record((0, 5));
+        assert_eq!(jumps.back((1, 9)), Some((0, 5)));
+        assert_eq!(jumps.back((0, 5)), Some((0, 1)));
+        assert_eq!(jumps.back((0, 1)), None);
+        assert_eq!(jumps.forward(), Some((0, 5)));
+        assert_eq!(jumps.forward(), Some((1, 9)));
+        assert_eq!(jumps.forward(), None);
+    }
+
+    #[test]
+    fn drops_the_places_walked_back_past_on_a_jump() {
+        let mut jumps = JumpList::default();
+        jumps.record((0, 1));
+        jumps.record((0, 5));
+        jumps.record((0, 8));
+        assert_eq!(jumps.back((0, 8)), Some((0, 5)));
+        jumps.record((0, 5));
+        assert_eq!(jumps.forward(), None);
+        assert_eq!(jumps.back((0, 20)), Some((0, 5)));
+        assert_eq!(jumps.back((0, 5)), Some((0, 1)));
+    }
+
+    #[test]
+    fn keeps_the_latest_places() {
+        let mut jumps = JumpList::default();
+        for number in 0..=MAX_JUMPS {
+            jumps.record((0, number));
+            jumps.record((0, number));
+        }
+        let mut walked = vec![];
+        let mut current = (1, 0);
+        while let Some(place) = jumps.back(current) {
+            walked.push(place);
+            current = place;
+        }
+        assert_eq!(walked.len(), MAX_JUMPS);
+        assert_eq!(walked.last(), Some(&(0, 1)));
+    }
+}
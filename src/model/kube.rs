@@ -0,0 +1,342 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use color_eyre::{Result, eyre::eyre};
+use futures_util::AsyncBufReadExt;
+use k8s_openapi::api::core::v1::Pod;
+use kube::{
+    Api, Client,
+    api::{ListParams, LogParams},
+};
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    pin::pin,
+    time::Duration,
+};
+use tokio::{runtime, task::JoinHandle};
+
+use crate::{LineSink, LineStream, decode_line};
+
+/// How many lines of history to load per container before following.
+const KUBE_HISTORY: i64 = 10000;
+
+/// Upper bound on the pods a label selector streams from at once.
+const MAX_PODS: u32 = 50;
+
+/// How often to reconnect to the containers whose stream ended, and to look
+/// for new pods matching a selector.
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// Status shown while none of the containers are streaming.
+const RECONNECT_STATUS: &str = "log stream ended, reconnecting";
+
+/// The pod, or pods picked by a label selector, to stream logs from.
+#[derive(Debug, Clone)]
+pub(crate) struct KubeTarget {
+    namespace: String,
+    /// A pod name, or a label selector like `app=web` when it contains `=`.
+    pods: String,
+    container: Option<String>,
+}
+
+impl KubeTarget {
+    /// Parses `<namespace>/<pod>[:container]`, where the pod may also be a
+    /// label selector.
+    pub(crate) fn parse(target: &str) -> Result<Self, &'static str> {
+        let usage = "--kube expects <namespace>/<pod>[:container].";
+        let (namespace, rest) = target.split_once('/').ok_or(usage)?;
+        let (pods, container) = match rest.split_once(':') {
+            Some((pods, container)) => (pods, Some(container.to_string())),
+            None => (rest, None),
+        };
+
+        if namespace.is_empty() || pods.is_empty() {
+            return Err(usage);
+        }
+
+        Ok(KubeTarget {
+            namespace: namespace.to_string(),
+            pods: pods.to_string(),
+            container,
+        })
+    }
+
+    pub(crate) fn name(&self) -> String {
+        format!("kube:{}/{}", self.namespace, self.pods)
+    }
+
+    /// Lists the containers to stream from: the one asked for, or all of
+    /// them, in each of the pods.
+    async fn containers(&self, api: &Api<Pod>) -> kube::Result<Vec<Container>> {
+        let pods = if self.pods.contains('=') {
+            let params = ListParams::default().labels(&self.pods).limit(MAX_PODS);
+            api.list(&params).await?.items
+        } else {
+            vec![api.get(&self.pods).await?]
+        };
+
+        let mut containers = vec![];
+        for pod in pods {
+            let Some(name) = pod.metadata.name else {
+                continue;
+            };
+            for container in pod.spec.map(|spec| spec.containers).unwrap_or_default() {
+                if self
+                    .container
+                    .as_ref()
+                    .is_none_or(|only| *only == container.name)
+                {
+                    containers.push(Container {
+                        pod: name.clone(),
+                        name: container.name,
+                    });
+                }
+            }
+        }
+        Ok(containers)
+    }
+}
+
+/// A container of one of the pods followed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Container {
+    pod: String,
+    name: String,
+}
+
+/// Follows the logs of the pods' containers through the Kubernetes API, each
+/// in a stream of its own, tagging each line with the container it's from.
+///
+/// A container whose stream ends, like when it restarts, is reconnected to
+/// and carries on after the last line seen from it. Pods that start matching
+/// a selector later on are picked up too.
+pub(crate) fn kube_stream(target: KubeTarget) -> Result<LineStream> {
+    let runtime = runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    // The cluster is reached here so a missing kubeconfig or pod is reported
+    // before the TUI starts.
+    let (api, containers) = runtime.block_on(async {
+        let client = Client::try_default().await?;
+        let api: Api<Pod> = Api::namespaced(client, &target.namespace);
+        let containers = target.containers(&api).await?;
+        Ok::<_, color_eyre::Report>((api, containers))
+    })?;
+    if containers.is_empty() {
+        return Err(eyre!(
+            "no pods in {} match {}",
+            target.namespace,
+            target.pods
+        ));
+    }
+
+    Ok(LineStream::spawn_thread(move |sink| {
+        runtime.block_on(follow_pods(api, target, containers, sink));
+    }))
+}
+
+/// Keeps a stream going for each container until the stream is dropped.
+async fn follow_pods(
+    api: Api<Pod>,
+    target: KubeTarget,
+    mut containers: Vec<Container>,
+    sink: LineSink,
+) {
+    let mut running: BTreeMap<Container, JoinHandle<Replay>> = BTreeMap::new();
+    let mut ended: BTreeMap<Container, Replay> = BTreeMap::new();
+
+    while !sink.is_stopped() {
+        for container in containers {
+            if let Entry::Vacant(entry) = running.entry(container) {
+                let container = entry.key().clone();
+                let replay = ended.remove(&container).unwrap_or_default();
+                let follow = follow_container(api.clone(), container, replay, sink.clone());
+                entry.insert(tokio::spawn(follow));
+            }
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+
+        let finished: Vec<Container> = running
+            .iter()
+            .filter(|(_, follow)| follow.is_finished())
+            .map(|(container, _)| container.clone())
+            .collect();
+        for container in finished {
+            if let Some(follow) = running.remove(&container)
+                && let Ok(replay) = follow.await
+            {
+                ended.insert(container, replay);
+            }
+        }
+        let status = running.is_empty().then(|| RECONNECT_STATUS.to_string());
+        sink.set_status(status);
+
+        // Failing to list them is retried on the next pass.
+        containers = target.containers(&api).await.unwrap_or_default();
+    }
+}
+
+/// Streams a container's logs until the stream ends, returning how far it
+/// got for the next stream to carry on from.
+async fn follow_container(
+    api: Api<Pod>,
+    container: Container,
+    mut replay: Replay,
+    sink: LineSink,
+) -> Replay {
+    let since = replay.since();
+    let params = LogParams {
+        container: Some(container.name.clone()),
+        follow: true,
+        timestamps: true,
+        tail_lines: since.is_none().then_some(KUBE_HISTORY),
+        since_time: since,
+        ..LogParams::default()
+    };
+    let Ok(logs) = api.log_stream(&container.pod, &params).await else {
+        return replay;
+    };
+
+    let mut logs = pin!(logs);
+    let mut raw = vec![];
+    while matches!(logs.read_until(b'\n', &mut raw).await, Ok(read) if read > 0) {
+        if let Some(line) = replay.take(&container, &decode_line(&raw)) {
+            sink.push(line);
+        }
+        if sink.is_stopped() {
+            break;
+        }
+        raw.clear();
+    }
+    replay
+}
+
+/// The last lines taken from a container: their timestamp, and how many
+/// there were with it.
+#[derive(Debug, Clone, PartialEq)]
+struct LastSeen {
+    time: DateTime<FixedOffset>,
+    count: usize,
+}
+
+/// How far into a container's logs its streams have got.
+///
+/// The API sends the lines since a time given to the second, so a reconnect
+/// drops the lines up to and including the last one seen.
+#[derive(Debug, Default)]
+struct Replay {
+    last_seen: Option<LastSeen>,
+    /// The lines still being sent again this stream.
+    replaying: Option<LastSeen>,
+}
+
+impl Replay {
+    /// The time to ask for the lines since, when reconnecting.
+    fn since(&mut self) -> Option<DateTime<Utc>> {
+        self.replaying = self.last_seen.clone();
+        self.last_seen.as_ref().map(|seen| seen.time.to_utc())
+    }
+
+    /// Takes a line of the container's logs, returning it with the
+    /// container's tag moved in after its timestamp, unless it was already
+    /// taken before the reconnect.
+    fn take(&mut self, container: &Container, line: &str) -> Option<String> {
+        let tag = format!("[{}/{}]", container.pod, container.name);
+        let (stamp, message) = line.split_once(' ').unwrap_or((line, ""));
+        let Ok(time) = DateTime::parse_from_rfc3339(stamp) else {
+            return Some(format!("{tag} {line}"));
+        };
+
+        if let Some(replayed) = &mut self.replaying {
+            if time < replayed.time {
+                return None;
+            }
+            if time == replayed.time && replayed.count > 0 {
+                replayed.count -= 1;
+                return None;
+            }
+            self.replaying = None;
+        }
+
+        match &mut self.last_seen {
+            Some(seen) if seen.time == time => seen.count += 1,
+            _ => self.last_seen = Some(LastSeen { time, count: 1 }),
+        }
+        Some(format!("{stamp} {tag} {message}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take(replay: &mut Replay, container: &Container, lines: &[&str]) -> Vec<String> {
+        lines
+            .iter()
+            .filter_map(|line| replay.take(container, line))
+            .collect()
+    }
+
+    #[test]
+    fn parses_the_target() {
+        let target = KubeTarget::parse("prod/app=api:web").unwrap();
+        assert_eq!(target.namespace, "prod");
+        assert_eq!(target.pods, "app=api");
+        assert_eq!(target.container.as_deref(), Some("web"));
+        assert_eq!(target.name(), "kube:prod/app=api");
+        assert!(KubeTarget::parse("api-1").is_err());
+        assert!(KubeTarget::parse("prod/").is_err());
+    }
+
+    #[test]
+    fn tags_each_line_with_its_container() {
+        let container = Container {
+            pod: String::from("api-1"),
+            name: String::from("app"),
+        };
+        let mut replay = Replay::default();
+        assert_eq!(
+            take(
+                &mut replay,
+                &container,
+                &["2024-05-03T14:02:10Z a", "plain"]
+            ),
+            ["2024-05-03T14:02:10Z [api-1/app] a", "[api-1/app] plain"]
+        );
+    }
+
+    #[test]
+    fn resumes_after_the_last_line_seen() {
+        let container = Container {
+            pod: String::from("api-1"),
+            name: String::from("app"),
+        };
+        let mut replay = Replay::default();
+        assert_eq!(replay.since(), None);
+        let first = [
+            "2024-05-03T14:02:10.5Z a",
+            "2024-05-03T14:02:12.25Z b",
+            "2024-05-03T14:02:12.25Z b",
+        ];
+        assert_eq!(take(&mut replay, &container, &first).len(), 3);
+
+        assert_eq!(
+            replay.since().map(|since| since.to_rfc3339()).as_deref(),
+            Some("2024-05-03T14:02:12.250+00:00")
+        );
+        let again = [
+            "2024-05-03T14:02:10.5Z a",
+            "2024-05-03T14:02:12.25Z b",
+            "2024-05-03T14:02:12.25Z b",
+            "2024-05-03T14:02:12.25Z c",
+            "2024-05-03T14:02:13Z d",
+        ];
+        assert_eq!(
+            take(&mut replay, &container, &again),
+            [
+                "2024-05-03T14:02:12.25Z [api-1/app] c",
+                "2024-05-03T14:02:13Z [api-1/app] d",
+            ]
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use regex::Regex;
+use strip_ansi_escapes::strip;
+
+use crate::{LogEntry, normalize_level};
+
+/// The severity of a line, which drives the level filters and row colors.
+///
+/// Levels are ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Level {
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
+
+impl Level {
+    pub(crate) const ALL: [Level; 5] = [
+        Level::Debug,
+        Level::Info,
+        Level::Warning,
+        Level::Error,
+        Level::Critical,
+    ];
+
+    /// The name the level is shown with, and the one the built in formats
+    /// give it.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warning => "WARNING",
+            Level::Error => "ERROR",
+            Level::Critical => "CRITICAL",
+        }
+    }
+
+    /// The name the level goes by in the config file.
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+            Level::Critical => "critical",
+        }
+    }
+
+    /// Reads a level written in any of the ways common loggers write them,
+    /// like `warn`, `FATAL` or `Information`.
+    pub(crate) fn parse(text: &str) -> Option<Level> {
+        let name = normalize_level(text)?;
+        Level::ALL.into_iter().find(|level| level.name() == name)
+    }
+
+    /// The next more severe level, if there is one.
+    pub(crate) fn raised(self) -> Option<Level> {
+        Level::ALL.get(self as usize + 1).copied()
+    }
+
+    /// The next less severe level, if there is one.
+    pub(crate) fn lowered(self) -> Option<Level> {
+        Level::ALL.get((self as usize).checked_sub(1)?).copied()
+    }
+}
+
+/// Extra ways of writing each level, from the `[levels]` section of the
+/// config file, for apps whose conventions the built in names don't cover.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LevelAliases {
+    /// Words that name a level, matched as written.
+    tokens: Vec<(String, Level)>,
+    /// Regexes that give a plain line the level when they match anywhere in
+    /// it, written between slashes in the config file.
+    patterns: Vec<(Regex, Level)>,
+}
+
+impl LevelAliases {
+    /// Adds an alias for the level, a `/regex/` or a word.
+    pub(crate) fn add(&mut self, alias: &str, level: Level) -> Result<(), regex::Error> {
+        match alias
+            .strip_prefix('/')
+            .and_then(|alias| alias.strip_suffix('/'))
+        {
+            Some(pattern) => self.patterns.push((Regex::new(pattern)?, level)),
+            None => self.tokens.push((alias.to_string(), level)),
+        }
+        Ok(())
+    }
+
+    fn token_level(&self, token: &str) -> Option<Level> {
+        self.tokens
+            .iter()
+            .find(|(alias, _)| alias == token)
+            .map(|(_, level)| *level)
+    }
+
+    fn line_level(&self, line: &str) -> Option<Level> {
+        self.patterns
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(line))
+            .map(|(_, level)| *level)
+    }
+
+    /// Reads a level, trying the aliases before the built in names.
+    fn parse(&self, token: &str) -> Option<Level> {
+        self.token_level(token).or_else(|| Level::parse(token))
+    }
+}
+
+/// Where the level of a plain line is found.
+#[derive(Debug, Default, Clone)]
+pub(crate) enum LevelRule {
+    /// The first word naming a level in capitals, like `WARN` or `[ERROR]`.
+    /// Lower case words are skipped so a message that mentions an error
+    /// isn't taken for one.
+    #[default]
+    FirstToken,
+    /// The nth whitespace separated token, counting from 1.
+    Token(usize),
+    /// The capture named `level`, or else the first capture of the regex.
+    Regex(Regex),
+}
+
+impl LevelRule {
+    /// Parses the argument of `--level-token`.
+    pub(crate) fn token(position: &str) -> Result<LevelRule, &'static str> {
+        match position.parse() {
+            Ok(position) if position > 0 => Ok(LevelRule::Token(position)),
+            _ => Err("--level-token needs a token position, counting from 1."),
+        }
+    }
+
+    /// Parses the argument of `--level-regex`.
+    pub(crate) fn regex(pattern: &str) -> Result<LevelRule, &'static str> {
+        let regex = Regex::new(pattern).map_err(|_| "--level-regex isn't a valid regex.")?;
+        if regex.captures_len() < 2 {
+            return Err("--level-regex needs a capture group around the level.");
+        }
+        Ok(LevelRule::Regex(regex))
+    }
+
+    /// Works out the level of the entry. Structured lines have theirs parsed
+    /// already, plain ones are searched according to the rule unless one of
+    /// the alias patterns matches.
+    pub(crate) fn level_of(&self, entry: &LogEntry, aliases: &LevelAliases) -> Option<Level> {
+        if let Some(structured) = &entry.structured {
+            return aliases.parse(structured.level.as_deref()?);
+        }
+
+        // Colored levels, like `\x1b[31mERROR\x1b[0m`, are matched by their
+        // text.
+        let plain;
+        let line = match entry.line.contains('\x1b') {
+            true => {
+                plain = String::from_utf8(strip(entry.line.as_bytes())).unwrap();
+                plain.as_str()
+            }
+            false => entry.line.as_str(),
+        };
+        if let Some(level) = aliases.line_level(line) {
+            return Some(level);
+        }
+        match self {
+            LevelRule::FirstToken => line.split_whitespace().find_map(|token| {
+                let trimmed = trim_token(token);
+                match aliases.token_level(token).or(aliases.token_level(trimmed)) {
+                    Some(level) => Some(level),
+                    None if trimmed.chars().any(|c| c.is_ascii_lowercase()) => None,
+                    None => Level::parse(trimmed),
+                }
+            }),
+            LevelRule::Token(position) => {
+                let token = line.split_whitespace().nth(position - 1)?;
+                aliases
+                    .token_level(token)
+                    .or_else(|| aliases.parse(trim_token(token)))
+            }
+            LevelRule::Regex(regex) => {
+                let captures = regex.captures(line)?;
+                let level = captures.name("level").or_else(|| captures.get(1))?;
+                aliases.parse(level.as_str().trim())
+            }
+        }
+    }
+}
+
+/// Drops the brackets and punctuation loggers put around a level, like in
+/// `[ERROR]` or `WARN:`.
+fn trim_token(token: &str) -> &str {
+    token.trim_matches(|c: char| !c.is_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StructuredLine;
+
+    fn level(rule: &LevelRule, line: &str) -> Option<Level> {
+        let entry = LogEntry::new(line.to_string(), 0);
+        rule.level_of(&entry, &LevelAliases::default())
+    }
+
+    #[test]
+    fn reads_the_first_level_in_capitals() {
+        let rule = LevelRule::FirstToken;
+        assert_eq!(
+            level(&rule, "14:02:10 [WARN] slow query"),
+            Some(Level::Warning)
+        );
+        assert_eq!(
+            level(&rule, "14:02:10 \x1b[31mFATAL\x1b[0m: out of memory"),
+            Some(Level::Critical)
+        );
+        // A message mentioning an error isn't one.
+        assert_eq!(level(&rule, "14:02:10 retried after error"), None);
+        assert_eq!(level(&rule, "14:02:10 Error: retried"), None);
+    }
+
+    #[test]
+    fn reads_the_level_where_the_rule_says() {
+        let rule = LevelRule::token("3").unwrap();
+        assert_eq!(
+            level(&rule, "14:02:10 api warn: slow"),
+            Some(Level::Warning)
+        );
+        assert_eq!(level(&rule, "14:02:10 ERROR api"), None);
+        assert!(LevelRule::token("0").is_err());
+
+        let rule = LevelRule::regex(r"sev=(?P<level>\w+)").unwrap();
+        assert_eq!(
+            level(&rule, "14:02:10 sev=information ready"),
+            Some(Level::Info)
+        );
+        assert!(LevelRule::regex(r"sev=\w+").is_err());
+    }
+
+    #[test]
+    fn takes_the_level_of_a_structured_line() {
+        let entry = LogEntry::from_structured(
+            StructuredLine {
+                level: Some(String::from("ERROR")),
+                message: Some(String::from("INFO only in the message")),
+                ..StructuredLine::default()
+            },
+            0,
+        );
+        let rule = LevelRule::FirstToken;
+        assert_eq!(
+            rule.level_of(&entry, &LevelAliases::default()),
+            Some(Level::Error)
+        );
+        assert_eq!(Level::parse("Information"), Some(Level::Info));
+        assert!(Level::Debug < Level::Critical);
+    }
+}
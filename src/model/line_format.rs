@@ -0,0 +1,54 @@
+use regex::Regex;
+
+use crate::{LEVEL_KEYS, MESSAGE_KEYS, StructuredLine, TIMESTAMP_KEYS, normalize_level};
+
+/// A line format given as a regex with named captures, like
+/// `^(?P<ts>\S+) \[(?P<level>\w+)\] (?P<msg>.*)$`.
+///
+/// Captures named like a timestamp, level or message fill those in and any
+/// other named capture becomes a field.
+#[derive(Debug, Clone)]
+pub(crate) struct LineFormat {
+    regex: Regex,
+}
+
+impl LineFormat {
+    pub(crate) fn parse(pattern: &str) -> Result<Self, &'static str> {
+        let format =
+            LineFormat::from_regex(pattern).map_err(|_| "--line-format isn't a valid regex.")?;
+        if format.regex.capture_names().flatten().next().is_none() {
+            return Err("--line-format needs at least one named capture, like (?P<level>\\w+).");
+        }
+        Ok(format)
+    }
+
+    pub(crate) fn from_regex(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(LineFormat {
+            regex: Regex::new(pattern)?,
+        })
+    }
+
+    /// Splits the line into its captures, or returns `None` if it doesn't
+    /// match, like the lines of a stack trace.
+    pub(crate) fn parse_line(&self, line: &str) -> Option<StructuredLine> {
+        let captures = self.regex.captures(line)?;
+        let mut structured = StructuredLine::default();
+
+        for name in self.regex.capture_names().flatten() {
+            let Some(value) = captures.name(name).map(|value| value.as_str().to_string()) else {
+                continue;
+            };
+            let key = name.to_lowercase();
+            if TIMESTAMP_KEYS.contains(&key.as_str()) && structured.timestamp.is_none() {
+                structured.timestamp = Some(value);
+            } else if LEVEL_KEYS.contains(&key.as_str()) && structured.level.is_none() {
+                structured.level = Some(normalize_level(&value).map_or(value, String::from));
+            } else if MESSAGE_KEYS.contains(&key.as_str()) && structured.message.is_none() {
+                structured.message = Some(value);
+            } else {
+                structured.fields.push((name.to_string(), value));
+            }
+        }
+        Some(structured)
+    }
+}
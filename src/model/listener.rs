@@ -0,0 +1,73 @@
+use color_eyre::Result;
+use std::{
+    io::{BufRead, BufReader},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use crate::{LineSink, LineStream};
+
+/// How long socket calls block before checking if the stream was dropped.
+pub(crate) const SOCKET_POLL: Duration = Duration::from_millis(250);
+
+/// Parses the address for `--listen`, with or without a `tcp://` scheme.
+pub(crate) fn parse_tcp_addr(addr: &str) -> Result<String, &'static str> {
+    let addr = match addr.split_once("://") {
+        Some(("tcp", addr)) => addr,
+        Some(_) => return Err("--listen only accepts tcp:// addresses."),
+        None => addr,
+    };
+
+    if addr.is_empty() {
+        return Err("Listen addresses need a host and port.");
+    }
+    Ok(addr.to_string())
+}
+
+/// Accepts any number of tcp clients and streams the lines each one sends,
+/// tagged with the client's address.
+pub(crate) fn tcp_stream(addr: &str) -> Result<LineStream> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    Ok(LineStream::spawn_thread(move |sink| {
+        accept_loop(listener, sink, receive_lines)
+    }))
+}
+
+/// Accepts connections until the stream is dropped, handling each one on its
+/// own thread.
+pub(crate) fn accept_loop(listener: TcpListener, sink: LineSink, handle: fn(TcpStream, LineSink)) {
+    while !sink.is_stopped() {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                // Accepted sockets inherit non-blocking mode from the listener.
+                if stream.set_nonblocking(false).is_ok() {
+                    let sink = sink.clone();
+                    thread::spawn(move || handle(stream, sink));
+                }
+            }
+            Err(_) => thread::sleep(SOCKET_POLL),
+        }
+    }
+}
+
+fn receive_lines(stream: TcpStream, sink: LineSink) {
+    let peer = stream
+        .peer_addr()
+        .map(|addr| addr.to_string())
+        .unwrap_or(String::from("unknown"));
+
+    let mut reader = BufReader::new(stream);
+    let mut line = vec![];
+    while !sink.is_stopped() {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&line);
+                sink.push(format!("[{peer}] {}", text.trim_end_matches(['\n', '\r'])));
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use bzip2::read::MultiBzDecoder;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use flate2::read::MultiGzDecoder;
+use std::io::{self, Read};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const BZIP2_MAGIC: [u8; 3] = *b"BZh";
+
+/// How much of a log is looked at to guess its encoding.
+const SNIFF_LEN: usize = 4096;
+
+/// Decodes the contents of a log file into a string, decompressing it first
+/// if it's a gzip, zstd or bzip2 archive.
+///
+/// The format is picked from the file's magic bytes rather than its extension,
+/// so rotated files like `app.log.1.gz` and misnamed ones both open.
+///
+/// The text is decoded with the given encoding, or one guessed from the
+/// contents when there's none.
+pub(crate) fn decode_log(
+    bytes: Vec<u8>,
+    encoding: Option<&'static Encoding>,
+) -> io::Result<String> {
+    let decoded = decompress(bytes)?;
+    let encoding = encoding.unwrap_or_else(|| guess_encoding(&decoded));
+    // A byte order mark wins over the encoding, and is dropped from the text.
+    let (text, _, _) = encoding.decode(&decoded);
+    Ok(text.into_owned())
+}
+
+/// Returns true if the bytes start like a gzip, zstd or bzip2 archive.
+pub(crate) fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.starts_with(&GZIP_MAGIC)
+        || bytes.starts_with(&ZSTD_MAGIC)
+        || bytes.starts_with(&BZIP2_MAGIC)
+}
+
+/// Decompresses the bytes if they're an archive, see `decode_log`.
+pub(crate) fn decompress(bytes: Vec<u8>) -> io::Result<Vec<u8>> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        decode(MultiGzDecoder::new(bytes.as_slice()))
+    } else if bytes.starts_with(&ZSTD_MAGIC) {
+        zstd::decode_all(bytes.as_slice())
+    } else if bytes.starts_with(&BZIP2_MAGIC) {
+        decode(MultiBzDecoder::new(bytes.as_slice()))
+    } else {
+        Ok(bytes)
+    }
+}
+
+/// Guesses the encoding of a log without a byte order mark. Windows services
+/// like to write UTF-16, which shows up as every other byte being zero for
+/// mostly ASCII text. Anything that isn't valid UTF-8 is taken to be Latin-1,
+/// which decodes every byte to something.
+pub(crate) fn guess_encoding(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    let zeros_at = |parity| {
+        sample
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|byte| **byte == 0)
+            .count()
+    };
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    let half = sample.len() / 2;
+    if half > 0 && odd > half / 3 && even == 0 {
+        return UTF_16LE;
+    }
+    if half > 0 && even > half / 3 && odd == 0 {
+        return UTF_16BE;
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(_) => encoding_rs::UTF_8,
+        // A multibyte character cut off at the end of a file that's still
+        // being written is still UTF-8.
+        Err(error) if error.error_len().is_none() => encoding_rs::UTF_8,
+        Err(_) => WINDOWS_1252,
+    }
+}
+
+fn decode(mut reader: impl Read) -> io::Result<Vec<u8>> {
+    let mut decoded = vec![];
+    reader.read_to_end(&mut decoded)?;
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| match big_endian {
+                true => unit.to_be_bytes(),
+                false => unit.to_le_bytes(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn guesses_utf16_without_a_byte_order_mark() {
+        let text = "2024-05-03 14:02:10 Service started\r\n";
+        assert_eq!(guess_encoding(&utf16(text, false)), UTF_16LE);
+        assert_eq!(guess_encoding(&utf16(text, true)), UTF_16BE);
+        assert_eq!(decode_log(utf16(text, false), None).unwrap(), text);
+    }
+
+    #[test]
+    fn drops_the_byte_order_mark() {
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend(utf16("ready", false));
+        assert_eq!(decode_log(bytes, None).unwrap(), "ready");
+        assert_eq!(
+            decode_log(b"\xef\xbb\xbfready".to_vec(), None).unwrap(),
+            "ready"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_latin1_for_anything_but_utf8() {
+        assert_eq!(
+            decode_log(b"caf\xe9 ouvert".to_vec(), None).unwrap(),
+            "café ouvert"
+        );
+        // The last character is still being written.
+        assert_eq!(
+            guess_encoding("café".as_bytes().split_last().unwrap().1),
+            encoding_rs::UTF_8
+        );
+        assert_eq!(
+            decode_log("café".as_bytes().to_vec(), Some(WINDOWS_1252)).unwrap(),
+            "cafÃ©"
+        );
+    }
+}
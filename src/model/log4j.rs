@@ -0,0 +1,188 @@
+use regex::escape;
+
+use crate::LineFormat;
+
+/// Default layout of `%d`, logback's ISO 8601 style `2024-05-01 10:00:00,123`.
+const DEFAULT_DATE: &str = r"\d{4}-\d{2}-\d{2}[ T]\d{2}:\d{2}:\d{2}(?:[,.]\d{3})?";
+
+/// Compiles a log4j or logback layout pattern like `%d [%t] %-5p %c - %m%n`
+/// into a line format, so the level, logger and thread can be read out of
+/// each line.
+pub(crate) fn parse_log4j_pattern(pattern: &str) -> Result<LineFormat, &'static str> {
+    let mut regex = String::from("^");
+    let mut names: Vec<String> = vec![];
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '%' if chars.peek() == Some(&'%') => {
+                chars.next();
+                regex.push('%');
+            }
+            '%' => {
+                // Width and truncation modifiers like `-5` or `.30` pad the
+                // value with spaces.
+                let mut padded = false;
+                while chars
+                    .next_if(|c| matches!(c, '-' | '.' | '0'..='9'))
+                    .is_some()
+                {
+                    padded = true;
+                }
+
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(char::is_ascii_alphabetic) {
+                    word.push(c);
+                }
+                let mut option = None;
+                if chars.next_if_eq(&'{').is_some() {
+                    option = Some(chars.by_ref().take_while(|c| *c != '}').collect::<String>());
+                }
+
+                let Some((name, value)) = conversion(&word, option.as_deref())? else {
+                    continue;
+                };
+                if padded {
+                    regex.push_str(" *");
+                }
+                regex.push_str(&format!("(?P<{}>{value})", unique_name(&mut names, name)));
+                if padded {
+                    regex.push_str(" *");
+                }
+            }
+            // Spacing in the pattern often lines up with padded values.
+            ' ' => regex.push_str(" +"),
+            c => regex.push_str(&escape(&c.to_string())),
+        }
+    }
+
+    LineFormat::from_regex(&regex).map_err(|_| "--log4j-pattern couldn't be turned into a parser.")
+}
+
+/// Returns the capture name and regex for a conversion word, or `None` for the
+/// ones that don't show up on the line, like `%n`.
+fn conversion(word: &str, option: Option<&str>) -> Result<Option<(String, String)>, &'static str> {
+    let capture = |name: &str, value: &str| Ok(Some((name.to_string(), value.to_string())));
+    match word {
+        "d" | "date" => capture(
+            "timestamp",
+            &option.map_or(DEFAULT_DATE.to_string(), date_regex),
+        ),
+        "p" | "le" | "level" => capture("level", r"[A-Za-z]+"),
+        "t" | "thread" => capture("thread", r".+?"),
+        "c" | "lo" | "logger" => capture("logger", r"\S+"),
+        "C" | "class" => capture("class", r"\S+"),
+        "M" | "method" => capture("method", r"\S+"),
+        "F" | "file" => capture("file", r"\S+"),
+        "L" | "line" => capture("line", r"\d+"),
+        "r" | "relative" => capture("relative", r"\d+"),
+        "X" | "mdc" => capture(option.unwrap_or("mdc"), r".*?"),
+        "m" | "msg" | "message" => capture("message", r".*"),
+        "n" | "ex" | "exception" | "throwable" | "xEx" | "wEx" => Ok(None),
+        "" => Err("--log4j-pattern has a % without a conversion word."),
+        _ => capture(word, r"\S+"),
+    }
+}
+
+/// Turns a java date layout like `yyyy-MM-dd HH:mm:ss.SSS` into a regex. The
+/// named layouts log4j knows are mapped to theirs.
+fn date_regex(layout: &str) -> String {
+    match layout {
+        "ISO8601" | "DEFAULT" => return DEFAULT_DATE.to_string(),
+        "ABSOLUTE" => return r"\d{2}:\d{2}:\d{2}[,.]\d{3}".to_string(),
+        "DATE" => return r"\d{2} [A-Za-z]{3} \d{4} \d{2}:\d{2}:\d{2}[,.]\d{3}".to_string(),
+        _ => {}
+    }
+
+    let mut regex = String::new();
+    let mut chars = layout.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut run = 1;
+        while chars.next_if_eq(&c).is_some() {
+            run += 1;
+        }
+        match c {
+            'M' if run >= 3 => regex.push_str("[A-Za-z]+"),
+            'E' | 'a' => regex.push_str("[A-Za-z]+"),
+            'y' | 'M' | 'd' | 'H' | 'h' | 'm' | 's' | 'S' | 'k' | 'K' => {
+                regex.push_str(&format!(r"\d{{1,{run}}}"))
+            }
+            'X' | 'Z' | 'z' => regex.push_str(r"(?:Z|[+-]\d{2}:?\d{2}|[A-Za-z]+)"),
+            '\'' => {}
+            c => regex.push_str(&escape(&c.to_string()).repeat(run)),
+        }
+    }
+    regex
+}
+
+/// Capture names have to be unique and made of word characters, so the
+/// second `%X{id}` becomes `id2`.
+fn unique_name(names: &mut Vec<String>, name: String) -> String {
+    let base: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let mut unique = base.clone();
+    let mut n = 2;
+    while names.contains(&unique) {
+        unique = format!("{base}{n}");
+        n += 1;
+    }
+    names.push(unique.clone());
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StructuredLine;
+
+    fn parse(pattern: &str, line: &str) -> Option<StructuredLine> {
+        parse_log4j_pattern(pattern).unwrap().parse_line(line)
+    }
+
+    #[test]
+    fn reads_lines_written_with_the_pattern() {
+        let line = parse(
+            "%d [%t] %-5p %c - %m%n",
+            "2024-05-03 14:02:10,123 [http-nio-8080-exec-1] WARN  com.acme.Orders - slow query",
+        )
+        .unwrap();
+        assert_eq!(line.timestamp.as_deref(), Some("2024-05-03 14:02:10,123"));
+        assert_eq!(line.level.as_deref(), Some("WARNING"));
+        assert_eq!(line.message.as_deref(), Some("slow query"));
+        assert_eq!(
+            line.fields,
+            [
+                (String::from("thread"), String::from("http-nio-8080-exec-1")),
+                (String::from("logger"), String::from("com.acme.Orders")),
+            ]
+        );
+    }
+
+    #[test]
+    fn turns_date_layouts_and_mdc_keys_into_captures() {
+        let line = parse(
+            "%d{dd MMM yyyy HH:mm:ss} %X{user} %X{user} %p %m",
+            "03 May 2024 14:02:10 bob alice ERROR failed",
+        )
+        .unwrap();
+        assert_eq!(line.timestamp.as_deref(), Some("03 May 2024 14:02:10"));
+        assert_eq!(line.level.as_deref(), Some("ERROR"));
+        assert_eq!(
+            line.fields,
+            [
+                (String::from("user"), String::from("bob")),
+                (String::from("user2"), String::from("alice")),
+            ]
+        );
+        assert!(parse("%d{ABSOLUTE} %m", "14:02:10.250 ready").is_some());
+        assert!(parse("%d %p %m", "    at com.acme.Orders.get").is_none());
+    }
+
+    #[test]
+    fn needs_a_conversion_word_after_each_percent() {
+        assert!(parse_log4j_pattern("%d %").is_err());
+        assert!(parse_log4j_pattern("100%% %m").is_ok());
+    }
+}
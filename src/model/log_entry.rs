@@ -0,0 +1,80 @@
+use chrono::NaiveDateTime;
+
+use crate::{Level, StructuredLine};
+
+/// A single line of a log along with the file it was read from.
+#[derive(Debug, Default, Clone)]
+pub struct LogEntry {
+    /// The text shown in the list. Structured lines are rendered so they read
+    /// like plain ones.
+    pub line: String,
+    /// Index into the model's log paths.
+    pub source: usize,
+    /// The parts of the line, when it's in a structured format.
+    pub(crate) structured: Option<StructuredLine>,
+    /// Lines that belong to this one, like a stack trace under an error.
+    pub(crate) continuation: Vec<String>,
+    /// When the line was logged, if it says.
+    pub(crate) timestamp: Option<NaiveDateTime>,
+    /// The severity of the line, if it has one.
+    pub(crate) level: Option<Level>,
+    /// The line as it was read, for structured lines parsed by the viewer,
+    /// so they can be parsed again in another format.
+    pub(crate) original: Option<String>,
+    /// Fields pulled out of the line by the `--extract` regexes.
+    pub(crate) extracted: Vec<(String, String)>,
+    /// How many lines in a row like this one it stands for, when repeats
+    /// are collapsed.
+    pub(crate) repeats: usize,
+    /// Set when the line is only shown for being near one the filters let
+    /// through.
+    pub(crate) context: bool,
+    /// Set on a line grouped under the entry above it, when it's shown as a
+    /// row of its own.
+    pub(crate) continued: bool,
+    /// Where the line is in the file it was read from, counting from 1.
+    pub(crate) number: usize,
+}
+
+impl LogEntry {
+    /// Creates an entry for a plain line. The viewer works out its format
+    /// when it's received.
+    pub fn new(line: String, source: usize) -> Self {
+        LogEntry {
+            line,
+            source,
+            ..LogEntry::default()
+        }
+    }
+
+    /// Returns the lines the entry was made of, as they were read.
+    pub(crate) fn into_lines(self) -> Vec<LogEntry> {
+        let source = self.source;
+        let head = match self.original {
+            Some(original) => LogEntry::new(original, source),
+            // Structured lines that came from the source, like the rows of
+            // a CSV file, can't be read any other way.
+            None => LogEntry {
+                continuation: vec![],
+                ..self.clone()
+            },
+        };
+        [head]
+            .into_iter()
+            .chain(
+                self.continuation
+                    .into_iter()
+                    .map(|line| LogEntry::new(line, source)),
+            )
+            .collect()
+    }
+
+    pub(crate) fn from_structured(structured: StructuredLine, source: usize) -> Self {
+        LogEntry {
+            line: structured.display(),
+            source,
+            structured: Some(structured),
+            ..LogEntry::default()
+        }
+    }
+}
@@ -1,20 +1,41 @@
-use rust_fuzzy_search::fuzzy_search_threshold;
-use std::fs;
-
-use crate::{Config, Message};
+use crate::{
+    CaseMode, Command, Context, Dedup, FileSource, FilterExpr, FilterStage, Filters, Fuzzy,
+    GapThreshold, Goto, History, JumpList, Level, LineStream, LogEntry, LogFormat, LogSource,
+    LoggerPicker, MarkKey, Marks, Matcher, Message, Minimap, NewLines, ParseOptions, Preset,
+    SAMPLE_RATES, Sampler, SavedPosition, SearchCache, SearchKind, SearchStyle, StreamSource,
+    TimeRange, TimestampDetector, collapse_repeats, hot_starts, hottest_line, line_template,
+    logger_of, marked_lines, minimap_rows, pop_stage, push_grouped, quick_filter_tokens,
+    record_stage, word_search,
+};
+use chrono::{NaiveDate, NaiveDateTime};
 use color_eyre::Result;
+use ratatui::layout::Rect;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
+use std::ops::Range;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Default, PartialEq, Eq)]
-pub(crate) enum Filter {
-    INFO,
-    WARNING,
-    ERROR,
-    CRITICAL,
-    DEBUG,
-    SELECT,
-    #[default]
-    NONE,
-}
+/// How long typing into the search of a large log has to pause before it's
+/// run.
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Logs with fewer lines than this are searched on every key.
+const DEBOUNCE_LINES: usize = 50_000;
+
+/// How many columns `h` and `l` scroll the lines sideways.
+const SCROLL_STEP: usize = 8;
+
+/// How many rows a turn of the mouse wheel scrolls.
+const WHEEL_ROWS: usize = 3;
+
+/// The least of the width either the list or the preview is resized down
+/// to, in percent.
+const MIN_PANE_PERCENT: u16 = 20;
+
+/// How many searches can be pinned as highlights at once.
+pub(crate) const MAX_PINS: usize = 6;
 
 #[derive(Debug, Default, PartialEq, Eq)]
 pub(crate) enum RunningState {
@@ -26,111 +47,1428 @@ pub(crate) enum RunningState {
 #[derive(Debug, Default, PartialEq, Eq)]
 pub(crate) enum SearchMode {
     Search,
+    /// Typing into a prompt, which takes effect once it's submitted.
+    Prompt(Prompt),
     #[default]
     None,
 }
 
+/// What the text typed into the prompt is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Prompt {
+    /// A regex lines have to match to be shown.
+    Regex,
+    /// A regex hiding the lines it matches, added to the ones already set.
+    Exclude,
+    /// The interval lines have to be logged in to be shown.
+    TimeRange,
+    /// A boolean expression lines have to match to be shown.
+    Expression,
+    /// A comparison on a field of structured lines, added to the ones
+    /// already set.
+    Field,
+    /// The name to save the current filters under as a preset.
+    PresetName,
+    /// A command, like a query to filter on.
+    Command,
+    /// Text to find within the selected line in the preview, like a frame of
+    /// a long stack trace.
+    PreviewSearch,
+    /// A search run across every open tab.
+    TabSearch,
+}
+
+impl Prompt {
+    /// What the prompt's entries are filed under in the history, for the
+    /// prompts worth remembering.
+    fn history_kind(self) -> Option<&'static str> {
+        match self {
+            Prompt::Regex => Some("regex"),
+            Prompt::Exclude => Some("exclude"),
+            Prompt::TimeRange => Some("time"),
+            Prompt::Expression => Some("expression"),
+            Prompt::Field => Some("field"),
+            Prompt::Command => Some("command"),
+            Prompt::PreviewSearch => Some("preview"),
+            Prompt::TabSearch => Some("tabs"),
+            Prompt::PresetName => None,
+        }
+    }
+
+    /// Whether `.` applies what was submitted to the prompt again.
+    fn repeatable(self) -> bool {
+        matches!(
+            self,
+            Prompt::Regex
+                | Prompt::Exclude
+                | Prompt::TimeRange
+                | Prompt::Expression
+                | Prompt::Field
+                | Prompt::Command
+        )
+    }
+
+    pub(crate) fn title(self) -> &'static str {
+        match self {
+            Prompt::Regex => "regex filter",
+            Prompt::Exclude => "exclude (empty clears all)",
+            Prompt::TimeRange => "time range (:from 14:02 :to 14:07)",
+            Prompt::Expression => "expression (level>=WARN && msg ~ \"timeout\")",
+            Prompt::Field => "field filter (status>=500, empty clears all)",
+            Prompt::Command => "command (q level>=ERROR and msg contains \"deadlock\")",
+            Prompt::PreviewSearch => "search the preview (empty clears it)",
+            Prompt::TabSearch => "search every tab",
+            Prompt::PresetName => "save preset as",
+        }
+    }
+}
+
+/// How the lines of a model reading several files are put together.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MergeMode {
+    /// Interleave the files by the timestamp on each line.
+    Chronological,
+    /// Read the files one after another, like the members of a rotated log.
+    #[default]
+    Concatenate,
+}
+
+/// How many lines the filters and search let through, out of how many, as
+/// of the last time the lines were filtered.
+#[derive(Debug, Default)]
+pub(crate) struct MatchCounts {
+    pub(crate) matched: usize,
+    pub(crate) total: usize,
+    /// The lines let through of each level.
+    pub(crate) levels: BTreeMap<Level, usize>,
+}
+
+/// Where the selected line is among the lines the search matches, as of the
+/// last time the lines were filtered.
+#[derive(Debug, Default)]
+pub(crate) struct SearchPosition {
+    /// Counting from 1, when the selected line is a match.
+    pub(crate) current: Option<usize>,
+    pub(crate) total: usize,
+}
+
+/// What the search did to the lines.
+#[derive(Debug)]
+enum Searched {
+    Nothing,
+    /// Only the matches are left, while the search is being typed.
+    Filtered,
+    /// Every line is left, with the indices of the matches.
+    Marked(Vec<usize>),
+}
+
+/// What the lines listed are worked out from, to tell when they have to be
+/// filtered and searched again.
+#[derive(Debug, PartialEq)]
+struct ListingKey {
+    logs_changes: usize,
+    filters: Filters,
+    case: CaseMode,
+    context: Context,
+    dedup: Dedup,
+    expanded_repeats: BTreeSet<String>,
+    expand_groups: bool,
+    expanded_groups: BTreeSet<(usize, usize)>,
+    narrowed: Vec<String>,
+    search: String,
+    search_kind: SearchKind,
+    fuzzy: Fuzzy,
+    search_filters: bool,
+}
+
+impl ListingKey {
+    fn of(model: &Model) -> ListingKey {
+        ListingKey {
+            logs_changes: model.logs_changes,
+            filters: model.filters.clone(),
+            case: model.case,
+            context: model.context,
+            dedup: model.dedup,
+            expanded_repeats: model.expanded_repeats.clone(),
+            expand_groups: model.expand_groups,
+            expanded_groups: model.expanded_groups.clone(),
+            narrowed: model
+                .narrowed
+                .iter()
+                .map(|(search, _)| search.clone())
+                .collect(),
+            search: model.applied_search.clone(),
+            search_kind: model.search_kind,
+            fuzzy: model.fuzzy,
+            search_filters: model.search_filters(),
+        }
+    }
+}
+
+/// The lines listed, as they're laid out in rows, kept until what they're
+/// worked out from changes.
+#[derive(Debug)]
+struct Listing {
+    key: ListingKey,
+    rows: Rc<Vec<LogEntry>>,
+    searched: Searched,
+}
+
 #[derive(Debug, Default)]
 pub(crate) struct Model {
     view_offset: usize,
     view_height: usize,
     pub(crate) line_idx: usize,
-    g_modifier: bool,
+    pub(crate) g_modifier: bool,
     pub(crate) search_mode: SearchMode,
     pub(crate) search_input: String,
+    /// How the search is matched.
+    pub(crate) search_kind: SearchKind,
+    /// Set when the search was started with `?`, so `n` goes up the log
+    /// toward older lines instead of down.
+    pub(crate) search_backward: bool,
+    /// How fuzzy searches are scored and how closely they have to match.
+    pub(crate) fuzzy: Fuzzy,
+    /// Why the search can't be matched, like an invalid regex.
+    pub(crate) search_error: Option<String>,
+    /// Set while there's a search.
+    pub(crate) search_position: Option<SearchPosition>,
+    /// How far through the lines listed the bottom of the view is, from 0
+    /// to 100, like less shows it. Unset when no lines are listed.
+    pub(crate) percent_through: Option<usize>,
+    /// Where the first line in view is among the lines listed, to number
+    /// them in the gutter.
+    pub(crate) first_shown: usize,
+    pub(crate) gutter: Gutter,
+    /// The search the lines were last searched for, which lags behind
+    /// what's typed into a search of a large log until typing pauses.
+    applied_search: String,
+    /// When the search was last typed into.
+    search_edited: Option<Instant>,
+    /// What the search does once it's kept.
+    pub(crate) search_style: SearchStyle,
+    /// The lines the last exact search ruled out.
+    search_cache: Option<SearchCache>,
+    /// The searches narrowed down into, from the first, each with what it
+    /// was typed as. Only the lines they all match are searched.
+    pub(crate) narrowed: Vec<(String, Matcher)>,
+    /// What earlier searches matched, highlighted in colors of their own, from
+    /// the first pinned.
+    pub(crate) pinned: Vec<Regex>,
+    /// Text found within the selected line in the preview.
+    pub(crate) preview_search: Option<String>,
+    /// Which of the places the preview search occurs is selected.
+    pub(crate) preview_match: usize,
+    /// How many places the preview search occurs, as of the last time the
+    /// preview was drawn.
+    pub(crate) preview_matches: usize,
+    /// What's typed into the prompt, kept apart from the search so both can
+    /// be in use.
+    pub(crate) prompt_input: String,
+    /// Why the prompt couldn't be submitted, like an invalid regex.
+    pub(crate) prompt_error: Option<String>,
     pub(crate) cursor_pos: usize,
-    pub(crate) log_path: String,
-    pub(crate) log_filter: Filter,
-    pub(crate) running: RunningState,
-    logs: Vec<String>,
+    /// The history entry brought into the prompt or search, if one is.
+    history_pos: Option<usize>,
+    /// What was typed before going through the history, to get back to.
+    history_draft: String,
+    /// Names of the logs the source reads, indexed by `LogEntry::source`.
+    pub(crate) log_paths: Vec<String>,
+    /// Set when the lines are rows of a table, like a CSV file.
+    pub(crate) columns: Option<Vec<String>>,
+    /// What lines are shown.
+    pub(crate) filters: Filters,
+    /// The changes made to the filters, to show them as a chain and undo
+    /// them one at a time.
+    pub(crate) filter_stages: Vec<FilterStage>,
+    /// Whether case counts in the search and plain text filters, set for
+    /// every tab at once.
+    pub(crate) case: CaseMode,
+    /// How many lines are hidden by the lines hidden while reading, as of
+    /// the last time the lines were filtered.
+    pub(crate) hidden_count: usize,
+    pub(crate) counts: MatchCounts,
+    /// Set while only some of the lines coming in are kept.
+    pub(crate) sampler: Option<Sampler>,
+    /// How many lines around the ones the filters let through are shown.
+    pub(crate) context: Context,
+    /// Which lines in a row are collapsed into one.
+    pub(crate) dedup: Dedup,
+    /// The first lines of the collapsed runs opened back up.
+    expanded_repeats: BTreeSet<String>,
+    /// The file and number of the entries whose grouped lines are opened up
+    /// under them.
+    expanded_groups: BTreeSet<(usize, usize)>,
+    /// Whether the keys pick levels to filter on.
+    pub(crate) filter_menu: bool,
+    /// Set while loggers are being picked to filter on.
+    pub(crate) logger_picker: Option<LoggerPicker>,
+    pub(crate) follow: bool,
+    /// Set once the selection moves off the newest line while following, so
+    /// new lines don't pull it back down until `G` or `F` is pressed.
+    pub(crate) follow_paused: bool,
+    /// Show the lines grouped under an entry, like a stack trace, as rows of
+    /// their own.
+    pub(crate) expand_groups: bool,
+    /// Show lines as plain text instead of in the colors their escape
+    /// sequences ask for.
+    pub(crate) strip_colors: bool,
+    /// The choice highlighted in the format picker, while it's shown.
+    pub(crate) format_picker: Option<usize>,
+    /// Lines marked with a letter to come back to.
+    marks: Marks,
+    /// Lines kept in sight above the others, in the order they were pinned.
+    pub(crate) pinned_lines: Vec<LogEntry>,
+    /// The last change to the filters or jump, for `.` to do again.
+    last_action: Option<Message>,
+    /// The last move to the next or previous of something, for `;` and `,`.
+    last_motion: Option<Message>,
+    /// The lines jumped away from, to walk back to.
+    jumps: JumpList,
+    /// The file and number of the selected line as it was last drawn, the
+    /// place a jump leaves.
+    selected_line: Option<(usize, usize)>,
+    /// How many columns the lines are scrolled to the left.
+    pub(crate) scroll_x: usize,
+    /// How far the widest line shown runs past the logs, as far as they
+    /// scroll.
+    overflow: usize,
+    /// Where the parts of the tab were last drawn.
+    pub(crate) areas: Areas,
+    /// Set when the view changes height, until the selected line is found
+    /// again.
+    resized: bool,
+    /// Whether the minimap is shown at the right edge.
+    pub(crate) show_minimap: bool,
+    /// How hot each stretch of the lines is, as the minimap was last drawn.
+    pub(crate) minimap: Minimap,
+    /// The line the file was left at and the row it was on, selected again
+    /// once the view's height is known.
+    restored_line: Option<(usize, usize)>,
+    /// How much of the width the list takes, in percent, once the divider
+    /// between it and the preview has been dragged.
+    pub(crate) list_percent: Option<u16>,
+    /// Set while the divider is being dragged.
+    pub(crate) resizing: bool,
+    /// Set after `m` or `'`, until the letter of the mark is typed.
+    pub(crate) pending_mark: Option<MarkKey>,
+    /// Set after `]` or `[`, until the level or gap to jump to is typed.
+    pub(crate) pending_jump: Option<Jump>,
+    /// Set after `z`, until where to scroll the selected line to is typed.
+    pub(crate) pending_align: bool,
+    /// How long the log goes quiet for `]g` and `[g` to stop there.
+    pub(crate) gap_threshold: GapThreshold,
+    /// How many rows the view keeps between the selection and its edges,
+    /// where the lines go on past them.
+    pub(crate) scrolloff: usize,
+    /// The number typed before a key, like the 50 of `50%`.
+    pub(crate) pending_count: Option<usize>,
+    /// The selected mark while the marks are listed.
+    pub(crate) mark_list: Option<usize>,
+    /// The format guessed from the first lines of the log.
+    pub(crate) detected_format: Option<LogFormat>,
+    source: Option<Box<dyn LogSource>>,
+    merge_mode: MergeMode,
+    parse_options: ParseOptions,
+    timestamps: TimestampDetector,
+    /// How many lines have been read from each source, to number them.
+    line_counts: Vec<usize>,
+    logs: Vec<LogEntry>,
+    /// Counts the changes to the lines read, for the listing to tell when
+    /// it's out of date.
+    logs_changes: usize,
+    /// The lines listed as of the last time they were filtered and searched.
+    listing: Option<Listing>,
 }
 
 impl Model {
-    pub(crate) fn new(config: Config) -> Result<Model> {
+    /// Creates a model over the given files. When there's more than one they're
+    /// joined into a single stream according to the merge mode.
+    pub(crate) fn new(
+        log_paths: &[String],
+        merge_mode: MergeMode,
+        options: &ParseOptions,
+    ) -> Result<Model> {
+        let source = FileSource::new(log_paths, options.level_column.clone(), options.encoding);
+        Model::open(Box::new(source), merge_mode, options)
+    }
+
+    /// Creates a model fed by a stream of lines, shown under the given name.
+    pub(crate) fn from_stream(
+        name: String,
+        stream: LineStream,
+        options: &ParseOptions,
+    ) -> Result<Model> {
+        Model::from_source(Box::new(StreamSource::new(name, stream)), options)
+    }
+
+    /// Opens the source and reads what it already holds.
+    pub(crate) fn from_source(source: Box<dyn LogSource>, options: &ParseOptions) -> Result<Model> {
+        Model::open(source, MergeMode::default(), options)
+    }
+
+    fn open(
+        mut source: Box<dyn LogSource>,
+        merge_mode: MergeMode,
+        options: &ParseOptions,
+    ) -> Result<Model> {
+        source.open()?;
         let mut model = Model {
-            view_offset: 0,
-            view_height: 0,
-            line_idx: 0,
-            g_modifier: false,
-            search_mode: SearchMode::default(),
-            search_input: String::new(),
-            cursor_pos: 0,
-            log_path: config.file_path.clone(),
-            log_filter: Filter::NONE,
-            running: RunningState::default(),
-            logs: vec![],
+            log_paths: source.metadata().names,
+            source: Some(source),
+            merge_mode,
+            parse_options: options.clone(),
+            sampler: options.sample_rate.map(Sampler::new),
+            ..Model::default()
         };
 
         model.refresh_logs();
         Ok(model)
     }
 
+    fn matcher(&self) -> Result<Matcher, String> {
+        Matcher::new(
+            &self.search_input,
+            self.search_kind,
+            self.case,
+            self.fuzzy,
+            self.start_date(),
+        )
+    }
+
+    /// Whether the search leaves only the lines it matches, as it does while
+    /// it's typed and after, when it's kept that way.
+    pub(crate) fn search_filters(&self) -> bool {
+        !self.search_input.is_empty()
+            && (self.search_mode == SearchMode::Search || self.search_style == SearchStyle::Filter)
+    }
+
+    /// The marked lines in the order of their letters.
+    pub(crate) fn marked_lines(&self) -> Vec<(char, &LogEntry)> {
+        marked_lines(&self.marks, &self.logs)
+    }
+
+    /// The day the log starts on, which times without a date are taken to
+    /// be on.
+    pub(crate) fn start_date(&self) -> Option<NaiveDate> {
+        self.logs
+            .iter()
+            .find_map(|entry| entry.timestamp)
+            .map(|timestamp| timestamp.date())
+    }
+
+    /// Regexes finding what the search and the filters on text matched in a
+    /// line, to highlight it.
+    pub(crate) fn highlights(&self) -> Vec<Regex> {
+        let search = match self.search_input.is_empty() {
+            true => None,
+            false => self.matcher().ok().and_then(|matcher| matcher.highlight()),
+        };
+        let narrowed = self
+            .narrowed
+            .iter()
+            .filter_map(|(_, matcher)| matcher.highlight());
+        let regex = self
+            .filters
+            .regex
+            .as_ref()
+            .map(|regex| self.case.regex(regex));
+        let quick = self.filters.quick.as_ref().and_then(|word| {
+            let regex = Regex::new(&regex::escape(word)).ok()?;
+            Some(self.case.regex(&regex))
+        });
+        search
+            .into_iter()
+            .chain(narrowed)
+            .chain(regex)
+            .chain(quick)
+            .collect()
+    }
+
+    /// A regex finding the preview search, with case treated like the
+    /// search's.
+    pub(crate) fn preview_search(&self) -> Option<Regex> {
+        let search = Regex::new(&regex::escape(self.preview_search.as_ref()?)).ok()?;
+        Some(self.case.regex(&search))
+    }
+
+    /// Opens the same files again in a model of its own, which has its own
+    /// filters and search. Streams can't be read twice, so there's nothing
+    /// to open for them.
+    pub(crate) fn reopen(&self) -> Option<Result<Model>> {
+        if self.source.as_ref()?.metadata().streaming {
+            return None;
+        }
+        let reopened =
+            Model::new(&self.log_paths, self.merge_mode, &self.parse_options).map(|model| Model {
+                // The format was already settled on in this model.
+                format_picker: None,
+                marks: Marks::default(),
+                jumps: JumpList::default(),
+                pending_mark: None,
+                pending_jump: None,
+                pending_align: false,
+                pending_count: None,
+                mark_list: None,
+                case: self.case,
+                fuzzy: self.fuzzy,
+                gap_threshold: self.gap_threshold,
+                scrolloff: self.scrolloff,
+                ..model
+            });
+        Some(reopened)
+    }
+
+    /// Guesses the format of the log from the lines read so far. When it's a
+    /// structured one, and wasn't given on the command line, the format
+    /// picker is shown to confirm it.
+    fn detect_format(&mut self) {
+        self.detected_format = LogFormat::detect(
+            self.logs
+                .iter()
+                .map(|entry| entry.original.as_deref().unwrap_or(&entry.line)),
+        );
+        if self.parse_options.format.is_none()
+            && self.columns.is_none()
+            && self
+                .detected_format
+                .is_some_and(|format| format != LogFormat::Plain)
+        {
+            self.open_format_picker(self.detected_format);
+        }
+    }
+
+    /// The format lines are read as, or `None` when every format is tried.
+    pub(crate) fn format(&self) -> Option<LogFormat> {
+        self.parse_options.format
+    }
+
+    fn open_format_picker(&mut self, format: Option<LogFormat>) {
+        let selected = LogFormat::CHOICES
+            .iter()
+            .position(|choice| *choice == format)
+            .unwrap_or(0);
+        self.format_picker = Some(selected);
+    }
+
+    /// Reads every line again in the given format.
+    fn set_format(&mut self, format: Option<LogFormat>) {
+        self.parse_options.format = format;
+        self.timestamps = TimestampDetector::default();
+        // The lines read differently, so what the search ruled out may not be.
+        self.search_cache = None;
+        let lines: Vec<LogEntry> = self.logs.drain(..).flat_map(LogEntry::into_lines).collect();
+        self.logs = self.prepare_all(lines);
+        self.logs_changes += 1;
+    }
+
     pub(crate) fn set_view_height(&mut self, height: usize) {
+        self.resized |= height != self.view_height;
         self.view_height = height;
     }
 
+    pub(crate) fn view_height(&self) -> usize {
+        self.view_height
+    }
+
     /// Returns the byte index based on the character position.
     ///
     /// Since each character in a string can be contain multiple bytes, it's necessary to calculate
     /// the byte index based on the index of the character.
     fn byte_index(&self) -> usize {
-        self.search_input
+        self.input()
             .char_indices()
             .map(|(i, _)| i)
             .nth(self.cursor_pos)
-            .unwrap_or(self.search_input.len())
+            .unwrap_or(self.input().len())
     }
 
     fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.search_input.chars().count())
+        new_cursor_pos.clamp(0, self.input().chars().count())
+    }
+
+    /// The text being typed, in the prompt or the search.
+    pub(crate) fn input(&self) -> &str {
+        match self.search_mode {
+            SearchMode::Prompt(_) => &self.prompt_input,
+            _ => &self.search_input,
+        }
+    }
+
+    /// What the history files the text being typed under.
+    pub(crate) fn history_kind(&self) -> Option<&'static str> {
+        match self.search_mode {
+            SearchMode::Search => Some("search"),
+            SearchMode::Prompt(prompt) => prompt.history_kind(),
+            SearchMode::None => None,
+        }
+    }
+
+    /// Replaces the text being typed with an older or newer entry of the
+    /// history. Going past the newest brings back what was typed.
+    pub(crate) fn recall(&mut self, history: &History, older: bool) {
+        let Some(kind) = self.history_kind() else {
+            return;
+        };
+        let found = match (older, self.history_pos) {
+            (true, pos) => history.older(kind, pos),
+            (false, Some(pos)) => history.newer(kind, pos),
+            (false, None) => return,
+        };
+
+        let text = match found {
+            Some((pos, text)) => {
+                if self.history_pos.is_none() {
+                    self.history_draft = self.input().to_string();
+                }
+                self.history_pos = Some(pos);
+                text.to_string()
+            }
+            None if older => return,
+            None => {
+                self.history_pos = None;
+                std::mem::take(&mut self.history_draft)
+            }
+        };
+        self.cursor_pos = text.chars().count();
+        *self.input_mut() = text;
+    }
+
+    /// Replaces the text being typed with the next older entry of the history
+    /// holding its letters, like a shell's Ctrl-r. Doing it again keeps
+    /// looking for what was first typed.
+    pub(crate) fn recall_matching(&mut self, history: &History) {
+        let Some(kind) = self.history_kind() else {
+            return;
+        };
+        let query = match self.history_pos {
+            Some(_) => self.history_draft.clone(),
+            None => self.input().to_string(),
+        };
+        let Some((pos, text)) = history.older_matching(kind, &query, self.history_pos) else {
+            return;
+        };
+        let text = text.to_string();
+        self.history_draft = query;
+        self.history_pos = Some(pos);
+        self.cursor_pos = text.chars().count();
+        *self.input_mut() = text;
+    }
+
+    fn input_mut(&mut self) -> &mut String {
+        match self.search_mode {
+            SearchMode::Prompt(_) => &mut self.prompt_input,
+            _ => &mut self.search_input,
+        }
+    }
+
+    /// Goes back to the top of the logs, after what's shown has changed.
+    pub(crate) fn reset_view(&mut self) {
+        self.view_offset = 0;
+        self.line_idx = 0;
+    }
+
+    /// Returns true if the source has new lines since the last refresh.
+    pub(crate) fn logs_changed(&mut self) -> bool {
+        self.source
+            .as_mut()
+            .is_some_and(|source| source.has_new_lines())
+    }
+
+    /// How long until what's typed into the search is run, while typing into
+    /// the search of a log large enough for running it on every key to lag.
+    pub(crate) fn search_settles_in(&self) -> Option<Duration> {
+        if self.search_mode != SearchMode::Search
+            || self.logs.len() < DEBOUNCE_LINES
+            || self.applied_search == self.search_input
+        {
+            return None;
+        }
+        SEARCH_DEBOUNCE.checked_sub(self.search_edited?.elapsed())
+    }
+
+    /// Drops any half typed key sequence, like the `g` waiting for a second `g`.
+    pub(crate) fn clear_pending_keys(&mut self) {
+        self.g_modifier = false;
+        self.pending_mark = None;
+        self.pending_jump = None;
+        self.pending_align = false;
+        self.pending_count = None;
     }
 
     fn reset_cursor(&mut self) {
         self.cursor_pos = 0;
     }
 
+    /// Returns what the source is busy with, if anything.
+    pub(crate) fn source_status(&self) -> Option<String> {
+        self.source
+            .as_ref()
+            .and_then(|source| source.metadata().status)
+    }
+
+    /// Returns true if the model reads from several files.
+    pub(crate) fn is_merged(&self) -> bool {
+        self.log_paths.len() > 1
+    }
+
+    /// Returns true if the selected line is an entry with lines grouped into
+    /// it, or one of those lines opened up under it.
+    pub(crate) fn selects_group(&mut self) -> bool {
+        let logs = listed_logs(self);
+        let selected = self.window(logs.len()).start + self.line_idx;
+        logs.get(selected).is_some_and(|entry| {
+            entry.continued
+                || !entry.continuation.is_empty()
+                || self.expanded_groups.contains(&(entry.source, entry.number))
+        })
+    }
+
+    /// Drops everything received so far from a streaming source. Files are
+    /// left alone since the next refresh would read them back anyway.
+    fn clear_logs(&mut self) {
+        if let Some(source) = self.source.as_mut()
+            && source.metadata().streaming
+        {
+            source.clear();
+            self.logs.clear();
+            self.logs_changes += 1;
+            self.view_offset = 0;
+            self.line_idx = 0;
+        }
+    }
+
     fn refresh_logs(&mut self) {
-        let logs: Vec<String> = fs::read_to_string(&self.log_path)
-            .unwrap_or(String::new())
-            .lines()
-            .map(|l| l.to_string())
-            .collect();
+        let Some(source) = self.source.as_mut() else {
+            return;
+        };
+
+        let new_lines = source.poll_new_lines();
+        self.columns = source.metadata().columns;
+        self.logs_changes += 1;
 
+        match new_lines {
+            NewLines::Appended(logs) => {
+                let before = self.logs.len();
+                let mut logs: Vec<LogEntry> =
+                    logs.into_iter().map(|entry| self.prepare(entry)).collect();
+                // What's appended to merged files comes after what was read
+                // before, so it's only merged with itself.
+                if self.merge_mode == MergeMode::Chronological {
+                    merge_by_timestamp(&mut logs);
+                }
+                for entry in logs {
+                    push_sampled(&mut self.logs, self.sampler.as_mut(), entry);
+                }
+                self.keep_view_position(self.logs.len() - before);
+            }
+            NewLines::Reloaded(logs) => {
+                self.search_cache = None;
+                let grouped = self.prepare_all(logs);
+                if grouped.len() > self.logs.len() {
+                    self.keep_view_position(grouped.len() - self.logs.len());
+                }
+                self.logs = grouped;
+            }
+        }
+
+        // Streams may not have anything to go by when they're opened.
+        if self.detected_format.is_none() {
+            self.detect_format();
+        }
+    }
+
+    /// Parses the entry, works out when it was logged and numbers it in its
+    /// file.
+    fn prepare(&mut self, entry: LogEntry) -> LogEntry {
+        let mut entry = self.parse_options.apply(entry);
+        self.timestamps.stamp(&mut entry);
+        if self.line_counts.len() <= entry.source {
+            self.line_counts.resize(entry.source + 1, 0);
+        }
+        self.line_counts[entry.source] += 1;
+        entry.number = self.line_counts[entry.source];
+        entry
+    }
+
+    /// Prepares a whole log read from the start, merging its files if it's
+    /// asked to and grouping the lines that continue an entry.
+    fn prepare_all(&mut self, logs: Vec<LogEntry>) -> Vec<LogEntry> {
+        self.line_counts.clear();
+        let mut logs: Vec<LogEntry> = logs.into_iter().map(|entry| self.prepare(entry)).collect();
+        if self.merge_mode == MergeMode::Chronological {
+            merge_by_timestamp(&mut logs);
+        }
+
+        // Files that are read again, like after being truncated, are
+        // sampled again from their start.
+        let mut sampler = self
+            .sampler
+            .as_ref()
+            .map(|sampler| Sampler::new(sampler.rate));
+        let mut grouped = vec![];
+        for entry in logs {
+            push_sampled(&mut grouped, sampler.as_mut(), entry);
+        }
+        self.sampler = sampler;
+        grouped
+    }
+
+    fn keep_view_position(&mut self, added: usize) {
         // If the we've added logs and we're not at the bottom of the view,
         // compensate the view offset so the filtered view doesn't scroll us
-        // downward when adding logs.
-        if logs.len() > self.logs.len() && self.view_offset != 0 {
-            self.view_offset += logs.len() - self.logs.len();
+        // downward when adding logs. A paused follow stays where it is too.
+        if self.view_offset != 0 || self.follow_paused {
+            self.view_offset += added;
+        }
+    }
+
+    /// The lines in view out of this many, with the view kept from
+    /// scrolling past the top of them.
+    fn window(&self, len: usize) -> Range<usize> {
+        let end = len - self.view_offset.min(len.saturating_sub(self.view_height));
+        end.saturating_sub(self.view_height)..end
+    }
+
+    /// Keeps the selection on one of this many lines, and `scrolloff` rows
+    /// from the edges of the view where the lines go on past them, scrolling
+    /// the view along with it.
+    fn settle_selection(&mut self, len: usize) {
+        let window = self.window(len);
+        self.view_offset = len - window.end;
+        self.line_idx = self.line_idx.min(window.len().saturating_sub(1));
+
+        let margin = self.scrolloff.min(self.view_height.saturating_sub(1) / 2);
+        if self.line_idx < margin {
+            let rows = (margin - self.line_idx).min(window.start);
+            self.view_offset += rows;
+            self.line_idx += rows;
+        }
+        let bottom = window.len().saturating_sub(margin + 1);
+        if self.line_idx > bottom {
+            let rows = (self.line_idx - bottom).min(self.view_offset);
+            self.view_offset -= rows;
+            self.line_idx -= rows;
+        }
+    }
+
+    /// Points the selection at the line at the index out of this many, in
+    /// the view as it's scrolled. A view too short to show any lines keeps
+    /// it at the top.
+    fn select_in_view(&mut self, idx: usize, len: usize) {
+        self.line_idx = idx.saturating_sub(self.window(len).start);
+    }
+
+    /// Selects the line at the index out of this many, scrolling it to the
+    /// middle of the view if it's out of sight.
+    fn select_index(&mut self, idx: usize, len: usize) {
+        if !self.window(len).contains(&idx) {
+            let end = (idx + self.view_height / 2 + 1).clamp(self.view_height.min(len), len);
+            self.view_offset = len - end;
+        }
+        self.select_in_view(idx, len);
+    }
+
+    /// Selects the line of the file nearest the numbered one, on the given
+    /// row.
+    fn select_at_row(&mut self, logs: &[LogEntry], number: usize, row: usize) {
+        let Some(idx) = logs
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, entry)| entry.number.abs_diff(number))
+            .map(|(idx, _)| idx)
+        else {
+            return;
+        };
+        self.place_at_row(idx, row, logs.len());
+    }
+
+    /// Selects the line at this position out of this many, scrolling the
+    /// view to have it on the given row where the lines allow.
+    fn place_at_row(&mut self, idx: usize, row: usize, len: usize) {
+        let end =
+            (idx.saturating_sub(row) + self.view_height).clamp(self.view_height.min(len), len);
+        self.view_offset = len - end;
+        self.select_in_view(idx, len);
+    }
+
+    /// Where the file was left, to open it there next time. Only a single
+    /// file on disk has one.
+    pub(crate) fn saved_position(&self) -> Option<SavedPosition> {
+        let streaming = self.source.as_ref()?.metadata().streaming;
+        if self.log_paths.len() != 1 || streaming {
+            return None;
         }
+        let (_, line) = self.selected_line?;
+        Some(SavedPosition {
+            line,
+            row: self.line_idx,
+            view: Preset::from_model("", self),
+        })
+    }
+
+    /// Sets the filters and search back to how the file was left, if it's
+    /// been opened before, and selects the line it was left at once the
+    /// view is drawn.
+    pub(crate) fn restore_saved_position(&mut self) {
+        if self.log_paths.len() != 1 {
+            return;
+        }
+        let Some(position) = SavedPosition::load(&self.log_paths[0]) else {
+            return;
+        };
+        position.view.apply(self);
+        self.restored_line = Some((position.line, position.row));
+    }
+
+    /// The lines shown that the search matches, for searching every tab.
+    pub(crate) fn matching_lines(&mut self, matcher: &Matcher) -> Vec<LogEntry> {
+        let mut logs = filter_logs(self);
+        logs.retain(|entry| matcher.matches(entry));
+        logs
+    }
+
+    /// Selects the line numbered `number` in the file at `source`, if the
+    /// filters show it.
+    pub(crate) fn select_line(&mut self, source: usize, number: usize) {
+        let logs = listed_logs(self);
+        if let Some(idx) = logs
+            .iter()
+            .position(|entry| entry.source == source && entry.number == number)
+        {
+            self.select_index(idx, logs.len());
+        }
+    }
+
+    fn move_top(&mut self) {
+        self.view_offset = self.top_offset();
+        self.line_idx = 0;
+    }
 
-        self.logs = logs;
+    fn move_bottom(&mut self) {
+        self.view_offset = 0;
+        self.line_idx = self.view_height.saturating_sub(1);
     }
 
-    fn move_up_page(&mut self) {
-        self.view_offset += self.view_height;
-        if self.view_offset == self.logs.len() {
+    /// Moves the selection up the rows, scrolling the view once it's at the
+    /// top.
+    fn move_up(&mut self, rows: usize) {
+        if rows <= self.line_idx {
+            self.line_idx -= rows;
+        } else {
+            self.view_offset += rows - self.line_idx;
             self.line_idx = 0;
         }
     }
 
-    fn move_down_page(&mut self) {
-        self.view_offset = self
-            .view_offset
-            .checked_sub(self.view_height)
-            .unwrap_or_else(|| {
-                self.line_idx = self.view_height - 1;
-                0
-            });
+    /// Moves the selection down the rows, scrolling the view once it's at
+    /// the bottom.
+    fn move_down(&mut self, rows: usize) {
+        let room = self.view_height.saturating_sub(self.line_idx + 1);
+        if rows <= room {
+            self.line_idx += rows;
+        } else {
+            self.line_idx = self.view_height.saturating_sub(1);
+            self.view_offset = self.view_offset.saturating_sub(rows - room);
+        }
+    }
+
+    /// Scrolls the view up by the rows, keeping the selection where it is on
+    /// the screen. Once the view is at the top, the first line is selected.
+    fn scroll_up(&mut self, rows: usize) {
+        let top = self.top_offset();
+        if self.view_offset >= top {
+            self.line_idx = 0;
+        }
+        self.view_offset = (self.view_offset + rows).min(top);
+    }
+
+    /// Scrolls the view down by the rows, keeping the selection where it is
+    /// on the screen. Once the view is at the bottom, the last line is
+    /// selected.
+    fn scroll_down(&mut self, rows: usize) {
+        self.view_offset = self.view_offset.min(self.top_offset());
+        if self.view_offset == 0 {
+            self.line_idx = self.view_height.saturating_sub(1);
+        }
+        self.view_offset = self.view_offset.saturating_sub(rows);
+    }
+
+    /// How far the view scrolls up before it's at the top of the lines.
+    fn top_offset(&mut self) -> usize {
+        listed_logs(self).len().saturating_sub(self.view_height)
+    }
+
+    /// Keeps the lines from scrolling past the end of the widest one shown.
+    pub(crate) fn set_overflow(&mut self, overflow: usize) {
+        self.overflow = overflow;
+        self.scroll_x = self.scroll_x.min(overflow);
+    }
+
+    /// Scrolls the share of the way through the lines listed to the top of
+    /// the view and selects it, like less's `50%`.
+    fn goto_percent(&mut self, percent: usize) {
+        let len = listed_logs(self).len();
+        if len == 0 {
+            return;
+        }
+        let idx = (len - 1) * percent.min(100) / 100;
+        let end = (idx + self.view_height).clamp(self.view_height.min(len), len);
+        self.view_offset = len - end;
+        self.select_in_view(idx, len);
+    }
+
+    /// Scrolls the selected line to the top, middle or bottom of the view,
+    /// like vim's `zt`, `zz` and `zb`.
+    fn align_selection(&mut self, place: ScreenPlace) {
+        let len = listed_logs(self).len();
+        let idx = self.window(len).start + self.line_idx;
+        let above = match place {
+            ScreenPlace::Top => 0,
+            ScreenPlace::Middle => self.view_height.saturating_sub(1) / 2,
+            ScreenPlace::Bottom => self.view_height.saturating_sub(1),
+        };
+        let end =
+            (idx.saturating_sub(above) + self.view_height).clamp(self.view_height.min(len), len);
+        self.view_offset = len - end;
+        self.select_in_view(idx, len);
+    }
+
+    /// Selects the line at the top, middle or bottom of the screen, like
+    /// vim's `H`, `M` and `L`.
+    fn select_on_screen(&mut self, place: ScreenPlace) {
+        let len = listed_logs(self).len();
+        let last = self.window(len).len().saturating_sub(1);
+        self.line_idx = match place {
+            ScreenPlace::Top => 0,
+            ScreenPlace::Middle => last / 2,
+            ScreenPlace::Bottom => last,
+        };
+    }
+}
+
+/// Adds the entry to the logs, unless the sampler leaves it out.
+fn push_sampled(logs: &mut Vec<LogEntry>, sampler: Option<&mut Sampler>, entry: LogEntry) {
+    let entry = match sampler {
+        Some(sampler) => sampler.sample(entry, logs.last()),
+        None => Some(entry),
+    };
+    if let Some(entry) = entry {
+        push_grouped(logs, entry);
+    }
+}
+
+/// Sorts the logs of several files into a single chronological timeline.
+///
+/// Lines without a timestamp (stack traces, wrapped messages) take the
+/// timestamp of the line above them, so they stay attached to it. The sort is
+/// stable, so each file keeps its own order and ties go to the earlier file.
+fn merge_by_timestamp(logs: &mut Vec<LogEntry>) {
+    let mut last_source = None;
+    let mut last_ts = None;
+    let mut keyed: Vec<_> = logs
+        .drain(..)
+        .map(|entry| {
+            if last_source != Some(entry.source) {
+                last_source = Some(entry.source);
+                last_ts = None;
+            }
+            if entry.timestamp.is_some() {
+                last_ts = entry.timestamp;
+            }
+            (last_ts, entry)
+        })
+        .collect();
+
+    keyed.sort_by_key(|(ts, _)| *ts);
+    logs.extend(keyed.into_iter().map(|(_, entry)| entry));
+}
+
+/// Adds the logger to the set, or takes it out if it's there.
+fn toggle(loggers: &mut BTreeSet<String>, logger: &str) {
+    if !loggers.remove(logger) {
+        loggers.insert(logger.to_string());
+    }
+}
+
+/// Filters on a word of the selected line. Doing it again on the same line
+/// moves on to its next word, for when the first guess isn't the one wanted.
+fn apply_quick_filter(model: &mut Model) {
+    let visible = get_filtered_logs(model);
+    let Some(entry) = visible.get(model.line_idx) else {
+        return;
+    };
+    let tokens = quick_filter_tokens(entry);
+    let next = match model
+        .filters
+        .quick
+        .as_ref()
+        .and_then(|current| tokens.iter().position(|token| token == current))
+    {
+        Some(idx) => tokens.get((idx + 1) % tokens.len()),
+        None => tokens.first(),
+    };
+    if let Some(token) = next {
+        model.filters.quick = Some(token.clone());
+        model.reset_view();
+    }
+}
+
+/// Hides the selected line, or every line of the same kind, going by what's
+/// left when the numbers and IDs are taken out.
+fn hide_line(model: &mut Model, like_it: bool) {
+    let visible = get_filtered_logs(model);
+    let Some(entry) = visible.get(model.line_idx) else {
+        return;
+    };
+    match like_it {
+        true => model
+            .filters
+            .hidden_templates
+            .insert(line_template(&entry.line)),
+        false => model.filters.hidden.insert(entry.line.clone()),
+    };
+    // Stay where the line was, on the one that took its place.
+    let last = get_filtered_logs(model).len().saturating_sub(1);
+    model.line_idx = model.line_idx.min(last);
+}
+
+/// Opens up the lines grouped under the selected entry, or else the
+/// collapsed run of repeats on it, or folds them away again. On a grouped
+/// line the entry above it is folded, selecting the entry.
+fn toggle_fold(model: &mut Model) {
+    let logs = listed_logs(model);
+    let selected = model.window(logs.len()).start + model.line_idx;
+    let Some(head) = (0..=selected)
+        .rev()
+        .find(|&idx| logs.get(idx).is_some_and(|entry| !entry.continued))
+    else {
+        return;
+    };
+    let entry = &logs[head];
+    let group = (entry.source, entry.number);
+    if head == selected && entry.continuation.is_empty() && !model.expanded_groups.contains(&group)
+    {
+        if entry.repeats > 1 && !model.expanded_repeats.remove(&entry.line) {
+            model.expanded_repeats.insert(entry.line.clone());
+        }
+        return;
+    }
+    // The entry stays on the row it was on, with its lines opening up or
+    // folding away below it.
+    let row = model.line_idx.saturating_sub(selected - head);
+    if !model.expanded_groups.remove(&group) {
+        model.expanded_groups.insert(group);
     }
+    let len = listed_logs(model).len();
+    model.place_at_row(head, row, len);
+}
+
+/// Where the parts of a tab were last drawn, to tell what the mouse is over.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Areas {
+    /// The list of lines and the preview beside it.
+    pub(crate) panes: Rect,
+    /// The rows of lines in the list, inside its border.
+    pub(crate) rows: Rect,
+    /// The column the preview starts at, where the panes are resized from.
+    pub(crate) divider: u16,
+    pub(crate) search: Rect,
+    /// The rows of the minimap, empty while it's hidden.
+    pub(crate) minimap: Rect,
+}
+
+/// What the gutter before the lines shows.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Gutter {
+    /// The number each line has in its file, only while a search leaves
+    /// just its matches.
+    #[default]
+    Auto,
+    /// The number each line has in its file, to find it with grep or an
+    /// editor.
+    Numbers,
+    /// The number in the file and, while lines are filtered out, where the
+    /// line is among the ones listed.
+    NumbersAndIndex,
+}
+
+impl Gutter {
+    /// The gutter after this one, to toggle through them.
+    pub(crate) fn next(self) -> Gutter {
+        match self {
+            Gutter::Auto => Gutter::Numbers,
+            Gutter::Numbers => Gutter::NumbersAndIndex,
+            Gutter::NumbersAndIndex => Gutter::Auto,
+        }
+    }
+}
+
+/// Where on the screen to select a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ScreenPlace {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// Where to look for a line from the selected one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Jump {
+    /// Further down the log.
+    Next,
+    /// Further up the log.
+    Previous,
+    /// The selected line if it matches, otherwise the next match or else the
+    /// previous one, going the way the search does.
+    Nearest,
+}
+
+impl Jump {
+    pub(crate) fn reversed(self) -> Jump {
+        match self {
+            Jump::Next => Jump::Previous,
+            Jump::Previous => Jump::Next,
+            Jump::Nearest => Jump::Nearest,
+        }
+    }
+}
+
+/// Selects a line the submitted search matches, out of every line the
+/// filters let through. Stays put when there's no match that way.
+fn jump_to_match(model: &mut Model, jump: Jump) {
+    if model.search_input.is_empty() {
+        return;
+    }
+    let Ok(matcher) = model.matcher() else {
+        return;
+    };
+    jump_to_line(model, jump, |entry| matcher.matches(entry));
+}
+
+/// Searches for the word of the selected line a quick filter would be made
+/// from, without opening the search, and jumps to the next or last line
+/// holding it. `n` keeps going the same way, as after searching backward.
+fn search_word(model: &mut Model, jump: Jump) {
+    let visible = get_filtered_logs(model);
+    let Some(word) = visible
+        .get(model.line_idx)
+        .and_then(|entry| quick_filter_tokens(entry).into_iter().next())
+    else {
+        return;
+    };
+    model.search_input = word_search(&word);
+    model.search_mode = SearchMode::None;
+    model.search_backward = jump == Jump::Previous;
+    model.reset_cursor();
+    jump_to_match(model, jump);
+}
+
+/// Selects a line listed that `picks` picks out, from the selected line.
+fn jump_to_line(model: &mut Model, jump: Jump, picks: impl Fn(&LogEntry) -> bool) {
+    let logs = listed_logs(model);
+    jump_to_index(model, logs.len(), jump, |idx| picks(&logs[idx]));
+}
+
+/// Selects the position of a line out of this many listed that `picks`
+/// picks out, from the selected line.
+fn jump_to_index(model: &mut Model, len: usize, jump: Jump, picks: impl Fn(usize) -> bool) {
+    let current = model.window(len).start + model.line_idx;
+    let matches = |idx: &usize| picks(*idx);
+
+    let next = || (current + 1..len).find(matches);
+    let previous = || (0..current.min(len)).rev().find(matches);
+    let found = match jump {
+        Jump::Next => next(),
+        Jump::Previous => previous(),
+        Jump::Nearest if current < len && matches(&current) => Some(current),
+        Jump::Nearest if model.search_backward => previous().or_else(next),
+        Jump::Nearest => next().or_else(previous),
+    };
+    if let Some(idx) = found {
+        model.select_index(idx, len);
+    }
+}
+
+/// The lines listed, as they're laid out in rows, for moving the selection
+/// to one of them.
+fn listed_logs(model: &mut Model) -> Rc<Vec<LogEntry>> {
+    update_listing(model);
+    model
+        .listing
+        .as_ref()
+        .map_or_else(Rc::default, |listing| Rc::clone(&listing.rows))
+}
+
+/// Filters and searches the lines again, when the lines, the filters or the
+/// search have changed since the last time.
+fn update_listing(model: &mut Model) {
+    if model.search_settles_in().is_none() {
+        model.applied_search.clone_from(&model.search_input);
+    }
+    let key = ListingKey::of(model);
+    if model
+        .listing
+        .as_ref()
+        .is_some_and(|listing| listing.key == key)
+    {
+        return;
+    }
+    let mut rows = filter_logs(model);
+    let searched = apply_search(model, &mut rows);
+    count_matches(model, &rows);
+    model.listing = Some(Listing {
+        key,
+        rows: Rc::new(rows),
+        searched,
+    });
+}
+
+/// Selects the line at the place in the file the selected line is from, or
+/// the nearest one listed when it's filtered out.
+fn goto_line(model: &mut Model, goto: Goto) -> Result<(), String> {
+    let logs = listed_logs(model);
+    let selected = model.window(logs.len()).start + model.line_idx;
+    let source = logs.get(selected).map_or(0, |entry| entry.source);
+    let number = match goto {
+        Goto::Line(number) => number,
+        Goto::Percent(percent) => {
+            let count = model.line_counts.get(source).copied().unwrap_or_default();
+            (count * percent).div_ceil(100).max(1)
+        }
+        Goto::Time(time) => {
+            let time = TimeRange::parse_moment(&time, model.start_date())?;
+            goto_time(model, &logs, time);
+            return Ok(());
+        }
+    };
+    select_nearest(model, &logs, source, number);
+    Ok(())
+}
+
+/// Selects the line with the number in the file the selected line is from,
+/// or the nearest one listed, for a count before `G` or `gg`.
+fn goto_number(model: &mut Model, number: usize) {
+    let logs = listed_logs(model);
+    let selected = model.window(logs.len()).start + model.line_idx;
+    let source = logs.get(selected).map_or(0, |entry| entry.source);
+    select_nearest(model, &logs, source, number);
+}
+
+/// Selects the line with the number in the file at `source`, or the nearest
+/// one listed when it's filtered out.
+fn select_nearest(model: &mut Model, logs: &[LogEntry], source: usize, number: usize) {
+    let nearest = logs
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.source == source)
+        .min_by_key(|(_, entry)| entry.number.abs_diff(number))
+        .map(|(idx, _)| idx);
+    if let Some(idx) = nearest {
+        model.select_index(idx, logs.len());
+    }
+}
+
+/// Pins the selected line above the others, or unpins it if it's pinned.
+fn toggle_pinned_line(model: &mut Model) {
+    let visible = get_filtered_logs(model);
+    let Some(entry) = visible.get(model.line_idx) else {
+        return;
+    };
+    match model
+        .pinned_lines
+        .iter()
+        .position(|pinned| pinned.source == entry.source && pinned.number == entry.number)
+    {
+        Some(idx) => {
+            model.pinned_lines.remove(idx);
+        }
+        None => model.pinned_lines.push(entry.clone()),
+    }
+}
+
+/// Marks the selected line with the letter, in place of any line marked
+/// with it before.
+fn set_mark(model: &mut Model, letter: char) {
+    let visible = get_filtered_logs(model);
+    if let Some(entry) = visible.get(model.line_idx) {
+        model.marks.insert(letter, (entry.source, entry.number));
+    }
+}
+
+/// Goes back to the line marked with the letter.
+fn jump_to_mark(model: &mut Model, letter: char) {
+    if let Some(&place) = model.marks.get(&letter) {
+        select_place(model, place);
+    }
+}
+
+/// Selects the line at the file and number, or the nearest one listed.
+fn select_place(model: &mut Model, (source, number): (usize, usize)) {
+    let logs = listed_logs(model);
+    select_nearest(model, &logs, source, number);
+}
+
+/// Selects the first line logged at or after the time, or the last line when
+/// they're all before it. Lines without a timestamp go by the line above.
+fn goto_time(model: &mut Model, logs: &[LogEntry], time: NaiveDateTime) {
+    let mut last = None;
+    let times: Vec<Option<NaiveDateTime>> = logs
+        .iter()
+        .map(|entry| {
+            last = entry.timestamp.or(last);
+            last
+        })
+        .collect();
+    // The lines are in the order they were logged, so the times only go up.
+    let idx = times.partition_point(|logged| logged.is_none_or(|logged| logged < time));
+    if !logs.is_empty() {
+        model.select_index(idx.min(logs.len() - 1), logs.len());
+    }
+}
+
+/// Pins what the search matches as a highlight, taking the place of the
+/// oldest pin when there are too many. Pinning it again, or pinning without
+/// a search, takes off the last pin instead.
+fn pin_search(model: &mut Model) {
+    let Some(regex) = model.matcher().ok().and_then(|matcher| matcher.highlight()) else {
+        model.pinned.pop();
+        return;
+    };
+    if let Some(idx) = model
+        .pinned
+        .iter()
+        .position(|pinned| pinned.as_str() == regex.as_str())
+    {
+        model.pinned.remove(idx);
+        return;
+    }
+    if model.pinned.len() == MAX_PINS {
+        model.pinned.remove(0);
+    }
+    model.pinned.push(regex);
 }
 
 /************************ Search Input Functions *****************************/
 fn enter_char(model: &mut Model, new_char: char) {
     let index = model.byte_index();
-    model.search_input.insert(index, new_char);
+    model.input_mut().insert(index, new_char);
     move_cursor_right(model);
 }
 
+/// Inserts pasted text at the cursor, on one line since that's all the input
+/// has.
+fn paste(model: &mut Model, text: &str) {
+    let text: String = text
+        .trim_end_matches(['\r', '\n'])
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    let index = model.byte_index();
+    model.input_mut().insert_str(index, &text);
+    model.cursor_pos = model.clamp_cursor(model.cursor_pos + text.chars().count());
+}
+
 fn move_cursor_left(model: &mut Model) {
     let cursor_moved_left = model.cursor_pos.saturating_sub(1);
     model.cursor_pos = model.clamp_cursor(cursor_moved_left);
@@ -152,31 +1490,349 @@ fn delete_char(model: &mut Model) {
         let from_left_to_current_index = current_index - 1;
 
         // Getting all characters before the selected character.
-        let before_char_to_delete = model.search_input.chars().take(from_left_to_current_index);
+        let before_char_to_delete = model.input().chars().take(from_left_to_current_index);
         // Getting all characters after selected character.
-        let after_char_to_delete = model.search_input.chars().skip(current_index);
+        let after_char_to_delete = model.input().chars().skip(current_index);
 
         // Put all characters together except the selected one.
         // By leaving the selected one out, it is forgotten and therefore deleted.
-        model.search_input = before_char_to_delete.chain(after_char_to_delete).collect();
+        *model.input_mut() = before_char_to_delete.chain(after_char_to_delete).collect();
         move_cursor_left(model);
     }
 }
 
+/// Where the word before the cursor starts, past any spaces before it, as
+/// readline sees words for `Ctrl-w` and `Alt-b`.
+fn word_start(model: &Model) -> usize {
+    let chars: Vec<char> = model.input().chars().collect();
+    let mut pos = model.cursor_pos.min(chars.len());
+    while pos > 0 && chars[pos - 1].is_whitespace() {
+        pos -= 1;
+    }
+    while pos > 0 && !chars[pos - 1].is_whitespace() {
+        pos -= 1;
+    }
+    pos
+}
+
+/// Where the word after the cursor ends, past any spaces before it.
+fn word_end(model: &Model) -> usize {
+    let chars: Vec<char> = model.input().chars().collect();
+    let mut pos = model.cursor_pos.min(chars.len());
+    while pos < chars.len() && chars[pos].is_whitespace() {
+        pos += 1;
+    }
+    while pos < chars.len() && !chars[pos].is_whitespace() {
+        pos += 1;
+    }
+    pos
+}
+
+/// Takes out the characters between the positions, leaving the cursor where
+/// they were.
+fn delete_chars(model: &mut Model, chars: Range<usize>) {
+    let kept = model
+        .input()
+        .chars()
+        .enumerate()
+        .filter(|(i, _)| !chars.contains(i))
+        .map(|(_, c)| c)
+        .collect();
+    *model.input_mut() = kept;
+    model.cursor_pos = model.clamp_cursor(chars.start);
+}
+
 fn reset_search(model: &mut Model) {
     model.search_input.clear();
     model.reset_cursor();
     model.search_mode = SearchMode::None;
 }
 
+/// Opens the prompt, filled in with what it's currently set to so it can be
+/// edited.
+fn open_prompt(model: &mut Model, prompt: Prompt) {
+    model.prompt_input = match prompt {
+        Prompt::Regex => model
+            .filters
+            .regex
+            .as_ref()
+            .map(|regex| regex.as_str().to_string())
+            .unwrap_or_default(),
+        Prompt::Exclude => String::new(),
+        Prompt::TimeRange => model
+            .filters
+            .time_range
+            .as_ref()
+            .map(|range| range.as_str().to_string())
+            .unwrap_or_default(),
+        Prompt::Expression => model
+            .filters
+            .expression
+            .as_ref()
+            .map(|expression| expression.as_str().to_string())
+            .unwrap_or_default(),
+        Prompt::PreviewSearch => model.preview_search.clone().unwrap_or_default(),
+        Prompt::Field | Prompt::PresetName | Prompt::Command | Prompt::TabSearch => String::new(),
+    };
+    model.prompt_error = None;
+    // Prompts are opened from the filter menu, which they close.
+    model.filter_menu = false;
+    model.search_mode = SearchMode::Prompt(prompt);
+    model.cursor_pos = model.prompt_input.chars().count();
+    model.history_pos = None;
+}
+
+/// Applies what was typed into the prompt. An empty prompt clears what it
+/// sets. The prompt stays open with an error if the input is invalid.
+fn submit_prompt(model: &mut Model, prompt: Prompt) {
+    let input = model.prompt_input.trim();
+    match prompt {
+        Prompt::Regex if input.is_empty() => model.filters.regex = None,
+        Prompt::Regex => match Regex::new(input) {
+            Ok(regex) => model.filters.regex = Some(regex),
+            Err(_) => {
+                model.prompt_error = Some(String::from("invalid regex"));
+                return;
+            }
+        },
+        Prompt::Exclude if input.is_empty() => model.filters.exclude.clear(),
+        Prompt::Exclude => match Regex::new(input) {
+            Ok(regex) => model.filters.exclude.push(regex),
+            Err(_) => {
+                model.prompt_error = Some(String::from("invalid regex"));
+                return;
+            }
+        },
+        Prompt::TimeRange if input.is_empty() => model.filters.time_range = None,
+        Prompt::TimeRange => match TimeRange::parse(input, model.start_date()) {
+            Ok(range) => model.filters.time_range = Some(range),
+            Err(error) => {
+                model.prompt_error = Some(String::from(error));
+                return;
+            }
+        },
+        Prompt::Expression if input.is_empty() => model.filters.expression = None,
+        Prompt::Expression => match FilterExpr::parse(input) {
+            Ok(expression) => model.filters.expression = Some(expression),
+            Err(error) => {
+                model.prompt_error = Some(error);
+                return;
+            }
+        },
+        Prompt::Field if input.is_empty() => model.filters.fields.clear(),
+        Prompt::Field => match FilterExpr::parse_field(input) {
+            Ok(filter) => model.filters.fields.push(filter),
+            Err(error) => {
+                model.prompt_error = Some(error);
+                return;
+            }
+        },
+        Prompt::Command => match Command::parse(input) {
+            Ok(Command::Query(query)) => model.filters.expression = query,
+            Ok(Command::Context(context)) => model.context = context,
+            // Moving to a line leaves what's shown as it is.
+            Ok(Command::Marks) => {
+                close_prompt(model);
+                model.mark_list = Some(0);
+                return;
+            }
+            Ok(Command::Goto(goto)) => {
+                if let Err(error) = goto_line(model, goto) {
+                    model.prompt_error = Some(error);
+                    return;
+                }
+                close_prompt(model);
+                return;
+            }
+            Err(error) => {
+                model.prompt_error = Some(error);
+                return;
+            }
+        },
+        // Searching the preview leaves the selection where it is.
+        Prompt::PreviewSearch => {
+            model.preview_search = (!input.is_empty()).then(|| input.to_string());
+            model.preview_match = 0;
+            close_prompt(model);
+            return;
+        }
+        // Presets and the other tabs are kept by the app, which is sent what
+        // was typed instead.
+        Prompt::PresetName | Prompt::TabSearch => return,
+    }
+    close_prompt(model);
+    model.reset_view();
+}
+
+pub(crate) fn close_prompt(model: &mut Model) {
+    model.prompt_input.clear();
+    model.prompt_error = None;
+    model.reset_cursor();
+    model.search_mode = SearchMode::None;
+}
+
 /*****************************************************************************/
 
 pub(crate) fn update(model: &mut Model, msg: Message) -> Option<Message> {
+    // Any change to the filters becomes a stage of the chain, apart from
+    // undoing one.
+    let before = match msg {
+        Message::RefreshLogs | Message::PopFilter => None,
+        _ => Some(model.filters.clone()),
+    };
+    let selected = (model.view_offset, model.line_idx);
+    let search = model.search_input.clone();
+    let jumps = matches!(
+        msg,
+        Message::NextMatch
+            | Message::PrevMatch
+            | Message::SearchWord(_)
+            | Message::SubmitSearch
+            | Message::SubmitPrompt
+            | Message::RepeatSearch(_)
+            | Message::RepeatPrompt(..)
+            | Message::GotoPercent
+            | Message::MoveTop
+            | Message::MoveBottom
+            | Message::JumpToMark(_)
+            | Message::PickMark
+            | Message::JumpToLevel(..)
+            | Message::JumpToGap(_)
+            | Message::JumpToHot(_)
+            | Message::SelectMinimapRow(_)
+            | Message::RepeatMotion { .. }
+            | Message::PopFilter
+    );
+    let next = update_model(model, msg);
+    if model.search_input != search {
+        model.search_edited = Some(Instant::now());
+    }
+    let moved = (model.view_offset, model.line_idx) != selected;
+    // Another line is previewed, so its search starts over at the top.
+    if moved {
+        model.preview_match = 0;
+    }
+    let filtered = before
+        .as_ref()
+        .is_some_and(|before| before.labels() != model.filters.labels());
+    if ((jumps && moved) || filtered)
+        && let Some(from) = model.selected_line
+    {
+        model.jumps.record(from);
+    }
+    if let Some(before) = before {
+        record_stage(&mut model.filter_stages, before, &model.filters);
+    }
+    next
+}
+
+fn update_model(model: &mut Model, msg: Message) -> Option<Message> {
+    // A count is used by the key right after it, or dropped. The first `g`
+    // of `gg` leaves it for the second.
+    let count = match msg {
+        Message::RefreshLogs => model.pending_count,
+        Message::MoveTop if !model.g_modifier => model.pending_count,
+        _ => model.pending_count.take(),
+    };
+
+    if let Some(selected) = model.format_picker {
+        match msg {
+            Message::MoveUp => model.format_picker = Some(selected.saturating_sub(1)),
+            Message::MoveDown => {
+                model.format_picker = Some((selected + 1).min(LogFormat::CHOICES.len() - 1));
+            }
+            Message::PickFormat => {
+                model.format_picker = None;
+                model.set_format(LogFormat::CHOICES[selected]);
+            }
+            Message::CloseFormatPicker => model.format_picker = None,
+            _ => {}
+        }
+        // The log keeps refreshing behind the picker.
+        if !matches!(msg, Message::RefreshLogs) {
+            return None;
+        }
+    }
+
+    if let Some(picker) = &mut model.logger_picker {
+        match msg {
+            Message::MoveUp => picker.move_up(),
+            Message::MoveDown => picker.move_down(),
+            Message::IncludeLogger => {
+                if let Some(logger) = picker.selected_logger() {
+                    toggle(&mut model.filters.logger_include, logger);
+                    model.filters.logger_exclude.remove(logger);
+                }
+                model.reset_view();
+            }
+            Message::MuteLogger => {
+                if let Some(logger) = picker.selected_logger() {
+                    toggle(&mut model.filters.logger_exclude, logger);
+                    model.filters.logger_include.remove(logger);
+                }
+                model.reset_view();
+            }
+            Message::ClearLoggers => {
+                model.filters.logger_include.clear();
+                model.filters.logger_exclude.clear();
+                model.reset_view();
+            }
+            Message::CloseLoggerPicker => model.logger_picker = None,
+            _ => {}
+        }
+        // The log keeps refreshing behind the picker.
+        if !matches!(msg, Message::RefreshLogs) {
+            return None;
+        }
+    }
+
+    if let Some(selected) = model.mark_list {
+        let letters: Vec<char> = model
+            .marked_lines()
+            .iter()
+            .map(|(letter, _)| *letter)
+            .collect();
+        match msg {
+            Message::MoveUp => model.mark_list = Some(selected.saturating_sub(1)),
+            Message::MoveDown => {
+                model.mark_list = Some((selected + 1).min(letters.len().saturating_sub(1)));
+            }
+            Message::PickMark => {
+                model.mark_list = None;
+                if let Some(&letter) = letters.get(selected) {
+                    jump_to_mark(model, letter);
+                }
+            }
+            Message::DeleteMark => {
+                if let Some(letter) = letters.get(selected) {
+                    model.marks.remove(letter);
+                }
+                model.mark_list = Some(selected.min(letters.len().saturating_sub(2)));
+            }
+            Message::CloseMarks => model.mark_list = None,
+            _ => {}
+        }
+        // The log keeps refreshing behind the list.
+        if !matches!(msg, Message::RefreshLogs) {
+            return None;
+        }
+    }
+
+    if msg.repeatable() {
+        model.last_action = Some(msg.clone());
+    }
+    if msg.reversed().is_some() {
+        model.last_motion = Some(msg.clone());
+    }
+
     if model.g_modifier {
         match msg {
             Message::MoveTop => {
-                model.view_offset = 0xffff;
-                model.line_idx = 0;
+                model.g_modifier = false;
+                match count {
+                    Some(number) => goto_number(model, number),
+                    None => model.move_top(),
+                }
                 return None;
             }
             _ => model.g_modifier = false,
@@ -184,95 +1840,816 @@ pub(crate) fn update(model: &mut Model, msg: Message) -> Option<Message> {
     }
 
     match msg {
-        Message::MoveUp => {
-            if model.line_idx == 0 {
-                model.view_offset += 1;
-            } else {
-                model.line_idx -= 1;
-            }
-        }
-        Message::MoveDown => {
-            if model.line_idx == model.view_height - 1 {
-                model.view_offset = model.view_offset.checked_sub(1).unwrap_or(0);
-            } else {
-                model.line_idx += 1;
-            }
-        }
-        Message::ApplyFilter(f) => {
-            model.log_filter = f;
+        Message::MoveUp => model.move_up(count.unwrap_or(1)),
+        Message::MoveDown => model.move_down(count.unwrap_or(1)),
+        Message::RaiseMinLevel | Message::LowerMinLevel => {
+            // Debug is left out since it'd only hide lines without a level.
+            model.filters.min_level = match (&msg, model.filters.min_level) {
+                (Message::RaiseMinLevel, None) => Some(Level::Info),
+                (Message::RaiseMinLevel, Some(level)) => level.raised().or(Some(level)),
+                (_, Some(Level::Info) | None) => None,
+                (_, Some(level)) => level.lowered(),
+            };
             model.view_offset = 0;
             model.line_idx = 0;
         }
-        Message::Quit => {
-            model.running = RunningState::Done;
+        Message::OpenLoggerPicker => model.logger_picker = Some(LoggerPicker::new(&model.logs)),
+        Message::CycleSampling => {
+            let next = match &model.sampler {
+                None => SAMPLE_RATES.first(),
+                Some(sampler) => SAMPLE_RATES.iter().find(|rate| **rate > sampler.rate),
+            };
+            model.sampler = next.copied().map(Sampler::new);
+        }
+        Message::CycleSearchKind => {
+            model.search_kind = model.search_kind.next();
+            model.reset_view();
+        }
+        Message::TightenFuzzy | Message::LoosenFuzzy => {
+            let steps = match msg {
+                Message::TightenFuzzy => 1.0,
+                _ => -1.0,
+            };
+            model.fuzzy.tighten(steps);
+            model.reset_view();
         }
-        Message::ToggleSearch => match model.search_mode {
+        Message::CycleFuzzyScorer => {
+            model.fuzzy.next_scorer();
+            model.reset_view();
+        }
+        Message::CycleDedup => {
+            model.dedup = model.dedup.next();
+            model.reset_view();
+        }
+        Message::ToggleFold => toggle_fold(model),
+        Message::QuickFilter => apply_quick_filter(model),
+        Message::HideLine => hide_line(model, false),
+        Message::HideLikeLine => hide_line(model, true),
+        Message::ClearQuickFilter => {
+            model.filters.quick = None;
+            model.reset_view();
+        }
+        Message::PopFilter => {
+            pop_stage(&mut model.filter_stages, &mut model.filters);
+            model.reset_view();
+        }
+        Message::ToggleFilterMenu => model.filter_menu = !model.filter_menu,
+        Message::ToggleLevel(level) => {
+            if !model.filters.levels.remove(&level) {
+                model.filters.levels.insert(level);
+            }
+            model.reset_view();
+        }
+        Message::ClearLevels => {
+            model.filters.levels.clear();
+            model.reset_view();
+        }
+        Message::ToggleSearch | Message::SearchBackward => match model.search_mode {
             SearchMode::Search => {
                 reset_search(model);
             }
+            SearchMode::Prompt(_) => {}
             SearchMode::None => {
+                model.search_backward = matches!(msg, Message::SearchBackward);
+                // A submitted search is brought back to be edited.
                 model.search_mode = SearchMode::Search;
+                model.cursor_pos = model.search_input.chars().count();
+                model.history_pos = None;
+            }
+        },
+        Message::NarrowSearch => {
+            if let Ok(matcher) = model.matcher() {
+                let search = std::mem::take(&mut model.search_input);
+                model.narrowed.push((search, matcher));
+            }
+            model.search_mode = SearchMode::Search;
+            model.search_backward = false;
+            model.reset_cursor();
+            model.history_pos = None;
+            model.reset_view();
+        }
+        Message::PopNarrowing => {
+            if let Some((search, _)) = model.narrowed.pop() {
+                model.cursor_pos = search.chars().count();
+                model.search_input = search;
+                model.reset_view();
+            }
+        }
+        Message::SubmitSearch => {
+            model.search_mode = SearchMode::None;
+            jump_to_match(model, Jump::Nearest);
+            if !model.search_input.is_empty() {
+                model.last_action = Some(Message::RepeatSearch(model.search_input.clone()));
+            }
+        }
+        Message::RepeatSearch(search) => {
+            model.search_input = search;
+            model.search_mode = SearchMode::None;
+            model.reset_cursor();
+            match model.search_backward {
+                true => jump_to_match(model, Jump::Previous),
+                false => jump_to_match(model, Jump::Next),
+            }
+        }
+        Message::NextMatch if model.search_backward => jump_to_match(model, Jump::Previous),
+        Message::PrevMatch if model.search_backward => jump_to_match(model, Jump::Next),
+        Message::NextMatch => jump_to_match(model, Jump::Next),
+        Message::SearchWord(jump) => search_word(model, jump),
+        Message::PinSearch => pin_search(model),
+        Message::AwaitMark(key) => model.pending_mark = Some(key),
+        Message::TogglePinLine => toggle_pinned_line(model),
+        Message::RepeatAction => return model.last_action.clone(),
+        Message::RepeatMotion { reverse } => {
+            if let Some(motion) = model.last_motion.clone() {
+                let repeated = match reverse {
+                    true => motion.reversed(),
+                    false => Some(motion.clone()),
+                };
+                let next = repeated.and_then(|repeated| update_model(model, repeated));
+                // Reversing it once doesn't turn it round for good.
+                model.last_motion = Some(motion);
+                return next;
+            }
+        }
+        Message::SetMark(letter) => {
+            model.pending_mark = None;
+            set_mark(model, letter);
+        }
+        Message::JumpToMark(letter) => {
+            model.pending_mark = None;
+            jump_to_mark(model, letter);
+        }
+        Message::AwaitJump(jump) => model.pending_jump = Some(jump),
+        Message::AwaitAlign => model.pending_align = true,
+        Message::AlignSelection(place) => {
+            model.pending_align = false;
+            model.align_selection(place);
+        }
+        Message::JumpToLevel(level, jump) => {
+            model.pending_jump = None;
+            jump_to_line(model, jump, |entry| entry.level == Some(level));
+        }
+        Message::JumpToGap(jump) => {
+            model.pending_jump = None;
+            let logs = listed_logs(model);
+            let gaps = model.gap_threshold.gaps(&logs);
+            jump_to_index(model, logs.len(), jump, |idx| gaps[idx]);
+        }
+        Message::JumpToHot(jump) => {
+            model.pending_jump = None;
+            let logs = listed_logs(model);
+            let starts = hot_starts(&logs, model.view_height);
+            jump_to_index(model, logs.len(), jump, |idx| starts[idx]);
+        }
+        Message::ToggleMinimap => model.show_minimap = !model.show_minimap,
+        Message::SelectMinimapRow(row) => {
+            let logs = listed_logs(model);
+            if let Some(lines) = minimap_rows(logs.len(), model.view_height).get(row) {
+                let idx = hottest_line(&logs, lines.clone());
+                model.select_index(idx, logs.len());
+            }
+        }
+        Message::CancelPendingKeys => model.clear_pending_keys(),
+        Message::JumpBack => {
+            if let Some(current) = model.selected_line
+                && let Some(place) = model.jumps.back(current)
+            {
+                select_place(model, place);
+            }
+        }
+        Message::JumpForward => {
+            if let Some(place) = model.jumps.forward() {
+                select_place(model, place);
+            }
+        }
+        Message::ToggleSearchStyle => model.search_style = model.search_style.toggled(),
+        Message::NextPreviewMatch => {
+            model.preview_match = (model.preview_match + 1) % model.preview_matches.max(1);
+        }
+        Message::PrevPreviewMatch => {
+            model.preview_match = model
+                .preview_match
+                .checked_sub(1)
+                .unwrap_or(model.preview_matches.saturating_sub(1));
+        }
+        Message::PrevMatch => jump_to_match(model, Jump::Previous),
+        Message::OpenPrompt(prompt) => open_prompt(model, prompt),
+        Message::SubmitPrompt => match model.search_mode {
+            SearchMode::Prompt(Prompt::PresetName) => {
+                return Some(Message::SavePreset(model.prompt_input.trim().to_string()));
+            }
+            SearchMode::Prompt(Prompt::TabSearch) => {
+                return Some(Message::SearchTabs(model.prompt_input.trim().to_string()));
+            }
+            SearchMode::Prompt(prompt) => {
+                let input = model.prompt_input.trim().to_string();
+                submit_prompt(model, prompt);
+                // Only what changes the filters, or moves to a line, is done
+                // again by `.`, once it's been applied.
+                if prompt.repeatable()
+                    && model.search_mode == SearchMode::None
+                    && model.mark_list.is_none()
+                {
+                    model.last_action = Some(Message::RepeatPrompt(prompt, input));
+                }
             }
+            _ => {}
         },
+        Message::RepeatPrompt(prompt, input) => {
+            model.prompt_input = input;
+            submit_prompt(model, prompt);
+            close_prompt(model);
+        }
+        Message::CancelPrompt => close_prompt(model),
         Message::AddChar(c) => enter_char(model, c),
+        Message::Paste(text) => paste(model, &text),
         Message::Delete => delete_char(model),
+        Message::DeleteForward => delete_chars(model, model.cursor_pos..model.cursor_pos + 1),
+        Message::DeleteWord => delete_chars(model, word_start(model)..model.cursor_pos),
+        Message::DeleteToStart => delete_chars(model, 0..model.cursor_pos),
+        Message::MoveCursorStart => model.reset_cursor(),
+        Message::MoveCursorEnd => model.cursor_pos = model.input().chars().count(),
+        Message::MoveCursorWordLeft => model.cursor_pos = word_start(model),
+        Message::MoveCursorWordRight => model.cursor_pos = word_end(model),
         Message::MoveCursorLeft => move_cursor_left(model),
         Message::MoveCursorRight => move_cursor_right(model),
         Message::MoveTop => model.g_modifier = true,
-        Message::MoveBottom => {
+        Message::MoveBottom => match count {
+            Some(number) => goto_number(model, number),
+            None => {
+                model.move_bottom();
+                model.follow_paused = false;
+            }
+        },
+        Message::MoveUpPage => model.scroll_up(model.view_height),
+        Message::MoveDownPage => model.scroll_down(model.view_height),
+        Message::MoveUpHalfPage => model.scroll_up(model.view_height / 2),
+        Message::MoveDownHalfPage => model.scroll_down(model.view_height / 2),
+        Message::SelectOnScreen(place) => model.select_on_screen(place),
+        Message::AddCountDigit(digit) => {
+            model.pending_count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        }
+        Message::WheelUp => model.scroll_up(WHEEL_ROWS),
+        Message::WheelDown => model.scroll_down(WHEEL_ROWS),
+        Message::SelectRow(row) => model.line_idx = row,
+        Message::Resizing(resizing) => model.resizing = resizing,
+        Message::ResizePanes(percent) => {
+            model.list_percent = Some(percent.clamp(MIN_PANE_PERCENT, 100 - MIN_PANE_PERCENT));
+        }
+        Message::ScrollLeft => model.scroll_x = model.scroll_x.saturating_sub(SCROLL_STEP),
+        Message::ScrollRight => model.scroll_x = (model.scroll_x + SCROLL_STEP).min(model.overflow),
+        Message::ScrollStart => model.scroll_x = 0,
+        Message::ScrollEnd => model.scroll_x = model.overflow,
+        Message::GotoPercent => {
+            if let Some(percent) = count {
+                model.goto_percent(percent);
+            }
+        }
+        Message::RefreshLogs => {
+            model.refresh_logs();
+            if model.follow && !model.follow_paused {
+                model.move_bottom();
+            }
+        }
+        Message::ToggleFollow => {
+            model.follow = !model.follow;
+            model.follow_paused = false;
+            if model.follow {
+                model.move_bottom();
+            }
+        }
+        Message::ClearLogs => model.clear_logs(),
+        Message::ToggleColors => model.strip_colors = !model.strip_colors,
+        Message::CycleGutter => model.gutter = model.gutter.next(),
+        Message::OpenFormatPicker => model.open_format_picker(model.format()),
+        Message::ToggleGroups => {
+            model.expand_groups = !model.expand_groups;
             model.view_offset = 0;
-            model.line_idx = model.view_height.checked_sub(1).unwrap_or(0);
+            model.line_idx = 0;
         }
-        Message::MoveUpPage => model.move_up_page(),
-        Message::MoveDownPage => model.move_down_page(),
+        // Handled above while the marks are listed.
+        Message::PickMark | Message::DeleteMark | Message::CloseMarks => {}
+        // Handled by the app before reaching a tab's model.
+        Message::Quit
+        | Message::DuplicateTab
+        | Message::NextTab
+        | Message::PrevTab
+        | Message::OpenPicker
+        | Message::OpenPicked
+        | Message::ClosePicker
+        | Message::PickFormat
+        | Message::CloseFormatPicker
+        | Message::OpenPresets
+        | Message::PickPreset
+        | Message::ClosePresets
+        | Message::SavePreset(_)
+        | Message::SearchTabs(_)
+        | Message::OpenTabMatch
+        | Message::CloseTabSearch
+        | Message::HistoryOlder
+        | Message::HistoryNewer
+        | Message::HistoryMatching
+        | Message::ToggleCase
+        | Message::CloseLoggerPicker
+        | Message::IncludeLogger
+        | Message::MuteLogger
+        | Message::ClearLoggers => {}
     };
     None
 }
 
-pub(crate) fn get_filtered_logs(model: &mut Model) -> Vec<String> {
-    let filter_str = match model.log_filter {
-        Filter::INFO => "INFO",
-        Filter::WARNING => "WARNING",
-        Filter::ERROR => "ERROR",
-        Filter::CRITICAL => "CRITICAL",
-        Filter::DEBUG => "DEBUG",
-        Filter::NONE | Filter::SELECT => "",
+pub(crate) fn get_filtered_logs(model: &mut Model) -> Vec<LogEntry> {
+    let logs = listed_logs(model);
+
+    // The view is kept by its bottom, so rows come and go at its top as it's
+    // resized, moving the selection off its line.
+    if mem::take(&mut model.resized)
+        && let Some((source, number)) = model.selected_line
+        && let Some(idx) = logs
+            .iter()
+            .position(|entry| entry.source == source && entry.number == number)
+    {
+        model.select_index(idx, logs.len());
+    }
+    if model.view_height > 0
+        && let Some((number, row)) = model.restored_line.take()
+    {
+        model.select_at_row(&logs, number, row);
+    }
+    model.settle_selection(logs.len());
+    let window = model.window(logs.len());
+    model.percent_through = (!logs.is_empty()).then(|| window.end * 100 / logs.len());
+    model.first_shown = window.start;
+    if model.show_minimap {
+        model.minimap = Minimap::new(&logs, model.view_height, window.clone());
+    }
+    let selected = window.start + model.line_idx;
+    model.selected_line = logs.get(selected).map(|entry| (entry.source, entry.number));
+    // Coming back to the newest line some other way, like with `j`, leaves
+    // following paused.
+    model.follow_paused |= model.follow && selected + 1 < logs.len();
+    model.search_position = match model.listing.as_ref().map(|listing| &listing.searched) {
+        None | Some(Searched::Nothing) => None,
+        Some(Searched::Filtered) => Some(SearchPosition {
+            current: (selected < logs.len()).then_some(selected + 1),
+            total: logs.len(),
+        }),
+        Some(Searched::Marked(matched)) => Some(SearchPosition {
+            current: matched.binary_search(&selected).ok().map(|idx| idx + 1),
+            total: matched.len(),
+        }),
     };
+    logs[window].to_vec()
+}
 
-    let mut logs = model
+/// Every line the filters let through, before it's searched or cut down to
+/// what fits in the view.
+fn filter_logs(model: &mut Model) -> Vec<LogEntry> {
+    // Lines without a timestamp, like a wrapped message, are taken to be
+    // logged with the line above them.
+    let mut last_timestamp = None;
+    let mut hidden_count = 0;
+    let case = model.case;
+    let regex = model.filters.regex.as_ref().map(|regex| case.regex(regex));
+    let exclude: Vec<Regex> = model
+        .filters
+        .exclude
+        .iter()
+        .map(|regex| case.regex(regex))
+        .collect();
+    let matched: Vec<usize> = model
         .logs
         .iter()
-        .filter(|line| line.contains(filter_str))
-        .map(|l| l.to_string())
-        .collect::<Vec<String>>();
-
-    match apply_search(model, &mut logs) {
-        true => logs,
-        false => {
-            if model.view_offset + model.view_height > logs.len() {
-                model.view_offset = logs.len().checked_sub(model.view_height).unwrap_or(0);
+        .enumerate()
+        .filter(|(_, entry)| {
+            if entry.timestamp.is_some() {
+                last_timestamp = entry.timestamp;
             }
+            model
+                .filters
+                .time_range
+                .as_ref()
+                .is_none_or(|range| last_timestamp.is_some_and(|ts| range.contains(ts)))
+        })
+        .filter(|(_, entry)| {
+            model.filters.levels.is_empty()
+                || entry
+                    .level
+                    .is_some_and(|level| model.filters.levels.contains(&level))
+        })
+        .filter(|(_, entry)| {
+            model
+                .filters
+                .min_level
+                .is_none_or(|min| entry.level.is_some_and(|level| level >= min))
+        })
+        .filter(|(_, entry)| {
+            regex.as_ref().is_none_or(|regex| {
+                // Grouped lines count too, so a trace can be found by a frame.
+                regex.is_match(&entry.line)
+                    || entry.continuation.iter().any(|line| regex.is_match(line))
+            })
+        })
+        .filter(|(_, entry)| {
+            // As with the regex, a grouped line can hide the whole entry.
+            !exclude.iter().any(|regex| {
+                regex.is_match(&entry.line)
+                    || entry.continuation.iter().any(|line| regex.is_match(line))
+            })
+        })
+        .filter(|(_, entry)| {
+            model
+                .filters
+                .expression
+                .as_ref()
+                .is_none_or(|expression| expression.matches(entry))
+        })
+        .filter(|(_, entry)| {
+            model
+                .filters
+                .fields
+                .iter()
+                .all(|filter| filter.matches(entry))
+        })
+        .filter(|(_, entry)| {
+            let logger = logger_of(entry);
+            (model.filters.logger_include.is_empty()
+                || logger.is_some_and(|logger| model.filters.logger_include.contains(logger)))
+                && logger.is_none_or(|logger| !model.filters.logger_exclude.contains(logger))
+        })
+        .filter(|(_, entry)| {
+            model.filters.quick.as_ref().is_none_or(|word| {
+                case.contains(&entry.line, word)
+                    || entry
+                        .continuation
+                        .iter()
+                        .any(|line| case.contains(line, word))
+            })
+        })
+        .filter(|(_, entry)| {
+            let hidden = model.filters.hidden.contains(&entry.line)
+                || (!model.filters.hidden_templates.is_empty()
+                    && model
+                        .filters
+                        .hidden_templates
+                        .contains(&line_template(&entry.line)));
+            hidden_count += usize::from(hidden);
+            !hidden
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+    model.hidden_count = hidden_count;
+
+    let mut logs: Vec<LogEntry> = match model.context.is_empty() {
+        true => matched.iter().map(|&idx| model.logs[idx].clone()).collect(),
+        false => model.context.around(&model.logs, &matched),
+    };
+
+    if model.dedup != Dedup::Off {
+        logs = collapse_repeats(logs, model.dedup, &model.expanded_repeats);
+    }
+    if model.expand_groups || !model.expanded_groups.is_empty() {
+        // The grouped lines become rows of their own under the entry.
+        logs = logs
+            .into_iter()
+            .flat_map(|mut entry| {
+                let (source, number) = (entry.source, entry.number);
+                let expanded =
+                    model.expand_groups || model.expanded_groups.contains(&(source, number));
+                let lines = match expanded {
+                    true => std::mem::take(&mut entry.continuation),
+                    false => vec![],
+                };
+                let lines = lines
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(i, line)| LogEntry {
+                        number: number + i + 1,
+                        continued: true,
+                        ..LogEntry::new(line, source)
+                    });
+                [entry].into_iter().chain(lines)
+            })
+            .collect();
+    }
+    if !model.narrowed.is_empty() {
+        logs.retain(|entry| {
+            model
+                .narrowed
+                .iter()
+                .all(|(_, matcher)| matcher.matches(entry))
+        });
+    }
+    logs
+}
+
+fn count_matches(model: &mut Model, logs: &[LogEntry]) {
+    let mut counts = MatchCounts {
+        total: model
+            .logs
+            .iter()
+            .map(|entry| {
+                match model.expand_groups
+                    || model
+                        .expanded_groups
+                        .contains(&(entry.source, entry.number))
+                {
+                    true => 1 + entry.continuation.len(),
+                    false => 1,
+                }
+            })
+            .sum(),
+        ..MatchCounts::default()
+    };
+    // Context is only shown for the lines that match, and a row of collapsed
+    // repeats stands for all of them.
+    for entry in logs.iter().filter(|entry| !entry.context) {
+        let lines = entry.repeats.max(1);
+        counts.matched += lines;
+        if let Some(level) = entry.level {
+            *counts.levels.entry(level).or_default() += lines;
+        }
+    }
+    model.counts = counts;
+}
 
-            let end_idx = logs
-                .len()
-                .checked_sub(model.view_offset)
-                .unwrap_or(model.view_height);
-            let start_idx = end_idx.checked_sub(model.view_height).unwrap_or(0);
-            logs.drain(start_idx..end_idx).collect()
+/// Keeps the lines the search matches while it's being typed. Once it's
+/// submitted the lines are all kept, to jump between the matches.
+fn apply_search(model: &mut Model, logs: &mut Vec<LogEntry>) -> Searched {
+    model.search_error = None;
+    if model.applied_search.is_empty() {
+        return Searched::Nothing;
+    }
+    let matcher = Matcher::new(
+        &model.applied_search,
+        model.search_kind,
+        model.case,
+        model.fuzzy,
+        model.start_date(),
+    );
+    let hits = match matcher {
+        Ok(matcher) => match_lines(model, logs, &matcher),
+        Err(error) => {
+            model.search_error = Some(error);
+            vec![false; logs.len()]
         }
+    };
+    if !model.search_filters() {
+        let matched = hits.iter().enumerate().filter(|(_, hit)| **hit);
+        return Searched::Marked(matched.map(|(idx, _)| idx).collect());
     }
+    let mut hits = hits.into_iter();
+    logs.retain(|_| hits.next().unwrap_or(false));
+    Searched::Filtered
+}
+
+/// Tells which lines the search matches. Lines the last search ruled out are
+/// skipped when this one only narrows it down, and the lines this one rules
+/// out are kept for the next.
+fn match_lines(model: &mut Model, logs: &[LogEntry], matcher: &Matcher) -> Vec<bool> {
+    let (search, kind, case) = (&model.applied_search, model.search_kind, model.case);
+    let cache = model
+        .search_cache
+        .take()
+        .filter(|cache| cache.narrows(search, kind, case));
+    let hits: Vec<bool> = logs
+        .iter()
+        .map(|entry| {
+            !cache
+                .as_ref()
+                .is_some_and(|cache| cache.missed((entry.source, entry.number)))
+                && matcher.matches(entry)
+        })
+        .collect();
+    model.search_cache = SearchCache::new(search, kind, case, || {
+        logs.iter()
+            .zip(&hits)
+            .filter(|(_, hit)| !**hit)
+            .map(|(entry, _)| (entry.source, entry.number))
+            .collect()
+    });
+    hits
 }
 
-fn apply_search(model: &mut Model, logs: &mut Vec<String>) -> bool {
-    if !model.search_input.is_empty() {
-        let search_logs: Vec<&str> = logs.iter().map(|log| log.as_str()).collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SourceMetadata;
+    use std::collections::VecDeque;
+
+    /// A source handing out the batches of lines it's given, in turn.
+    #[derive(Debug)]
+    struct Batches(VecDeque<NewLines>);
+
+    impl LogSource for Batches {
+        fn open(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn has_new_lines(&mut self) -> bool {
+            !self.0.is_empty()
+        }
+
+        fn poll_new_lines(&mut self) -> NewLines {
+            self.0.pop_front().unwrap_or(NewLines::Appended(vec![]))
+        }
+
+        fn metadata(&self) -> SourceMetadata {
+            SourceMetadata::default()
+        }
+    }
+
+    fn entries(lines: &[String]) -> Vec<LogEntry> {
+        lines
+            .iter()
+            .map(|line| LogEntry::new(line.clone(), 0))
+            .collect()
+    }
+
+    /// A model reading the first batch, with the rest left for refreshes.
+    fn model_of(batches: Vec<NewLines>, options: &ParseOptions) -> Model {
+        Model::from_source(Box::new(Batches(batches.into())), options).unwrap()
+    }
+
+    fn shown(model: &Model) -> Vec<String> {
+        model.logs.iter().map(|entry| entry.line.clone()).collect()
+    }
+
+    #[test]
+    fn samples_lines_read_again_as_well_as_appended_ones() {
+        let info: Vec<String> = (0..12).map(|idx| format!("INFO {idx}")).collect();
+        let mut reloaded = info.clone();
+        reloaded.insert(5, String::from("ERROR failed"));
+        let options = ParseOptions {
+            sample_rate: Some(10),
+            ..ParseOptions::default()
+        };
+        let mut model = model_of(
+            vec![
+                NewLines::Reloaded(entries(&reloaded)),
+                NewLines::Appended(entries(&info)),
+                NewLines::Reloaded(entries(&info[..2])),
+            ],
+            &options,
+        );
+        assert_eq!(shown(&model), ["INFO 0", "ERROR failed", "INFO 9"]);
+
+        // The count of lines seen carries on into the appended ones.
+        model.refresh_logs();
+        assert_eq!(
+            shown(&model),
+            ["INFO 0", "ERROR failed", "INFO 9", "INFO 7"]
+        );
+
+        // Reading the lines again starts sampling them over.
+        model.refresh_logs();
+        assert_eq!(shown(&model), ["INFO 0"]);
+        assert_eq!(
+            model.sampler.as_ref().map(|sampler| sampler.dropped),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn selects_lines_in_a_view_too_short_to_show_any() {
+        let lines: Vec<String> = (0..10).map(|idx| format!("line {idx}")).collect();
+        let mut model = model_of(
+            vec![NewLines::Reloaded(entries(&lines))],
+            &ParseOptions::default(),
+        );
+        model.view_height = 0;
+
+        model.select_index(5, 10);
+        model.place_at_row(5, 2, 10);
+        model.goto_percent(50);
+        model.align_selection(ScreenPlace::Middle);
+        assert_eq!(model.line_idx, 0);
+    }
+
+    #[test]
+    fn follows_new_lines_until_moved_off_the_newest() {
+        let lines = |range: Range<usize>| -> Vec<String> {
+            range.map(|idx| format!("line {idx}")).collect()
+        };
+        let mut model = model_of(
+            vec![
+                NewLines::Reloaded(entries(&lines(0..5))),
+                NewLines::Appended(entries(&lines(5..7))),
+                NewLines::Appended(entries(&lines(7..8))),
+                NewLines::Appended(entries(&lines(8..9))),
+            ],
+            &ParseOptions::default(),
+        );
+        model.view_height = 3;
+        model.select_index(0, 5);
+
+        update(&mut model, Message::ToggleFollow);
+        assert_eq!(selected(&mut model), "line 4");
+        update(&mut model, Message::RefreshLogs);
+        assert_eq!(selected(&mut model), "line 6");
+
+        // Moving up pauses following once it's drawn, keeping the line in
+        // place.
+        update(&mut model, Message::MoveUp);
+        get_filtered_logs(&mut model);
+        update(&mut model, Message::RefreshLogs);
+        assert!(model.follow_paused);
+        assert_eq!(selected(&mut model), "line 5");
 
-        *logs = fuzzy_search_threshold(&model.search_input, &search_logs, 0.4)
+        // `G` takes it up again.
+        update(&mut model, Message::MoveBottom);
+        update(&mut model, Message::RefreshLogs);
+        assert_eq!(selected(&mut model), "line 8");
+
+        update(&mut model, Message::ToggleFollow);
+        assert!(!model.follow);
+    }
+
+    /// The line selected, by its text.
+    fn selected(model: &mut Model) -> String {
+        let logs = listed_logs(model);
+        let idx = model.window(logs.len()).start + model.line_idx;
+        logs[idx].line.clone()
+    }
+
+    #[test]
+    fn repeats_the_last_jump_either_way() {
+        let lines: Vec<String> = ["INFO a", "ERROR b", "INFO c", "ERROR d", "ERROR e"]
             .iter()
-            .map(|res| res.0.to_string())
-            .rev()
+            .map(|line| line.to_string())
             .collect();
-        return true;
-    };
-    false
+        let mut model = model_of(
+            vec![NewLines::Reloaded(entries(&lines))],
+            &ParseOptions::default(),
+        );
+        model.view_height = 10;
+
+        update(&mut model, Message::JumpToLevel(Level::Error, Jump::Next));
+        assert_eq!(selected(&mut model), "ERROR b");
+        update(&mut model, Message::RepeatMotion { reverse: false });
+        assert_eq!(selected(&mut model), "ERROR d");
+        update(&mut model, Message::RepeatMotion { reverse: true });
+        assert_eq!(selected(&mut model), "ERROR b");
+        // `;` still goes the way `]e` did.
+        update(&mut model, Message::RepeatMotion { reverse: false });
+        update(&mut model, Message::RepeatMotion { reverse: false });
+        assert_eq!(selected(&mut model), "ERROR e");
+
+        model.search_input = String::from("INFO");
+        model.select_index(0, lines.len());
+        update(&mut model, Message::NextMatch);
+        assert_eq!(selected(&mut model), "INFO c");
+        update(&mut model, Message::RepeatMotion { reverse: true });
+        assert_eq!(selected(&mut model), "INFO a");
+        update(&mut model, Message::RepeatMotion { reverse: false });
+        assert_eq!(selected(&mut model), "INFO c");
+    }
+
+    #[test]
+    fn jumps_to_where_the_log_went_quiet() {
+        let lines: Vec<String> = [
+            "2024-05-03 14:00:00 INFO start",
+            "2024-05-03 14:00:05 INFO ready",
+            "2024-05-03 14:02:00 WARN slow",
+            "2024-05-03 14:02:01 INFO ok",
+            "2024-05-03 14:05:00 ERROR hung",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        let mut model = model_of(
+            vec![NewLines::Reloaded(entries(&lines))],
+            &ParseOptions::default(),
+        );
+        model.view_height = 10;
+
+        update(&mut model, Message::JumpToGap(Jump::Next));
+        assert_eq!(selected(&mut model), "2024-05-03 14:02:00 WARN slow");
+        update(&mut model, Message::JumpToGap(Jump::Next));
+        assert_eq!(selected(&mut model), "2024-05-03 14:05:00 ERROR hung");
+        update(&mut model, Message::JumpToGap(Jump::Previous));
+        assert_eq!(selected(&mut model), "2024-05-03 14:02:00 WARN slow");
+    }
+
+    #[test]
+    fn tab_folds_only_the_lines_of_a_group() {
+        let lines: Vec<String> = ["ERROR failed", "    at main.rs:3", "INFO done"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let mut model = model_of(
+            vec![NewLines::Reloaded(entries(&lines))],
+            &ParseOptions::default(),
+        );
+        model.view_height = 10;
+        assert!(model.selects_group());
+
+        update(&mut model, Message::ToggleFold);
+        model.select_index(1, 3);
+        assert_eq!(selected(&mut model), "    at main.rs:3");
+        assert!(model.selects_group());
+
+        model.select_index(2, 3);
+        assert!(!model.selects_group());
+    }
 }
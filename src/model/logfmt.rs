@@ -0,0 +1,96 @@
+use crate::{LEVEL_KEYS, MESSAGE_KEYS, StructuredLine, TIMESTAMP_KEYS, normalize_level};
+
+/// Parses a logfmt line, `level=warn msg="slow query" ms=1200`, as written by
+/// logrus, go-kit and slog's text handler.
+///
+/// Every token has to be a `key=value` pair and there have to be at least two
+/// of them, so prose that happens to contain an `=` isn't mistaken for it.
+pub(crate) fn parse_logfmt(line: &str) -> Option<StructuredLine> {
+    let mut pairs = vec![];
+    let mut rest = line.trim();
+    while !rest.is_empty() {
+        let (key, after_key) = rest.split_once('=')?;
+        if key.is_empty() || key.contains([' ', '"']) {
+            return None;
+        }
+        let (value, after_value) = parse_value(after_key)?;
+        pairs.push((key.to_string(), value));
+        rest = after_value.trim_start();
+    }
+
+    if pairs.len() < 2 {
+        return None;
+    }
+
+    let mut take_first = |keys: &[&str]| {
+        let idx = keys
+            .iter()
+            .find_map(|key| pairs.iter().position(|(name, _)| name == key))?;
+        Some(pairs.remove(idx).1)
+    };
+    let timestamp = take_first(&TIMESTAMP_KEYS);
+    let level =
+        take_first(&LEVEL_KEYS).map(|level| normalize_level(&level).map_or(level, String::from));
+    let message = take_first(&MESSAGE_KEYS);
+
+    Some(StructuredLine {
+        timestamp,
+        level,
+        message,
+        fields: pairs,
+    })
+}
+
+/// Reads a bare or quoted value, returning it along with the rest of the line.
+fn parse_value(text: &str) -> Option<(String, &str)> {
+    let Some(quoted) = text.strip_prefix('"') else {
+        let end = text.find(' ').unwrap_or(text.len());
+        return Some((text[..end].to_string(), &text[end..]));
+    };
+
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &quoted[idx + 1..])),
+            '\\' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                escaped => value.push(escaped),
+            },
+            c => value.push(c),
+        }
+    }
+    // An unterminated quote isn't logfmt.
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_a_logfmt_line_into_its_parts() {
+        let line = parse_logfmt(
+            r#"time=2024-05-03T14:02:10Z level=warn msg="slow \"orders\" query" ms=1200 db="""#,
+        )
+        .unwrap();
+        assert_eq!(line.timestamp.as_deref(), Some("2024-05-03T14:02:10Z"));
+        assert_eq!(line.level.as_deref(), Some("WARNING"));
+        assert_eq!(line.message.as_deref(), Some(r#"slow "orders" query"#));
+        assert_eq!(
+            line.fields,
+            [
+                (String::from("ms"), String::from("1200")),
+                (String::from("db"), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_prose_with_an_equals_sign() {
+        assert!(parse_logfmt("retrying with timeout=30").is_none());
+        assert!(parse_logfmt("timeout=30").is_none());
+        assert!(parse_logfmt(r#"level=info msg="unterminated"#).is_none());
+    }
+}
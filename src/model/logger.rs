@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use crate::LogEntry;
+
+/// Fields structured lines name the logger or module that wrote them by,
+/// tried in order.
+const LOGGER_KEYS: [&str; 8] = [
+    "logger",
+    "logger_name",
+    "loggerName",
+    "module",
+    "component",
+    "category",
+    "scope",
+    "name",
+];
+
+/// The logger or module that wrote the entry, if its format says.
+pub(crate) fn logger_of(entry: &LogEntry) -> Option<&str> {
+    let fields = &entry.structured.as_ref()?.fields;
+    LOGGER_KEYS.iter().find_map(|key| {
+        fields
+            .iter()
+            .find(|(field, _)| field == key)
+            .map(|(_, value)| value.as_str())
+    })
+}
+
+/// The loggers picked from while the logger picker is open.
+#[derive(Debug, Default)]
+pub(crate) struct LoggerPicker {
+    /// Each logger in the log with how many lines it wrote, the busiest
+    /// first.
+    pub(crate) loggers: Vec<(String, usize)>,
+    pub(crate) selected: usize,
+}
+
+impl LoggerPicker {
+    pub(crate) fn new(logs: &[LogEntry]) -> LoggerPicker {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for logger in logs.iter().filter_map(logger_of) {
+            *counts.entry(logger).or_default() += 1;
+        }
+        let mut loggers: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(logger, count)| (logger.to_string(), count))
+            .collect();
+        loggers.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+
+        LoggerPicker {
+            loggers,
+            selected: 0,
+        }
+    }
+
+    pub(crate) fn selected_logger(&self) -> Option<&str> {
+        self.loggers
+            .get(self.selected)
+            .map(|(logger, _)| logger.as_str())
+    }
+
+    pub(crate) fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub(crate) fn move_down(&mut self) {
+        self.selected = (self.selected + 1).min(self.loggers.len().saturating_sub(1));
+    }
+}
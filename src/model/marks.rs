@@ -0,0 +1,30 @@
+use std::collections::BTreeMap;
+
+use crate::LogEntry;
+
+/// What the letter typed after `m` or `'` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MarkKey {
+    /// Marks the selected line with the letter.
+    Set,
+    /// Goes back to the line marked with the letter.
+    Jump,
+}
+
+/// Lines marked with a letter, like vim's marks. Each is kept by the file
+/// it's in and its number there, so it's found again whatever the filters
+/// show.
+pub(crate) type Marks = BTreeMap<char, (usize, usize)>;
+
+/// The marked lines in the order of their letters, leaving out the ones no
+/// longer in the log, like after it's cleared.
+pub(crate) fn marked_lines<'a>(marks: &Marks, logs: &'a [LogEntry]) -> Vec<(char, &'a LogEntry)> {
+    marks
+        .iter()
+        .filter_map(|(&letter, &(source, number))| {
+            logs.iter()
+                .find(|entry| entry.source == source && entry.number == number)
+                .map(|entry| (letter, entry))
+        })
+        .collect()
+}
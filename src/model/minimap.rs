@@ -0,0 +1,164 @@
+use std::ops::Range;
+
+use crate::{Level, LogEntry};
+
+/// How many columns the minimap takes at the right edge: one marking the
+/// lines in view and two for how hot they are.
+pub(crate) const MINIMAP_WIDTH: u16 = 3;
+
+/// How hot a row of the minimap is: the most severe level of the lines it
+/// stands for, and what share of them are at it.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct Heat {
+    pub(crate) level: Option<Level>,
+    pub(crate) share: f32,
+}
+
+impl Heat {
+    fn of(logs: &[LogEntry]) -> Heat {
+        let level = logs.iter().filter_map(|entry| entry.level).max();
+        let at_level = logs
+            .iter()
+            .filter(|entry| level.is_some() && entry.level == level)
+            .count();
+        Heat {
+            level,
+            share: at_level as f32 / logs.len().max(1) as f32,
+        }
+    }
+
+    /// Rows with errors in them, where `]h` and `[h` stop.
+    pub(crate) fn is_hot(self) -> bool {
+        self.level >= Some(Level::Error)
+    }
+}
+
+/// The whole list of lines squeezed into a column as high as the view, so
+/// the clusters of errors in a long log stand out.
+#[derive(Debug, Default)]
+pub(crate) struct Minimap {
+    /// How hot each row is, from the top.
+    pub(crate) rows: Vec<Heat>,
+    /// The rows standing for the lines in view.
+    pub(crate) in_view: Range<usize>,
+}
+
+impl Minimap {
+    pub(crate) fn new(logs: &[LogEntry], rows: usize, window: Range<usize>) -> Minimap {
+        let lines = minimap_rows(logs.len(), rows);
+        let first = lines.iter().position(|lines| lines.end > window.start);
+        let last = lines.iter().rposition(|lines| lines.start < window.end);
+        let in_view = first.unwrap_or(0)..last.map_or(0, |row| row + 1);
+        Minimap {
+            rows: lines
+                .into_iter()
+                .map(|lines| Heat::of(&logs[lines]))
+                .collect(),
+            in_view,
+        }
+    }
+}
+
+/// The lines each row of a minimap this many rows high stands for, as even
+/// as they go. A list shorter than the minimap takes a row per line.
+pub(crate) fn minimap_rows(len: usize, rows: usize) -> Vec<Range<usize>> {
+    let rows = rows.min(len);
+    (0..rows)
+        .map(|row| row * len / rows..(row + 1) * len / rows)
+        .collect()
+}
+
+/// The lines where each run of hot rows starts, at its first line of the
+/// most severe level, for `]h` and `[h` to stop at.
+pub(crate) fn hot_starts(logs: &[LogEntry], rows: usize) -> Vec<bool> {
+    let mut starts = vec![false; logs.len()];
+    let mut was_hot = false;
+    for lines in minimap_rows(logs.len(), rows) {
+        let heat = Heat::of(&logs[lines.clone()]);
+        if heat.is_hot() && !was_hot {
+            starts[hottest_line(logs, lines)] = true;
+        }
+        was_hot = heat.is_hot();
+    }
+    starts
+}
+
+/// The first of the lines with the most severe level among them, the one
+/// clicking on their row of the minimap selects.
+pub(crate) fn hottest_line(logs: &[LogEntry], lines: Range<usize>) -> usize {
+    let level = logs[lines.clone()]
+        .iter()
+        .filter_map(|entry| entry.level)
+        .max();
+    logs[lines.clone()]
+        .iter()
+        .position(|entry| entry.level == level)
+        .map_or(lines.start, |idx| lines.start + idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A line for each letter: Info, Warning, Error or Critical, or no level
+    /// for anything else.
+    fn logs(levels: &str) -> Vec<LogEntry> {
+        levels
+            .chars()
+            .map(|level| LogEntry {
+                level: match level {
+                    'I' => Some(Level::Info),
+                    'W' => Some(Level::Warning),
+                    'E' => Some(Level::Error),
+                    'C' => Some(Level::Critical),
+                    _ => None,
+                },
+                ..LogEntry::new(level.to_string(), 0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn spreads_the_lines_evenly_over_the_rows() {
+        assert_eq!(minimap_rows(10, 4), [0..2, 2..5, 5..7, 7..10]);
+        assert_eq!(minimap_rows(3, 10), [0..1, 1..2, 2..3]);
+        assert!(minimap_rows(0, 10).is_empty());
+    }
+
+    #[test]
+    fn heats_each_row_by_its_most_severe_lines() {
+        let minimap = Minimap::new(&logs("IIEWIIIIECEI"), 3, 5..9);
+        let heat: Vec<(Option<Level>, f32)> = minimap
+            .rows
+            .iter()
+            .map(|heat| (heat.level, heat.share))
+            .collect();
+        assert_eq!(
+            heat,
+            [
+                (Some(Level::Error), 0.25),
+                (Some(Level::Info), 1.0),
+                (Some(Level::Critical), 0.25),
+            ]
+        );
+        assert_eq!(minimap.in_view, 1..3);
+        assert!(minimap.rows[0].is_hot() && !minimap.rows[1].is_hot());
+    }
+
+    #[test]
+    fn stops_at_the_start_of_each_run_of_hot_rows() {
+        let lines = logs("IIEWIIIIECEI");
+        let starts = |rows| -> Vec<usize> {
+            hot_starts(&lines, rows)
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, &start)| start.then_some(idx))
+                .collect()
+        };
+        assert_eq!(starts(3), [2, 9]);
+        // Both halves have errors, so they're one run.
+        assert_eq!(starts(2), [2]);
+        assert_eq!(hottest_line(&lines, 8..12), 9);
+        assert_eq!(hottest_line(&logs("--"), 0..2), 0);
+    }
+}
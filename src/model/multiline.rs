@@ -0,0 +1,80 @@
+use crate::LogEntry;
+
+/// Adds the entry to the logs, folding it into the entry above when it's a
+/// continuation of it, like the lines of a stack trace.
+pub(crate) fn push_grouped(logs: &mut Vec<LogEntry>, entry: LogEntry) {
+    match logs.last_mut() {
+        Some(head) if continues(head, &entry) => head.continuation.push(entry.line),
+        _ => logs.push(entry),
+    }
+}
+
+/// Returns true if the line belongs to the entry above it. That's the case for
+/// indented lines and the headers of java and python traces, and for any line
+/// without a timestamp when the entry above has one.
+pub(crate) fn continues(head: &LogEntry, entry: &LogEntry) -> bool {
+    if head.source != entry.source || entry.structured.is_some() || entry.line.trim().is_empty() {
+        return false;
+    }
+
+    let line = entry.line.as_str();
+    line.starts_with([' ', '\t'])
+        || line.starts_with("Caused by:")
+        || line.starts_with("Traceback (most recent call last)")
+        || (head.timestamp.is_some() && entry.timestamp.is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn group(lines: &[(&str, bool)]) -> Vec<LogEntry> {
+        let time = NaiveDate::from_ymd_opt(2024, 5, 3).and_then(|date| date.and_hms_opt(14, 2, 10));
+        let mut logs = vec![];
+        for (line, stamped) in lines {
+            let entry = LogEntry {
+                timestamp: time.filter(|_| *stamped),
+                ..LogEntry::new(line.to_string(), 0)
+            };
+            push_grouped(&mut logs, entry);
+        }
+        logs
+    }
+
+    #[test]
+    fn folds_a_trace_into_the_entry_above() {
+        let logs = group(&[
+            ("ERROR request failed", true),
+            ("java.lang.IllegalStateException: closed", false),
+            ("    at Pool.get(Pool.java:42)", false),
+            ("Caused by: java.io.IOException", false),
+            ("", false),
+            ("INFO retrying", true),
+        ]);
+        assert_eq!(logs.len(), 3);
+        assert_eq!(logs[0].continuation.len(), 3);
+        assert_eq!(logs[1].line, "");
+        assert_eq!(logs[2].line, "INFO retrying");
+    }
+
+    #[test]
+    fn needs_an_indent_without_timestamps() {
+        let logs = group(&[
+            ("failed to connect", false),
+            ("Traceback (most recent call last)", false),
+            ("  File \"app.py\", line 3", false),
+            ("retrying", false),
+        ]);
+        let lines: Vec<&str> = logs.iter().map(|entry| entry.line.as_str()).collect();
+        assert_eq!(lines, ["failed to connect", "retrying"]);
+        assert_eq!(logs[0].continuation.len(), 2);
+    }
+
+    #[test]
+    fn keeps_lines_of_other_files_apart() {
+        let head = LogEntry::new(String::from("ERROR failed"), 0);
+        let other = LogEntry::new(String::from("    at main"), 1);
+        assert!(!continues(&head, &other));
+    }
+}
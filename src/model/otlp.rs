@@ -0,0 +1,603 @@
+use chrono::{DateTime, SecondsFormat};
+use color_eyre::Result;
+use flate2::read::MultiGzDecoder;
+use serde_json::{Map, Number, Value};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{LineSink, LineStream, accept_loop};
+
+/// Largest request body we accept, to keep a bad client from eating memory.
+const MAX_BODY: usize = 16 * 1024 * 1024;
+
+/// Parses the address for `--listen-otlp`. A bare port listens on every
+/// interface, so collectors running in containers can reach it.
+pub(crate) fn parse_otlp_addr(addr: &str) -> Result<String, &'static str> {
+    let addr = match addr.split_once("://") {
+        Some(("http", addr)) => addr,
+        Some(_) => return Err("--listen-otlp only accepts http:// addresses."),
+        None => addr,
+    };
+
+    let addr = addr.trim_end_matches('/');
+    if addr.is_empty() {
+        return Err("--listen-otlp needs a port or an address.");
+    }
+    match addr.parse::<u16>() {
+        Ok(port) => Ok(format!("0.0.0.0:{port}")),
+        Err(_) => Ok(addr.to_string()),
+    }
+}
+
+/// Runs an OTLP/HTTP logs receiver and streams in each log record it's sent.
+///
+/// Records are handed over as JSON lines holding their time, level, body and
+/// attributes, with the attributes of the resource that sent them prefixed
+/// by `resource.`, so they're parsed like any other JSON log.
+pub(crate) fn otlp_stream(addr: &str) -> Result<LineStream> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    Ok(LineStream::spawn_thread(move |sink| {
+        accept_loop(listener, sink, receive_requests)
+    }))
+}
+
+/// A request read off a connection, with just the parts the receiver uses.
+struct Request {
+    method: String,
+    path: String,
+    content_type: String,
+    gzipped: bool,
+    body: Vec<u8>,
+}
+
+/// Answers the requests of one connection until the client closes it.
+fn receive_requests(stream: TcpStream, sink: LineSink) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+
+    while !sink.is_stopped() {
+        let request = match read_request(&mut reader) {
+            Ok(Some(request)) => request,
+            Ok(None) => return,
+            Err(error) => {
+                sink.set_status(Some(error.to_string()));
+                return;
+            }
+        };
+
+        let json = request.content_type.starts_with("application/json");
+        let (status, body) = if request.method != "POST" || !request.path.ends_with("/v1/logs") {
+            // Traces and metrics are refused so exporters don't retry them.
+            ("404 Not Found", vec![])
+        } else {
+            match read_logs(&request, json) {
+                Some(lines) => {
+                    lines.into_iter().for_each(|line| sink.push(line));
+                    // An empty response means every record was accepted.
+                    let body = if json { b"{}".to_vec() } else { vec![] };
+                    ("200 OK", body)
+                }
+                None => ("400 Bad Request", vec![]),
+            }
+        };
+
+        let content_type = if json {
+            "application/json"
+        } else {
+            "application/x-protobuf"
+        };
+        let head = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        );
+        if writer.write_all(head.as_bytes()).is_err() || writer.write_all(&body).is_err() {
+            return;
+        }
+    }
+}
+
+/// Reads the next request off the connection, or `None` once it's closed.
+fn read_request(reader: &mut impl BufRead) -> Result<Option<Request>, &'static str> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).unwrap_or(0) == 0 {
+        return Ok(None);
+    }
+    // gRPC clients open with the HTTP/2 preface.
+    if line.starts_with("PRI * HTTP/2") {
+        return Err("OTLP/gRPC isn't supported, send logs over OTLP/HTTP");
+    }
+
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        content_type: String::new(),
+        gzipped: false,
+        body: vec![],
+    };
+
+    let mut length = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return Ok(None);
+        }
+        let Some((name, value)) = line.trim_end().split_once(':') else {
+            break;
+        };
+        let value = value.trim();
+        match name.to_ascii_lowercase().as_str() {
+            "content-length" => length = value.parse().unwrap_or(0),
+            "content-type" => request.content_type = value.to_ascii_lowercase(),
+            "content-encoding" => request.gzipped = value.eq_ignore_ascii_case("gzip"),
+            _ => {}
+        }
+    }
+
+    if length > MAX_BODY {
+        return Err("OTLP request too large");
+    }
+    request.body = vec![0; length];
+    if reader.read_exact(&mut request.body).is_err() {
+        return Ok(None);
+    }
+    Ok(Some(request))
+}
+
+/// Decodes the export request in the body into one line per log record.
+fn read_logs(request: &Request, json: bool) -> Option<Vec<String>> {
+    let mut body = request.body.clone();
+    if request.gzipped {
+        body.clear();
+        MultiGzDecoder::new(request.body.as_slice())
+            .take(MAX_BODY as u64)
+            .read_to_end(&mut body)
+            .ok()?;
+    }
+
+    let records = match json {
+        true => json_records(&serde_json::from_slice(&body).ok()?),
+        false => proto_records(&body)?,
+    };
+    Some(records.into_iter().map(format_record).collect())
+}
+
+/// A log record, with the attributes of the resource that sent it.
+struct Record {
+    time_nanos: u64,
+    severity_number: u64,
+    severity_text: String,
+    body: Value,
+    attributes: Vec<(String, Value)>,
+    trace_id: String,
+    span_id: String,
+    scope: String,
+    resource: Vec<(String, Value)>,
+}
+
+/// Writes the record as a JSON line the JSON parser picks apart.
+fn format_record(record: Record) -> String {
+    let mut object = Map::new();
+    if let Some(time) = i64::try_from(record.time_nanos)
+        .ok()
+        .filter(|nanos| *nanos > 0)
+        .map(DateTime::from_timestamp_nanos)
+    {
+        let time = time.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+        object.insert(String::from("time"), Value::String(time));
+    }
+
+    let level = match severity_number_level(record.severity_number) {
+        Some(level) => level.to_string(),
+        None => record.severity_text,
+    };
+    if !level.is_empty() {
+        object.insert(String::from("level"), Value::String(level));
+    }
+    object.insert(String::from("msg"), record.body);
+
+    object.extend(record.attributes);
+    for (key, value) in [
+        ("trace_id", record.trace_id),
+        ("span_id", record.span_id),
+        ("scope", record.scope),
+    ] {
+        if !value.is_empty() {
+            object.insert(key.to_string(), Value::String(value));
+        }
+    }
+    object.extend(
+        record
+            .resource
+            .into_iter()
+            .map(|(key, value)| (format!("resource.{key}"), value)),
+    );
+    Value::Object(object).to_string()
+}
+
+/// Maps an OTLP severity number onto the level names used by the filters.
+/// Each level spans four numbers, like 9 to 12 for the INFO variants.
+fn severity_number_level(number: u64) -> Option<&'static str> {
+    match number {
+        1..=8 => Some("DEBUG"),
+        9..=12 => Some("INFO"),
+        13..=16 => Some("WARNING"),
+        17..=20 => Some("ERROR"),
+        21..=24 => Some("CRITICAL"),
+        _ => None,
+    }
+}
+
+/********************************* JSON ************************************/
+
+/// Reads the records of an export request in the OTLP JSON encoding.
+fn json_records(request: &Value) -> Vec<Record> {
+    let mut records = vec![];
+    for resource_logs in json_array(request, "resourceLogs") {
+        let resource = json_attributes(&resource_logs["resource"]);
+        for scope_logs in json_array(resource_logs, "scopeLogs") {
+            let scope = json_string(&scope_logs["scope"]["name"]);
+            for log in json_array(scope_logs, "logRecords") {
+                let time = match json_number(&log["timeUnixNano"]) {
+                    0 => json_number(&log["observedTimeUnixNano"]),
+                    time => time,
+                };
+                records.push(Record {
+                    time_nanos: time,
+                    severity_number: json_number(&log["severityNumber"]),
+                    severity_text: json_string(&log["severityText"]),
+                    body: json_any_value(&log["body"]),
+                    attributes: json_attributes(log),
+                    trace_id: json_string(&log["traceId"]),
+                    span_id: json_string(&log["spanId"]),
+                    scope: scope.clone(),
+                    resource: resource.clone(),
+                });
+            }
+        }
+    }
+    records
+}
+
+fn json_array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value[key].as_array().map_or(&[], Vec::as_slice)
+}
+
+fn json_string(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+/// 64 bit numbers are written as strings, since JSON numbers can't hold them.
+fn json_number(value: &Value) -> u64 {
+    match value {
+        Value::String(text) => text.parse().unwrap_or(0),
+        value => value.as_u64().unwrap_or(0),
+    }
+}
+
+fn json_attributes(value: &Value) -> Vec<(String, Value)> {
+    json_array(value, "attributes")
+        .iter()
+        .map(|pair| (json_string(&pair["key"]), json_any_value(&pair["value"])))
+        .collect()
+}
+
+/// Unwraps an `AnyValue`, like `{"stringValue": "..."}`, into the value it
+/// holds.
+fn json_any_value(value: &Value) -> Value {
+    let Some((kind, inner)) = value.as_object().and_then(|object| object.iter().next()) else {
+        return Value::Null;
+    };
+    match kind.as_str() {
+        "intValue" => match inner {
+            Value::String(text) => text
+                .parse::<i64>()
+                .map_or(inner.clone(), |int| Value::Number(int.into())),
+            _ => inner.clone(),
+        },
+        "arrayValue" => Value::Array(
+            json_array(inner, "values")
+                .iter()
+                .map(json_any_value)
+                .collect(),
+        ),
+        "kvlistValue" => Value::Object(
+            json_array(inner, "values")
+                .iter()
+                .map(|pair| (json_string(&pair["key"]), json_any_value(&pair["value"])))
+                .collect(),
+        ),
+        _ => inner.clone(),
+    }
+}
+
+/******************************* Protobuf **********************************/
+
+/// A protobuf field's value, by wire type.
+enum Wire<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    /// Nothing in the logs protocol uses 32 bit fields, so they're skipped.
+    Fixed32,
+}
+
+/// Splits a protobuf message into its fields. Returns `None` if the message
+/// is cut short or uses a wire type that no longer exists.
+fn proto_fields(mut buf: &[u8]) -> Option<Vec<(u64, Wire<'_>)>> {
+    let mut fields = vec![];
+    while !buf.is_empty() {
+        let key = read_varint(&mut buf)?;
+        let value = match key & 7 {
+            0 => Wire::Varint(read_varint(&mut buf)?),
+            1 => Wire::Fixed64(u64::from_le_bytes(take(&mut buf, 8)?.try_into().ok()?)),
+            2 => {
+                let len = usize::try_from(read_varint(&mut buf)?).ok()?;
+                Wire::Bytes(take(&mut buf, len)?)
+            }
+            5 => {
+                take(&mut buf, 4)?;
+                Wire::Fixed32
+            }
+            _ => return None,
+        };
+        fields.push((key >> 3, value));
+    }
+    Some(fields)
+}
+
+fn read_varint(buf: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = buf.split_first()?;
+        *buf = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if buf.len() < len {
+        return None;
+    }
+    let (head, rest) = buf.split_at(len);
+    *buf = rest;
+    Some(head)
+}
+
+/// Returns the embedded messages, strings or bytes held by the field.
+fn proto_bytes<'a>(fields: &[(u64, Wire<'a>)], number: u64) -> impl Iterator<Item = &'a [u8]> {
+    fields.iter().filter_map(move |(field, value)| match value {
+        Wire::Bytes(bytes) if *field == number => Some(*bytes),
+        _ => None,
+    })
+}
+
+fn proto_string(fields: &[(u64, Wire)], number: u64) -> String {
+    proto_bytes(fields, number)
+        .last()
+        .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+        .unwrap_or_default()
+}
+
+fn proto_number(fields: &[(u64, Wire)], number: u64) -> u64 {
+    fields
+        .iter()
+        .rev()
+        .find_map(|(field, value)| match value {
+            Wire::Varint(value) | Wire::Fixed64(value) if *field == number => Some(*value),
+            _ => None,
+        })
+        .unwrap_or(0)
+}
+
+/// Reads the records of an `ExportLogsServiceRequest`.
+fn proto_records(body: &[u8]) -> Option<Vec<Record>> {
+    let mut records = vec![];
+    for resource_logs in proto_bytes(&proto_fields(body)?, 1) {
+        let resource_logs = proto_fields(resource_logs)?;
+        let resource = match proto_bytes(&resource_logs, 1).last() {
+            Some(resource) => proto_attributes(&proto_fields(resource)?, 1)?,
+            None => vec![],
+        };
+
+        for scope_logs in proto_bytes(&resource_logs, 2) {
+            let scope_logs = proto_fields(scope_logs)?;
+            let scope = match proto_bytes(&scope_logs, 1).last() {
+                Some(scope) => proto_string(&proto_fields(scope)?, 1),
+                None => String::new(),
+            };
+
+            for log in proto_bytes(&scope_logs, 2) {
+                let log = proto_fields(log)?;
+                let time = match proto_number(&log, 1) {
+                    0 => proto_number(&log, 11),
+                    time => time,
+                };
+                records.push(Record {
+                    time_nanos: time,
+                    severity_number: proto_number(&log, 2),
+                    severity_text: proto_string(&log, 3),
+                    body: match proto_bytes(&log, 5).last() {
+                        Some(body) => proto_any_value(body)?,
+                        None => Value::Null,
+                    },
+                    attributes: proto_attributes(&log, 6)?,
+                    trace_id: hex(proto_bytes(&log, 9).last().unwrap_or_default()),
+                    span_id: hex(proto_bytes(&log, 10).last().unwrap_or_default()),
+                    scope: scope.clone(),
+                    resource: resource.clone(),
+                });
+            }
+        }
+    }
+    Some(records)
+}
+
+/// Reads the `KeyValue` messages held by the field.
+fn proto_attributes(fields: &[(u64, Wire)], number: u64) -> Option<Vec<(String, Value)>> {
+    proto_bytes(fields, number)
+        .map(|pair| {
+            let pair = proto_fields(pair)?;
+            let value = match proto_bytes(&pair, 2).last() {
+                Some(value) => proto_any_value(value)?,
+                None => Value::Null,
+            };
+            Some((proto_string(&pair, 1), value))
+        })
+        .collect()
+}
+
+/// Unwraps an `AnyValue` message into the value it holds.
+fn proto_any_value(buf: &[u8]) -> Option<Value> {
+    let fields = proto_fields(buf)?;
+    let Some((kind, value)) = fields.last() else {
+        return Some(Value::Null);
+    };
+    Some(match (kind, value) {
+        (1, Wire::Bytes(text)) => Value::String(String::from_utf8_lossy(text).to_string()),
+        (2, Wire::Varint(flag)) => Value::Bool(*flag != 0),
+        // Negative ints are written as their two's complement.
+        (3, Wire::Varint(int)) => Value::Number((*int as i64).into()),
+        (4, Wire::Fixed64(bits)) => {
+            Number::from_f64(f64::from_bits(*bits)).map_or(Value::Null, Value::Number)
+        }
+        (5, Wire::Bytes(array)) => Value::Array(
+            proto_bytes(&proto_fields(array)?, 1)
+                .map(proto_any_value)
+                .collect::<Option<_>>()?,
+        ),
+        (6, Wire::Bytes(list)) => Value::Object(
+            proto_attributes(&proto_fields(list)?, 1)?
+                .into_iter()
+                .collect(),
+        ),
+        (7, Wire::Bytes(bytes)) => Value::String(hex(bytes)),
+        _ => Value::Null,
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(body: &str) -> Vec<Value> {
+        let request = Request {
+            method: String::from("POST"),
+            path: String::from("/v1/logs"),
+            content_type: String::from("application/json"),
+            gzipped: false,
+            body: body.as_bytes().to_vec(),
+        };
+        read_logs(&request, true)
+            .unwrap()
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn parses_the_address() {
+        assert_eq!(parse_otlp_addr("4318").unwrap(), "0.0.0.0:4318");
+        assert_eq!(
+            parse_otlp_addr("http://127.0.0.1:4318/").unwrap(),
+            "127.0.0.1:4318"
+        );
+        assert!(parse_otlp_addr("https://collector:4318").is_err());
+        assert!(parse_otlp_addr("").is_err());
+    }
+
+    #[test]
+    fn reads_the_records_of_a_json_export() {
+        let records = records(
+            r#"{"resourceLogs": [{
+                "resource": {"attributes": [
+                    {"key": "service.name", "value": {"stringValue": "checkout"}}
+                ]},
+                "scopeLogs": [{
+                    "scope": {"name": "cart"},
+                    "logRecords": [{
+                        "timeUnixNano": "1714744930500000000",
+                        "severityNumber": 17,
+                        "severityText": "Error",
+                        "body": {"stringValue": "payment declined"},
+                        "attributes": [
+                            {"key": "attempt", "value": {"intValue": "3"}}
+                        ],
+                        "traceId": "5b8efff798038103d269b633813fc60c",
+                        "spanId": "eee19b7ec3c1b174"
+                    }]
+                }]
+            }]}"#,
+        );
+        assert_eq!(
+            records,
+            [serde_json::json!({
+                "time": "2024-05-03T14:02:10.500Z",
+                "level": "ERROR",
+                "msg": "payment declined",
+                "attempt": 3,
+                "trace_id": "5b8efff798038103d269b633813fc60c",
+                "span_id": "eee19b7ec3c1b174",
+                "scope": "cart",
+                "resource.service.name": "checkout",
+            })]
+        );
+    }
+
+    #[test]
+    fn unwraps_a_nested_body() {
+        let records = records(
+            r#"{"resourceLogs": [{"scopeLogs": [{"logRecords": [{
+                "severityNumber": 10,
+                "body": {"kvlistValue": {"values": [
+                    {"key": "event", "value": {"stringValue": "login"}},
+                    {"key": "roles", "value": {"arrayValue": {"values": [
+                        {"stringValue": "admin"}, {"boolValue": true}
+                    ]}}}
+                ]}}
+            }]}]}]}"#,
+        );
+        assert_eq!(records[0]["level"], "INFO");
+        assert_eq!(
+            records[0]["msg"],
+            serde_json::json!({"event": "login", "roles": ["admin", true]})
+        );
+    }
+
+    #[test]
+    fn leaves_out_the_fields_a_record_is_missing() {
+        let records = records(
+            r#"{"resourceLogs": [{"scopeLogs": [{"logRecords": [
+                {"observedTimeUnixNano": "1714744930000000000", "severityText": "notice"},
+                {}
+            ]}]}]}"#,
+        );
+        assert_eq!(
+            records,
+            [
+                serde_json::json!({
+                    "time": "2024-05-03T14:02:10Z",
+                    "level": "notice",
+                    "msg": null,
+                }),
+                serde_json::json!({"msg": null}),
+            ]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use color_eyre::Result;
+use std::{cmp::Reverse, fs, time::SystemTime};
+
+/// A file shown in the picker.
+#[derive(Debug)]
+pub(crate) struct PickerEntry {
+    pub(crate) path: String,
+    pub(crate) size: u64,
+    pub(crate) modified: SystemTime,
+}
+
+/// Lists the files of a directory given on the command line so one can be
+/// opened from inside the viewer.
+#[derive(Debug, Default)]
+pub(crate) struct FilePicker {
+    pub(crate) dir: String,
+    /// Newest first, since that's usually the log being looked for.
+    pub(crate) entries: Vec<PickerEntry>,
+    pub(crate) selected: usize,
+    /// Why the last file picked couldn't be opened.
+    pub(crate) error: Option<String>,
+}
+
+impl FilePicker {
+    pub(crate) fn new(dir: &str) -> Result<FilePicker> {
+        let mut picker = FilePicker {
+            dir: dir.to_string(),
+            ..FilePicker::default()
+        };
+        picker.refresh()?;
+        Ok(picker)
+    }
+
+    /// Lists the directory again, picking up files written since it was
+    /// last opened.
+    pub(crate) fn refresh(&mut self) -> Result<()> {
+        let mut entries = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if hidden || !meta.is_file() {
+                continue;
+            }
+
+            entries.push(PickerEntry {
+                path: entry.path().to_string_lossy().to_string(),
+                size: meta.len(),
+                modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+
+        entries.sort_by_key(|entry| Reverse(entry.modified));
+        self.entries = entries;
+        self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+        self.error = None;
+        Ok(())
+    }
+
+    pub(crate) fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub(crate) fn move_down(&mut self) {
+        if self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub(crate) fn move_top(&mut self) {
+        self.selected = 0;
+    }
+
+    pub(crate) fn move_bottom(&mut self) {
+        self.selected = self.entries.len().saturating_sub(1);
+    }
+
+    pub(crate) fn selected_path(&self) -> Option<&str> {
+        self.entries
+            .get(self.selected)
+            .map(|entry| entry.path.as_str())
+    }
+}
@@ -0,0 +1,74 @@
+use std::{fs, path::PathBuf};
+
+use toml::{Table, Value};
+
+use crate::{Preset, data_dir};
+
+/// Where a file was left when the viewer was closed: the line selected, the
+/// row of the view it was on, and the filters and search that were set, as
+/// a preset would keep them.
+///
+/// Each file's is saved to `positions/<hash>.toml` in the data directory,
+/// the hash being of the file's full path, and brought back the next time
+/// it's opened unless `--fresh` is passed.
+#[derive(Debug, Clone)]
+pub(crate) struct SavedPosition {
+    /// The number of the selected line in the file.
+    pub(crate) line: usize,
+    /// How far down the view the selected line was.
+    pub(crate) row: usize,
+    pub(crate) view: Preset,
+}
+
+impl SavedPosition {
+    /// Reads the position the file was left at. A missing or unreadable one
+    /// just means opening the file as if for the first time.
+    pub(crate) fn load(log_path: &str) -> Option<SavedPosition> {
+        let (full_path, path) = position_path(log_path)?;
+        let saved: Table = fs::read_to_string(path).ok()?.parse().ok()?;
+        // Another file whose path hashes the same isn't mistaken for this one.
+        if saved.get("path")?.as_str()? != full_path {
+            return None;
+        }
+        let number = |key: &str| saved.get(key)?.as_integer()?.try_into().ok();
+        Some(SavedPosition {
+            line: number("line")?,
+            row: number("row")?,
+            view: Preset::from_toml("", saved.get("view")?).ok()?,
+        })
+    }
+
+    /// Saves the position the file was left at. Failing to isn't worth
+    /// interrupting anyone over, so it's ignored.
+    pub(crate) fn save(&self, log_path: &str) {
+        let Some((full_path, path)) = position_path(log_path) else {
+            return;
+        };
+        let mut saved = Table::new();
+        saved.insert(String::from("path"), Value::from(full_path));
+        saved.insert(String::from("line"), Value::from(self.line as i64));
+        saved.insert(String::from("row"), Value::from(self.row as i64));
+        saved.insert(String::from("view"), Value::Table(self.view.to_toml()));
+        let _ = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(path, saved.to_string()));
+    }
+}
+
+/// The full path of the log and where its position is saved. The hash is
+/// FNV-1a, which unlike the standard library's hasher stays the same from
+/// one build to the next.
+fn position_path(log_path: &str) -> Option<(String, PathBuf)> {
+    let full_path = fs::canonicalize(log_path).ok()?;
+    let full_path = full_path.to_str()?.to_string();
+    let hash = full_path
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+    let path = data_dir()?
+        .join("positions")
+        .join(format!("{hash:016x}.toml"));
+    Some((full_path, path))
+}
@@ -0,0 +1,215 @@
+use std::{
+    collections::BTreeSet,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use regex::Regex;
+use toml::{Table, Value};
+
+use crate::{FilterExpr, Filters, Level, Model, record_stage};
+
+/// A named set of filters and a search, from a `[presets.NAME]` section of the
+/// config file.
+///
+/// ```toml
+/// [presets.payments-errors]
+/// levels = ["error", "critical"]
+/// regex = "service=payments"
+/// exclude = ["healthz"]
+/// fields = ["status>=500"]
+/// muted_loggers = ["healthcheck"]
+/// search = "timeout"
+/// ```
+#[derive(Debug, Clone)]
+pub(crate) struct Preset {
+    pub(crate) name: String,
+    filters: Filters,
+    search: String,
+}
+
+impl Preset {
+    pub(crate) fn from_toml(name: &str, preset: &Value) -> Result<Preset, &'static str> {
+        let preset = preset
+            .as_table()
+            .ok_or("Each preset in the config file must be a table.")?;
+        let text = |key: &str| -> Result<Option<&str>, &'static str> {
+            match preset.get(key) {
+                Some(value) => value
+                    .as_str()
+                    .map(Some)
+                    .ok_or("A preset setting in the config file must be a string."),
+                None => Ok(None),
+            }
+        };
+        let list = |key: &str| -> Result<Vec<&str>, &'static str> {
+            let Some(values) = preset.get(key) else {
+                return Ok(vec![]);
+            };
+            values
+                .as_array()
+                .ok_or("levels, exclude, fields and loggers in a preset take lists of strings.")?
+                .iter()
+                .map(|value| {
+                    value.as_str().ok_or(
+                        "levels, exclude, fields and loggers in a preset take lists of strings.",
+                    )
+                })
+                .collect()
+        };
+        let level = |name: &str| {
+            Level::parse(name).ok_or("A preset in the config file names an unknown level.")
+        };
+        let regex = |pattern: &str| {
+            Regex::new(pattern).map_err(|_| "A preset in the config file has an invalid regex.")
+        };
+
+        let filters = Filters {
+            levels: list("levels")?
+                .into_iter()
+                .map(level)
+                .collect::<Result<_, _>>()?,
+            min_level: text("min_level")?.map(level).transpose()?,
+            regex: text("regex")?.map(regex).transpose()?,
+            exclude: list("exclude")?
+                .into_iter()
+                .map(regex)
+                .collect::<Result<_, _>>()?,
+            expression: text("expression")?
+                .map(FilterExpr::parse)
+                .transpose()
+                .map_err(|_| "A preset in the config file has an invalid expression.")?,
+            fields: list("fields")?
+                .into_iter()
+                .map(FilterExpr::parse_field)
+                .collect::<Result<_, _>>()
+                .map_err(|_| "A preset in the config file has an invalid field filter.")?,
+            logger_include: list("loggers")?.into_iter().map(String::from).collect(),
+            logger_exclude: list("muted_loggers")?
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            ..Filters::default()
+        };
+        Ok(Preset {
+            name: name.to_string(),
+            filters,
+            search: text("search")?.unwrap_or_default().to_string(),
+        })
+    }
+
+    /// Takes the filters and search currently set on the model, apart from
+    /// the time range, quick filter and hidden lines, which are only good for
+    /// the log at hand.
+    pub(crate) fn from_model(name: &str, model: &Model) -> Preset {
+        Preset {
+            name: name.to_string(),
+            filters: Filters {
+                time_range: None,
+                quick: None,
+                hidden: BTreeSet::new(),
+                hidden_templates: BTreeSet::new(),
+                ..model.filters.clone()
+            },
+            search: model.search_input.clone(),
+        }
+    }
+
+    /// Replaces the model's filters and search with the preset's, as one
+    /// stage that can be undone.
+    pub(crate) fn apply(&self, model: &mut Model) {
+        let before = std::mem::replace(&mut model.filters, self.filters.clone());
+        record_stage(&mut model.filter_stages, before, &model.filters);
+        model.search_input = self.search.clone();
+        model.reset_view();
+    }
+
+    pub(crate) fn to_toml(&self) -> Table {
+        let mut preset = Table::new();
+        if !self.filters.levels.is_empty() {
+            let levels = self
+                .filters
+                .levels
+                .iter()
+                .map(|level| Value::from(level.key()));
+            preset.insert(String::from("levels"), Value::Array(levels.collect()));
+        }
+        if let Some(level) = self.filters.min_level {
+            preset.insert(String::from("min_level"), Value::from(level.key()));
+        }
+        if let Some(regex) = &self.filters.regex {
+            preset.insert(String::from("regex"), Value::from(regex.as_str()));
+        }
+        if !self.filters.exclude.is_empty() {
+            let exclude = self
+                .filters
+                .exclude
+                .iter()
+                .map(|regex| Value::from(regex.as_str()));
+            preset.insert(String::from("exclude"), Value::Array(exclude.collect()));
+        }
+        if let Some(expression) = &self.filters.expression {
+            preset.insert(String::from("expression"), Value::from(expression.as_str()));
+        }
+        if !self.filters.fields.is_empty() {
+            let fields = self
+                .filters
+                .fields
+                .iter()
+                .map(|filter| Value::from(filter.as_str()));
+            preset.insert(String::from("fields"), Value::Array(fields.collect()));
+        }
+        if !self.filters.logger_include.is_empty() {
+            let loggers = self
+                .filters
+                .logger_include
+                .iter()
+                .map(|logger| Value::from(logger.as_str()));
+            preset.insert(String::from("loggers"), Value::Array(loggers.collect()));
+        }
+        if !self.filters.logger_exclude.is_empty() {
+            let loggers = self
+                .filters
+                .logger_exclude
+                .iter()
+                .map(|logger| Value::from(logger.as_str()));
+            preset.insert(
+                String::from("muted_loggers"),
+                Value::Array(loggers.collect()),
+            );
+        }
+        if !self.search.is_empty() {
+            preset.insert(String::from("search"), Value::from(self.search.as_str()));
+        }
+        preset
+    }
+
+    /// Adds the preset to the end of the config file, leaving what's already
+    /// written there as it is.
+    pub(crate) fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut presets = Table::new();
+        presets.insert(self.name.clone(), Value::Table(self.to_toml()));
+        let mut section = Table::new();
+        section.insert(String::from("presets"), Value::Table(presets));
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if file.metadata()?.len() > 0 {
+            writeln!(file)?;
+        }
+        write!(file, "{section}")
+    }
+}
+
+/// Reads the `[presets]` section, a table of presets by name.
+pub(crate) fn parse_presets(presets: &Value) -> Result<Vec<Preset>, &'static str> {
+    presets
+        .as_table()
+        .ok_or("[presets] in the config file must be a table.")?
+        .iter()
+        .map(|(name, preset)| Preset::from_toml(name, preset))
+        .collect()
+}
@@ -0,0 +1,43 @@
+use regex::Regex;
+use std::sync::LazyLock;
+use strip_ansi_escapes::strip;
+
+use crate::{Level, LogEntry};
+
+/// Dates and times, which are in every line and so not worth filtering on.
+static TIME_TOKEN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\d{4}[-/]\d\d[-/]\d\d|^\d\d:\d\d(:\d\d)?").unwrap());
+
+/// Lists the words of the entry a quick filter can be made from, the ones
+/// most likely to be an identifier first: words mixing digits with letters
+/// or separators, like request IDs and addresses, then other words, then
+/// plain numbers. Timestamps, levels and short words are left out.
+pub(crate) fn quick_filter_tokens(entry: &LogEntry) -> Vec<String> {
+    let line = String::from_utf8(strip(entry.line.as_bytes())).unwrap();
+    let mut tokens: Vec<(u8, String)> = vec![];
+
+    for word in line.split_whitespace() {
+        // The value is what identifies a line in `key=value`.
+        let word = word.rsplit_once('=').map_or(word, |(_, value)| value);
+        let token = word.trim_matches(|c: char| "[](){}<>,;:\"'".contains(c));
+        if token.chars().count() < 3
+            || TIME_TOKEN.is_match(token)
+            || Level::parse(token).is_some()
+            || tokens.iter().any(|(_, seen)| seen == token)
+        {
+            continue;
+        }
+
+        let has_digit = token.chars().any(|c| c.is_ascii_digit());
+        let rank = match token.chars().all(|c| c.is_ascii_digit()) {
+            true => 2,
+            false if has_digit => 0,
+            false => 1,
+        };
+        tokens.push((rank, token.to_string()));
+    }
+
+    // The sort is stable, so words of the same rank stay in line order.
+    tokens.sort_by_key(|(rank, _)| *rank);
+    tokens.into_iter().map(|(_, token)| token).collect()
+}
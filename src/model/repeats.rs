@@ -0,0 +1,151 @@
+use std::collections::BTreeSet;
+
+use crate::{LogEntry, line_template};
+
+/// Which lines in a row are collapsed into one, to see past retry storms.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Dedup {
+    #[default]
+    Off,
+    /// Lines that are exactly the same.
+    Exact,
+    /// Lines that only differ in their numbers, times and IDs.
+    Like,
+}
+
+impl Dedup {
+    /// The mode after this one, to toggle through them.
+    pub(crate) fn next(self) -> Dedup {
+        match self {
+            Dedup::Off => Dedup::Exact,
+            Dedup::Exact => Dedup::Like,
+            Dedup::Like => Dedup::Off,
+        }
+    }
+
+    /// Describes the mode for the title of the logs.
+    pub(crate) fn describe(self) -> Option<&'static str> {
+        match self {
+            Dedup::Off => None,
+            Dedup::Exact => Some("repeats collapsed"),
+            Dedup::Like => Some("similar lines collapsed"),
+        }
+    }
+
+    fn key(self, entry: &LogEntry) -> String {
+        match self {
+            Dedup::Like => line_template(&entry.line),
+            _ => entry.line.clone(),
+        }
+    }
+}
+
+/// Collapses each run of repeated lines into its first line, which counts
+/// them in `repeats`. Runs whose first line is in `expanded` keep their
+/// lines, with the count still on the first.
+pub(crate) fn collapse_repeats(
+    logs: Vec<LogEntry>,
+    dedup: Dedup,
+    expanded: &BTreeSet<String>,
+) -> Vec<LogEntry> {
+    let mut collapsed: Vec<LogEntry> = vec![];
+    let mut run_start = 0;
+    let mut run_key = None;
+
+    for entry in logs {
+        let key = dedup.key(&entry);
+        if run_key.as_ref() == Some(&key) {
+            let head = &mut collapsed[run_start];
+            head.repeats = head.repeats.max(1) + 1;
+            if expanded.contains(&head.line) {
+                collapsed.push(entry);
+            }
+            continue;
+        }
+        run_start = collapsed.len();
+        run_key = Some(key);
+        collapsed.push(entry);
+    }
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logs(lines: &[&str]) -> Vec<LogEntry> {
+        lines
+            .iter()
+            .enumerate()
+            .map(|(number, line)| LogEntry {
+                number,
+                ..LogEntry::new(line.to_string(), 0)
+            })
+            .collect()
+    }
+
+    fn counted(logs: &[LogEntry]) -> Vec<(&str, usize)> {
+        logs.iter()
+            .map(|entry| (entry.line.as_str(), entry.repeats))
+            .collect()
+    }
+
+    const RETRIES: [&str; 5] = [
+        "retrying in 5s",
+        "retrying in 5s",
+        "retrying in 10s",
+        "connected",
+        "retrying in 5s",
+    ];
+
+    #[test]
+    fn collapses_each_run_of_the_same_line() {
+        let collapsed = collapse_repeats(logs(&RETRIES), Dedup::Exact, &BTreeSet::new());
+        assert_eq!(
+            counted(&collapsed),
+            [
+                ("retrying in 5s", 2),
+                ("retrying in 10s", 0),
+                ("connected", 0),
+                ("retrying in 5s", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn collapses_lines_that_differ_in_their_numbers() {
+        let collapsed = collapse_repeats(logs(&RETRIES), Dedup::Like, &BTreeSet::new());
+        assert_eq!(
+            counted(&collapsed),
+            [
+                ("retrying in 5s", 3),
+                ("connected", 0),
+                ("retrying in 5s", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_the_lines_of_an_expanded_run() {
+        let expanded = BTreeSet::from([String::from("retrying in 5s")]);
+        let collapsed = collapse_repeats(logs(&RETRIES), Dedup::Like, &expanded);
+        assert_eq!(
+            counted(&collapsed),
+            [
+                ("retrying in 5s", 3),
+                ("retrying in 5s", 0),
+                ("retrying in 10s", 0),
+                ("connected", 0),
+                ("retrying in 5s", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn toggles_through_the_modes() {
+        assert_eq!(Dedup::Off.next(), Dedup::Exact);
+        assert_eq!(Dedup::Exact.next(), Dedup::Like);
+        assert_eq!(Dedup::Like.next(), Dedup::Off);
+        assert_eq!(Dedup::Off.describe(), None);
+    }
+}
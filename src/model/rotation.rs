@@ -0,0 +1,50 @@
+use std::{cmp::Reverse, fs, time::SystemTime};
+
+/// Extensions added by logrotate when it compresses old files.
+const COMPRESSED_EXTENSIONS: [&str; 3] = [".gz", ".zst", ".bz2"];
+
+/// Returns true if the argument should be expanded as a glob rather than
+/// opened as a path.
+pub(crate) fn is_glob(arg: &str) -> bool {
+    arg.contains(['*', '?', '['])
+}
+
+/// Expands a glob like `app.log*` into the members of a rotated log set,
+/// ordered oldest first so they read as a single stream.
+///
+/// Files are ordered by their rotation index (`app.log.2.gz` before
+/// `app.log.1` before `app.log`), and files without one by modification time.
+pub(crate) fn expand_rotated(pattern: &str) -> Result<Vec<String>, &'static str> {
+    let mut paths: Vec<String> = glob::glob(pattern)
+        .map_err(|_| "Invalid glob pattern.")?
+        .filter_map(|path| path.ok())
+        .filter(|path| path.is_file())
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+
+    if paths.is_empty() {
+        return Err("No files match the glob pattern.");
+    }
+
+    paths.sort_by_cached_key(|path| (Reverse(rotation_index(path)), modified(path)));
+    Ok(paths)
+}
+
+/// Parses the number logrotate appends to old files, `app.log.3.gz` -> 3.
+/// The live file has no number and counts as 0.
+fn rotation_index(path: &str) -> usize {
+    let path = COMPRESSED_EXTENSIONS
+        .iter()
+        .find_map(|ext| path.strip_suffix(ext))
+        .unwrap_or(path);
+
+    path.rsplit_once('.')
+        .and_then(|(_, suffix)| suffix.parse().ok())
+        .unwrap_or(0)
+}
+
+fn modified(path: &str) -> SystemTime {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
@@ -0,0 +1,231 @@
+use chrono::Utc;
+use color_eyre::Result;
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::{Digest, Sha256};
+use std::{env, io::Read};
+
+use crate::{LineSink, LineStream, decode_log};
+
+/// Bytes read from the response between progress updates.
+const DOWNLOAD_CHUNK: usize = 256 * 1024;
+
+/// Region used when none is set in the environment.
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// An object in S3 or an S3 compatible store, written as `s3://bucket/key`.
+#[derive(Debug, Clone)]
+pub(crate) struct S3Object {
+    bucket: String,
+    key: String,
+}
+
+impl S3Object {
+    /// Returns true if the argument should be fetched from object storage.
+    pub(crate) fn is_s3(arg: &str) -> bool {
+        arg.starts_with("s3://")
+    }
+
+    pub(crate) fn parse(uri: &str) -> Result<Self, &'static str> {
+        let usage = "s3 sources look like s3://bucket/path/to/log";
+        let (bucket, key) = uri
+            .strip_prefix("s3://")
+            .and_then(|rest| rest.split_once('/'))
+            .ok_or(usage)?;
+
+        if bucket.is_empty() || key.is_empty() {
+            return Err(usage);
+        }
+
+        Ok(S3Object {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+        })
+    }
+
+    pub(crate) fn uri(&self) -> String {
+        format!("s3://{}/{}", self.bucket, self.key)
+    }
+}
+
+/// Credentials and endpoint read from the usual AWS environment variables.
+struct S3Env {
+    region: String,
+    /// Set for S3 compatible stores like MinIO, which use path style URLs.
+    endpoint: Option<String>,
+    access_key: Option<String>,
+    secret_key: Option<String>,
+    session_token: Option<String>,
+}
+
+impl S3Env {
+    fn from_env() -> Self {
+        S3Env {
+            region: env::var("AWS_REGION")
+                .or(env::var("AWS_DEFAULT_REGION"))
+                .unwrap_or(DEFAULT_REGION.to_string()),
+            endpoint: env::var("AWS_ENDPOINT_URL_S3")
+                .or(env::var("AWS_ENDPOINT_URL"))
+                .ok(),
+            access_key: env::var("AWS_ACCESS_KEY_ID").ok(),
+            secret_key: env::var("AWS_SECRET_ACCESS_KEY").ok(),
+            session_token: env::var("AWS_SESSION_TOKEN").ok(),
+        }
+    }
+}
+
+/// Downloads the object on a background thread, showing the progress in the
+/// stream's status, then decompresses it like a local file would be.
+pub(crate) fn s3_stream(object: S3Object) -> LineStream {
+    LineStream::spawn_thread(move |sink| {
+        if let Err(error) = download(&object, &S3Env::from_env(), &sink) {
+            sink.push(format!(
+                "[ERROR] couldn't download {}: {error}",
+                object.uri()
+            ));
+        }
+        sink.set_status(None);
+    })
+}
+
+fn download(object: &S3Object, s3_env: &S3Env, sink: &LineSink) -> Result<()> {
+    let (url, host, path) = match &s3_env.endpoint {
+        Some(endpoint) => {
+            let endpoint = endpoint.trim_end_matches('/');
+            let host = endpoint
+                .split_once("://")
+                .map_or(endpoint, |(_, host)| host);
+            let path = format!("/{}/{}", object.bucket, uri_encode(&object.key));
+            (format!("{endpoint}{path}"), host.to_string(), path)
+        }
+        None => {
+            let host = format!("{}.s3.{}.amazonaws.com", object.bucket, s3_env.region);
+            let path = format!("/{}", uri_encode(&object.key));
+            (format!("https://{host}{path}"), host, path)
+        }
+    };
+
+    let mut request = ureq::get(&url);
+    for (name, value) in signed_headers(s3_env, &host, &path) {
+        request = request.header(name, value);
+    }
+
+    let response = request.call()?;
+    let total: Option<u64> = response
+        .headers()
+        .get("content-length")
+        .and_then(|len| len.to_str().ok())
+        .and_then(|len| len.parse().ok());
+
+    let mut reader = response.into_body().into_reader();
+    let mut bytes = vec![];
+    let mut chunk = vec![0; DOWNLOAD_CHUNK];
+    loop {
+        if sink.is_stopped() {
+            return Ok(());
+        }
+        let read = reader.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..read]);
+        sink.set_status(Some(progress(bytes.len() as u64, total)));
+    }
+
+    for line in decode_log(bytes, None)?.lines() {
+        sink.push(line.to_string());
+    }
+    Ok(())
+}
+
+fn progress(done: u64, total: Option<u64>) -> String {
+    let mb = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+    match total {
+        Some(total) if total > 0 => format!(
+            "downloading {:.1} / {:.1} MB ({}%)",
+            mb(done),
+            mb(total),
+            done * 100 / total
+        ),
+        _ => format!("downloading {:.1} MB", mb(done)),
+    }
+}
+
+/// Signs the request with AWS signature version 4. Without credentials the
+/// request goes out unsigned, which works for public buckets.
+fn signed_headers(s3_env: &S3Env, host: &str, path: &str) -> Vec<(&'static str, String)> {
+    let (Some(access_key), Some(secret_key)) = (&s3_env.access_key, &s3_env.secret_key) else {
+        return vec![];
+    };
+
+    let now = Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = String::from("UNSIGNED-PAYLOAD");
+
+    // Canonical headers have to be sorted by name.
+    let mut headers = vec![
+        ("host", host.to_string()),
+        ("x-amz-content-sha256", payload_hash.clone()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(token) = &s3_env.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}\n"))
+        .collect();
+    let signed_names = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<&str>>()
+        .join(";");
+
+    let canonical_request =
+        format!("GET\n{path}\n\n{canonical_headers}\n{signed_names}\n{payload_hash}");
+    let scope = format!("{date}/{}/s3/aws4_request", s3_env.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let signing_key = [s3_env.region.as_str(), "s3", "aws4_request"].iter().fold(
+        hmac(format!("AWS4{secret_key}").as_bytes(), date.as_bytes()),
+        |key, part| hmac(&key, part.as_bytes()),
+    );
+    let signature = hex(&hmac(&signing_key, string_to_sign.as_bytes()));
+
+    // Host is set by the http client from the URL.
+    headers.retain(|(name, _)| *name != "host");
+    headers.push((
+        "authorization",
+        format!(
+            "AWS4-HMAC-SHA256 Credential={access_key}/{scope}, \
+             SignedHeaders={signed_names}, Signature={signature}"
+        ),
+    ));
+    headers
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Percent encodes an object key the way S3 expects, leaving `/` alone.
+pub(crate) fn uri_encode(key: &str) -> String {
+    key.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
@@ -0,0 +1,137 @@
+use crate::{Level, LogEntry, continues};
+
+/// The rates `S` goes through, before turning sampling off again.
+pub(crate) const SAMPLE_RATES: [usize; 3] = [10, 100, 1000];
+
+/// Keeps one in every so many lines coming in, along with every line of
+/// WARNING and above, so the viewer keeps up with a busy stream.
+#[derive(Debug)]
+pub(crate) struct Sampler {
+    /// One line in this many is kept.
+    pub(crate) rate: usize,
+    seen: usize,
+    /// How many lines were left out.
+    pub(crate) dropped: usize,
+    /// The last line left out, so the lines grouped under it go too.
+    last_dropped: Option<LogEntry>,
+}
+
+impl Sampler {
+    pub(crate) fn new(rate: usize) -> Sampler {
+        Sampler {
+            rate,
+            seen: 0,
+            dropped: 0,
+            last_dropped: None,
+        }
+    }
+
+    /// Returns the entry if it's kept. The lines grouped under an entry, like
+    /// a stack trace, go the same way as it, with `last` being the entry kept
+    /// before this one.
+    pub(crate) fn sample(&mut self, entry: LogEntry, last: Option<&LogEntry>) -> Option<LogEntry> {
+        match &self.last_dropped {
+            Some(head) if continues(head, &entry) => {
+                self.dropped += 1;
+                return None;
+            }
+            Some(_) => self.last_dropped = None,
+            None if last.is_some_and(|last| continues(last, &entry)) => return Some(entry),
+            None => {}
+        }
+
+        self.seen += 1;
+        if entry.level.is_some_and(|level| level >= Level::Warning)
+            || (self.seen - 1).is_multiple_of(self.rate)
+        {
+            return Some(entry);
+        }
+        self.dropped += 1;
+        self.last_dropped = Some(entry);
+        None
+    }
+
+    /// Describes the sampling for the title of the logs.
+    pub(crate) fn describe(&self) -> String {
+        format!(
+            "sampling 1 in {}, WARNING+ kept ({} left out)",
+            self.rate, self.dropped
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(line: &str, level: Option<Level>) -> LogEntry {
+        LogEntry {
+            level,
+            ..LogEntry::new(line.to_string(), 0)
+        }
+    }
+
+    /// The lines the sampler keeps, with the last kept handed back to it like
+    /// the model does.
+    fn kept(sampler: &mut Sampler, entries: Vec<LogEntry>) -> Vec<String> {
+        let mut kept: Vec<LogEntry> = vec![];
+        for entry in entries {
+            if let Some(entry) = sampler.sample(entry, kept.last()) {
+                kept.push(entry);
+            }
+        }
+        kept.into_iter().map(|entry| entry.line).collect()
+    }
+
+    #[test]
+    fn keeps_one_in_every_so_many_lines() {
+        let mut sampler = Sampler::new(10);
+        let entries = (0..25)
+            .map(|idx| entry(&format!("line {idx}"), Some(Level::Info)))
+            .collect();
+        assert_eq!(
+            kept(&mut sampler, entries),
+            ["line 0", "line 10", "line 20"]
+        );
+        assert_eq!(sampler.dropped, 22);
+    }
+
+    #[test]
+    fn always_keeps_warnings_and_above() {
+        let mut sampler = Sampler::new(100);
+        let entries = vec![
+            entry("first", None),
+            entry("quiet", Some(Level::Debug)),
+            entry("slow", Some(Level::Warning)),
+            entry("fine", Some(Level::Info)),
+            entry("failed", Some(Level::Error)),
+            entry("down", Some(Level::Critical)),
+        ];
+        assert_eq!(
+            kept(&mut sampler, entries),
+            ["first", "slow", "failed", "down"]
+        );
+        assert_eq!(
+            sampler.describe(),
+            "sampling 1 in 100, WARNING+ kept (2 left out)"
+        );
+    }
+
+    #[test]
+    fn keeps_or_leaves_out_grouped_lines_with_their_entry() {
+        let mut sampler = Sampler::new(2);
+        let entries = vec![
+            entry("kept", Some(Level::Info)),
+            entry("  at kept", None),
+            entry("dropped", Some(Level::Info)),
+            entry("  at dropped", None),
+            entry("Caused by: dropped", None),
+            entry("kept too", Some(Level::Info)),
+        ];
+        assert_eq!(
+            kept(&mut sampler, entries),
+            ["kept", "  at kept", "kept too"]
+        );
+        assert_eq!(sampler.dropped, 3);
+    }
+}
@@ -0,0 +1,495 @@
+use chrono::NaiveDate;
+use regex::Regex;
+use rust_fuzzy_search::fuzzy_compare;
+use std::collections::HashSet;
+
+use crate::{CaseMode, LogEntry, TimeRange};
+
+/// Searches starting with this are regexes whatever the kind, like
+/// `re:[0-9a-f]{8}-`.
+const REGEX_PREFIX: &str = "re:";
+
+/// Starts the word of a search keeping it to a window of time, like
+/// `timeout @14:00-14:10`.
+const WINDOW_PREFIX: char = '@';
+
+/// How closely a line has to match a fuzzy search, from 0 to 1, unless the
+/// config file says otherwise.
+const FUZZY_THRESHOLD: f32 = 0.4;
+
+/// How much the threshold changes each time it's tightened or loosened.
+const FUZZY_STEP: f32 = 0.1;
+
+/// How the search is matched against lines.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SearchKind {
+    /// Lines close to the search, for looking around.
+    #[default]
+    Fuzzy,
+    /// Lines holding every word of the search as it's written, in any
+    /// order, for finding a request ID. Quoted words are kept together, like
+    /// `"connection reset" payments`.
+    Exact,
+    Regex,
+}
+
+impl SearchKind {
+    /// The kind after this one, to toggle through them.
+    pub(crate) fn next(self) -> SearchKind {
+        match self {
+            SearchKind::Fuzzy => SearchKind::Exact,
+            SearchKind::Exact => SearchKind::Regex,
+            SearchKind::Regex => SearchKind::Fuzzy,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            SearchKind::Fuzzy => "fuzzy",
+            SearchKind::Exact => "exact",
+            SearchKind::Regex => "regex",
+        }
+    }
+}
+
+/// What a search does once it's kept with `Enter`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SearchStyle {
+    /// Shows every line again, for moving between the matches with `n` and
+    /// `N` among the lines around them.
+    #[default]
+    Jump,
+    /// Keeps showing only the lines it matches, as it does while typing.
+    Filter,
+}
+
+impl SearchStyle {
+    pub(crate) fn toggled(self) -> SearchStyle {
+        match self {
+            SearchStyle::Jump => SearchStyle::Filter,
+            SearchStyle::Filter => SearchStyle::Jump,
+        }
+    }
+
+    /// Reads the style as it's written in the config file.
+    pub(crate) fn from_name(name: &str) -> Option<SearchStyle> {
+        match name {
+            "jump" => Some(SearchStyle::Jump),
+            "filter" => Some(SearchStyle::Filter),
+            _ => None,
+        }
+    }
+}
+
+/// How lines are scored against a fuzzy search.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FuzzyScorer {
+    /// By the runs of three letters of the search the line shares, which
+    /// forgives typos.
+    #[default]
+    Trigram,
+    /// By the letters of the search the line holds in order, like fzf, with
+    /// letters next to each other scoring higher.
+    Subsequence,
+}
+
+impl FuzzyScorer {
+    /// Reads the scorer as it's written in the config file or on the command
+    /// line.
+    pub(crate) fn from_name(name: &str) -> Option<FuzzyScorer> {
+        match name {
+            "trigram" => Some(FuzzyScorer::Trigram),
+            "subsequence" => Some(FuzzyScorer::Subsequence),
+            _ => None,
+        }
+    }
+}
+
+/// How fuzzy searches are matched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Fuzzy {
+    /// How well a line has to score to match, from 0 to 1.
+    pub(crate) threshold: f32,
+    pub(crate) scorer: FuzzyScorer,
+}
+
+impl Default for Fuzzy {
+    fn default() -> Fuzzy {
+        Fuzzy {
+            threshold: FUZZY_THRESHOLD,
+            scorer: FuzzyScorer::default(),
+        }
+    }
+}
+
+impl Fuzzy {
+    /// Checks a threshold read from the config file or the command line.
+    pub(crate) fn valid_threshold(threshold: f32) -> Option<f32> {
+        (threshold > 0.0 && threshold <= 1.0).then_some(threshold)
+    }
+
+    /// Needs lines to match more closely, or less with a negative step.
+    pub(crate) fn tighten(&mut self, steps: f32) {
+        let threshold = self.threshold + steps * FUZZY_STEP;
+        // Rounded so the steps don't drift, and kept from matching all or
+        // nothing.
+        self.threshold = ((threshold * 10.0).round() / 10.0).clamp(FUZZY_STEP, 1.0 - FUZZY_STEP);
+    }
+
+    pub(crate) fn next_scorer(&mut self) {
+        self.scorer = match self.scorer {
+            FuzzyScorer::Trigram => FuzzyScorer::Subsequence,
+            FuzzyScorer::Subsequence => FuzzyScorer::Trigram,
+        };
+    }
+
+    /// Describes the matching for the search bar, like `fuzzy 0.4`.
+    pub(crate) fn describe(self) -> String {
+        match self.scorer {
+            FuzzyScorer::Trigram => format!("fuzzy {:.1}", self.threshold),
+            FuzzyScorer::Subsequence => format!("fuzzy {:.1} in order", self.threshold),
+        }
+    }
+
+    /// How closely the line matches the search, from 0 to 1.
+    fn score(self, search: &str, line: &str, case: CaseMode) -> f32 {
+        let score = match self.scorer {
+            FuzzyScorer::Trigram => fuzzy_compare,
+            FuzzyScorer::Subsequence => subsequence_score,
+        };
+        match case.ignores_case(search) {
+            true => score(&search.to_lowercase(), &line.to_lowercase()),
+            false => score(search, line),
+        }
+    }
+}
+
+/// Scores the letters of the search found in the line in order, a point for
+/// each and another for each one right after the one before it, so the
+/// search written out in full scores 1. Each place the search could start is
+/// tried, to find its best run.
+fn subsequence_score(search: &str, line: &str) -> f32 {
+    let search: Vec<char> = search.chars().filter(|c| !c.is_whitespace()).collect();
+    let line: Vec<char> = line.chars().collect();
+    let Some(first) = search.first() else {
+        return 0.0;
+    };
+
+    let mut best = 0;
+    for start in (0..line.len()).filter(|&idx| line[idx] == *first) {
+        let mut points = 1;
+        let mut pos = start;
+        for c in &search[1..] {
+            let Some(found) = line[pos + 1..].iter().position(|letter| letter == c) else {
+                points = 0;
+                break;
+            };
+            points += if found == 0 { 2 } else { 1 };
+            pos += found + 1;
+        }
+        best = best.max(points);
+    }
+    best as f32 / (2 * search.len() - 1) as f32
+}
+
+/// The lines an exact search didn't match, to skip them once more is typed
+/// onto it. A line missing a word can't hold a longer one, so typing on only
+/// rules out more lines.
+#[derive(Debug)]
+pub(crate) struct SearchCache {
+    search: String,
+    case: CaseMode,
+    /// The file and number of each line ruled out.
+    missed: HashSet<(usize, usize)>,
+}
+
+impl SearchCache {
+    /// Only exact searches are worth caching, since they're the only ones
+    /// typing on narrows down.
+    pub(crate) fn new(
+        search: &str,
+        kind: SearchKind,
+        case: CaseMode,
+        missed: impl FnOnce() -> HashSet<(usize, usize)>,
+    ) -> Option<SearchCache> {
+        // Typing on to a window can widen it, like `@14:00` to
+        // `@14:00-14:10`.
+        let narrows = !search.starts_with(REGEX_PREFIX) && !search.contains(WINDOW_PREFIX);
+        (kind == SearchKind::Exact && narrows).then(|| SearchCache {
+            search: search.to_string(),
+            case,
+            missed: missed(),
+        })
+    }
+
+    /// Whether the lines ruled out are ruled out for this search too.
+    pub(crate) fn narrows(&self, search: &str, kind: SearchKind, case: CaseMode) -> bool {
+        kind == SearchKind::Exact
+            && case == self.case
+            && search.starts_with(&self.search)
+            && !search.starts_with(REGEX_PREFIX)
+    }
+
+    pub(crate) fn missed(&self, line: (usize, usize)) -> bool {
+        self.missed.contains(&line)
+    }
+}
+
+/// A search for the word as it's written, whatever the kind of search is
+/// set to, so its dots and dashes aren't taken as a regex or split up.
+pub(crate) fn word_search(word: &str) -> String {
+    format!("{REGEX_PREFIX}{}", regex::escape(word))
+}
+
+/// Splits the search into its words, keeping the ones in double quotes
+/// together as a phrase. A quote left open runs to the end.
+fn search_words(search: &str) -> Vec<String> {
+    let mut words = vec![];
+    for (i, part) in search.split('"').enumerate() {
+        // Every other part is between quotes.
+        match i % 2 {
+            1 => words.push(part.to_string()),
+            _ => words.extend(part.split_whitespace().map(String::from)),
+        }
+    }
+    words.retain(|word| !word.is_empty());
+    words
+}
+
+/// Takes the window of time out of the search, if it has one. It's the
+/// last word starting with `@` and then a time, or a dash for a window
+/// without a start, so the `@` of a handle like `@alice` is searched for.
+fn split_window(search: &str) -> (String, Option<&str>) {
+    let window = search.split_whitespace().rev().find_map(|word| {
+        let window = word.strip_prefix(WINDOW_PREFIX)?;
+        window
+            .starts_with(|c: char| c.is_ascii_digit() || c == '-')
+            .then_some((word, window))
+    });
+    match window {
+        Some((word, window)) => (
+            search.replacen(word, "", 1).trim().to_string(),
+            Some(window),
+        ),
+        None => (search.to_string(), None),
+    }
+}
+
+/// How the text of a line is matched against the search.
+#[derive(Debug, Clone)]
+enum Pattern {
+    Fuzzy(String, CaseMode, Fuzzy),
+    /// The words a line has to hold, each matched with the case mode on
+    /// its own.
+    Exact(Vec<String>, CaseMode),
+    Regex(Regex),
+}
+
+/// What the search is matched with, made once each time the lines are
+/// filtered.
+#[derive(Debug, Clone)]
+pub(crate) struct Matcher {
+    pattern: Pattern,
+    /// When lines have to be logged to match, if the search says.
+    window: Option<TimeRange>,
+}
+
+impl Matcher {
+    /// Times in the window without a date are taken to be on the given
+    /// date, the one the log starts on.
+    pub(crate) fn new(
+        search: &str,
+        kind: SearchKind,
+        case: CaseMode,
+        fuzzy: Fuzzy,
+        date: Option<NaiveDate>,
+    ) -> Result<Matcher, String> {
+        let (search, window) = split_window(search);
+        let window = window
+            .map(|window| TimeRange::parse_window(window, date))
+            .transpose()
+            .map_err(String::from)?;
+        let (search, kind) = match search.strip_prefix(REGEX_PREFIX) {
+            Some(pattern) => (pattern, SearchKind::Regex),
+            None => (search.as_str(), kind),
+        };
+        let pattern = match kind {
+            // A window on its own matches every line logged in it.
+            _ if search.trim().is_empty() => Pattern::Exact(vec![], case),
+            SearchKind::Fuzzy => Pattern::Fuzzy(search.to_string(), case, fuzzy),
+            SearchKind::Exact => Pattern::Exact(search_words(search), case),
+            SearchKind::Regex => Regex::new(search)
+                .map(|regex| Pattern::Regex(case.regex(&regex)))
+                .map_err(|_| String::from("invalid regex"))?,
+        };
+        Ok(Matcher { pattern, window })
+    }
+
+    /// A regex finding what the search matched in a line, to highlight it.
+    /// Fuzzy searches highlight the words of the search a line holds.
+    pub(crate) fn highlight(&self) -> Option<Regex> {
+        let (words, case): (Vec<String>, _) = match &self.pattern {
+            Pattern::Regex(regex) => return Some(regex.clone()),
+            Pattern::Exact(words, case) => {
+                (words.iter().map(|word| regex::escape(word)).collect(), case)
+            }
+            Pattern::Fuzzy(search, case, _) => (
+                search
+                    .split_whitespace()
+                    .filter(|word| word.chars().count() > 1)
+                    .map(regex::escape)
+                    .collect(),
+                case,
+            ),
+        };
+        if words.is_empty() {
+            return None;
+        }
+        let words = Regex::new(&words.join("|")).ok()?;
+        Some(case.regex(&words))
+    }
+
+    /// Lines without a timestamp are left out of a window. Like the
+    /// filters, any of the lines grouped into an entry can match it.
+    pub(crate) fn matches(&self, entry: &LogEntry) -> bool {
+        let in_window = self.window.as_ref().is_none_or(|window| {
+            entry
+                .timestamp
+                .is_some_and(|timestamp| window.contains(timestamp))
+        });
+        in_window
+            && (self.matches_line(&entry.line)
+                || entry
+                    .continuation
+                    .iter()
+                    .any(|line| self.matches_line(line)))
+    }
+
+    fn matches_line(&self, line: &str) -> bool {
+        match &self.pattern {
+            Pattern::Fuzzy(search, case, fuzzy) => {
+                fuzzy.score(search, line, *case) >= fuzzy.threshold
+            }
+            Pattern::Exact(words, case) => words.iter().all(|word| case.contains(line, word)),
+            Pattern::Regex(regex) => regex.is_match(line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn matcher(search: &str, kind: SearchKind) -> Matcher {
+        Matcher::new(search, kind, CaseMode::default(), Fuzzy::default(), None).unwrap()
+    }
+
+    #[test]
+    fn matches_the_lines_grouped_into_an_entry() {
+        let entry = LogEntry {
+            continuation: vec![String::from("    at Db.connect (db.js:12)")],
+            ..LogEntry::new(String::from("ERROR request failed"), 0)
+        };
+        assert!(matcher("Db.connect", SearchKind::Exact).matches(&entry));
+        assert!(matcher("re:db\\.js:\\d+", SearchKind::Regex).matches(&entry));
+        assert!(matcher("request failed", SearchKind::Exact).matches(&entry));
+        // The words of an exact search are all looked for in the same line.
+        assert!(!matcher("request Db.connect", SearchKind::Exact).matches(&entry));
+    }
+
+    #[test]
+    fn splits_an_exact_search_into_words_and_phrases() {
+        assert_eq!(
+            search_words(r#"payments "connection reset"  by peer"#),
+            ["payments", "connection reset", "by", "peer"]
+        );
+        assert_eq!(
+            search_words(r#"timeout "read tcp"#),
+            ["timeout", "read tcp"]
+        );
+        assert!(search_words(r#" "" "#).is_empty());
+    }
+
+    #[test]
+    fn matches_every_word_of_an_exact_search_in_any_order() {
+        let search = matcher(r#""connection reset" payments"#, SearchKind::Exact);
+        let line = |line: &str| LogEntry::new(line.to_string(), 0);
+        assert!(search.matches(&line("payments: connection reset by peer")));
+        assert!(!search.matches(&line("payments: reset connection")));
+        assert!(!search.matches(&line("orders: connection reset by peer")));
+
+        let highlight = search.highlight().unwrap();
+        let found: Vec<&str> = highlight
+            .find_iter("payments: connection reset")
+            .map(|found| found.as_str())
+            .collect();
+        assert_eq!(found, ["payments", "connection reset"]);
+    }
+
+    #[test]
+    fn keeps_a_search_to_a_window_of_time() {
+        assert_eq!(
+            split_window("timeout @14:00-14:10 db"),
+            (String::from("timeout  db"), Some("14:00-14:10"))
+        );
+        assert_eq!(
+            split_window("ping @alice"),
+            (String::from("ping @alice"), None)
+        );
+
+        let date = NaiveDate::from_ymd_opt(2024, 5, 3);
+        let search = Matcher::new(
+            "timeout @14:00-",
+            SearchKind::Exact,
+            CaseMode::default(),
+            Fuzzy::default(),
+            date,
+        )
+        .unwrap();
+        let logged = |time: &str| LogEntry {
+            timestamp: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").ok(),
+            ..LogEntry::new(String::from("db timeout"), 0)
+        };
+        assert!(search.matches(&logged("2024-05-03 14:01")));
+        assert!(!search.matches(&logged("2024-05-03 13:59")));
+        assert!(!search.matches(&logged("no timestamp")));
+        assert!(
+            Matcher::new(
+                "@25:00",
+                SearchKind::Exact,
+                CaseMode::default(),
+                Fuzzy::default(),
+                date
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn caches_only_exact_searches_that_typing_narrows() {
+        let cache =
+            |search, kind| SearchCache::new(search, kind, CaseMode::default(), HashSet::new);
+        assert!(cache("timeout", SearchKind::Exact).is_some());
+        assert!(cache("timeout", SearchKind::Fuzzy).is_none());
+        assert!(cache("timeout", SearchKind::Regex).is_none());
+        assert!(cache("re:time", SearchKind::Exact).is_none());
+        assert!(cache("timeout @14:00", SearchKind::Exact).is_none());
+    }
+
+    #[test]
+    fn rules_out_the_lines_missed_while_typing_on() {
+        let case = CaseMode::default();
+        let cache = SearchCache::new("time", SearchKind::Exact, case, || {
+            HashSet::from([(0, 3), (1, 7)])
+        })
+        .unwrap();
+        assert!(cache.missed((1, 7)));
+        assert!(!cache.missed((0, 7)));
+
+        assert!(cache.narrows("timeout", SearchKind::Exact, case));
+        assert!(!cache.narrows("tim", SearchKind::Exact, case));
+        assert!(!cache.narrows("timeout", SearchKind::Fuzzy, case));
+        assert!(!cache.narrows("timeout", SearchKind::Exact, case.next()));
+    }
+}
@@ -0,0 +1,81 @@
+use color_eyre::Result;
+use std::{io::Read, time::Duration};
+
+use crate::{LineSink, LineStream};
+
+/// Baud rate used when the target doesn't give one.
+const DEFAULT_BAUD: u32 = 115_200;
+
+/// How long a read blocks before checking if the stream was dropped.
+const READ_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// A serial device and its baud rate, written as `/dev/ttyUSB0:115200`.
+#[derive(Debug, Clone)]
+pub(crate) struct SerialTarget {
+    path: String,
+    baud: u32,
+}
+
+impl SerialTarget {
+    pub(crate) fn parse(target: &str) -> Result<Self, &'static str> {
+        let (path, baud) = match target.rsplit_once(':') {
+            Some((path, baud)) => (
+                path,
+                baud.parse()
+                    .map_err(|_| "The baud rate must be a number.")?,
+            ),
+            None => (target, DEFAULT_BAUD),
+        };
+
+        if path.is_empty() {
+            return Err("--serial needs a device path.");
+        }
+
+        Ok(SerialTarget {
+            path: path.to_string(),
+            baud,
+        })
+    }
+
+    pub(crate) fn name(&self) -> String {
+        format!("{}@{}", self.path, self.baud)
+    }
+}
+
+/// Reads lines from a serial port as the device writes them.
+///
+/// The port is opened here so a missing device or a permissions problem
+/// fails before the TUI starts.
+pub(crate) fn serial_stream(target: &SerialTarget) -> Result<LineStream> {
+    let port = serialport::new(&target.path, target.baud)
+        .timeout(READ_TIMEOUT)
+        .open()?;
+
+    Ok(LineStream::spawn_thread(move |sink| read_port(port, sink)))
+}
+
+fn read_port(mut port: Box<dyn serialport::SerialPort>, sink: LineSink) {
+    let mut buf = [0; 1024];
+    let mut line = vec![];
+
+    while !sink.is_stopped() {
+        match port.read(&mut buf) {
+            Ok(read) => {
+                for &byte in &buf[..read] {
+                    if byte == b'\n' {
+                        let text = String::from_utf8_lossy(&line);
+                        sink.push(text.trim_end_matches('\r').to_string());
+                        line.clear();
+                    } else {
+                        line.push(byte);
+                    }
+                }
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(error) => {
+                sink.push(format!("[ERROR] serial port closed: {error}"));
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::{env, fs, io::ErrorKind, path::PathBuf};
+
+use toml::{Table, Value};
+
+use crate::{
+    CaseMode, FieldExtractor, Fuzzy, FuzzyScorer, GapThreshold, Level, LevelAliases, Preset,
+    SearchStyle, parse_presets,
+};
+
+/// Settings read from the config file.
+///
+/// ```toml
+/// case = "smart"
+/// fuzzy_threshold = 0.6
+/// fuzzy_scorer = "subsequence"
+/// search_style = "filter"
+/// gap_threshold = 60
+/// scrolloff = 5
+/// extract = ['latency=(?P<ms>\d+)ms']
+///
+/// [levels]
+/// warning = ["WARN", "Avertissement"]
+/// critical = ["FATAL", "/^!!!/"]
+///
+/// [presets.payments-errors]
+/// levels = ["error", "critical"]
+/// regex = "service=payments"
+/// ```
+#[derive(Debug, Default)]
+pub(crate) struct Settings {
+    pub(crate) level_aliases: LevelAliases,
+    pub(crate) presets: Vec<Preset>,
+    /// Whether case counts in the search and plain text filters.
+    pub(crate) case: CaseMode,
+    /// How fuzzy searches are matched.
+    pub(crate) fuzzy: Fuzzy,
+    /// What searches do once they're kept.
+    pub(crate) search_style: SearchStyle,
+    /// How long the log goes quiet for a gap, in seconds in the file.
+    pub(crate) gap_threshold: GapThreshold,
+    /// How many rows to keep between the selection and the edges of the
+    /// logs, like vim's `scrolloff`.
+    pub(crate) scrolloff: usize,
+    /// Regexes pulling fields out of lines, from the `extract` list.
+    pub(crate) extractors: Vec<FieldExtractor>,
+    /// Where the config file is, or would be, so presets can be saved to it.
+    pub(crate) path: Option<PathBuf>,
+}
+
+impl Settings {
+    /// Reads the config file at the given path, or the one in the config
+    /// directory. Only a file that was asked for has to exist.
+    pub(crate) fn load(path: Option<&str>) -> Result<Settings, &'static str> {
+        let (path, required) = match path {
+            Some(path) => (PathBuf::from(path), true),
+            None => match config_dir() {
+                Some(dir) => (dir.join("config.toml"), false),
+                None => return Ok(Settings::default()),
+            },
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == ErrorKind::NotFound && !required => {
+                return Ok(Settings {
+                    path: Some(path),
+                    ..Settings::default()
+                });
+            }
+            Err(_) => return Err("Couldn't read the config file."),
+        };
+        let table: Table = text
+            .parse()
+            .map_err(|_| "The config file isn't valid TOML.")?;
+
+        let mut settings = Settings {
+            path: Some(path),
+            ..Settings::default()
+        };
+        if let Some(case) = table.get("case") {
+            settings.case = case
+                .as_str()
+                .and_then(CaseMode::from_name)
+                .ok_or("case in the config file takes smart, sensitive or insensitive.")?;
+        }
+        if let Some(threshold) = table.get("fuzzy_threshold") {
+            settings.fuzzy.threshold = threshold
+                .as_float()
+                .and_then(|threshold| Fuzzy::valid_threshold(threshold as f32))
+                .ok_or("fuzzy_threshold in the config file takes a number from 0 to 1.")?;
+        }
+        if let Some(scorer) = table.get("fuzzy_scorer") {
+            settings.fuzzy.scorer = scorer
+                .as_str()
+                .and_then(FuzzyScorer::from_name)
+                .ok_or("fuzzy_scorer in the config file takes trigram or subsequence.")?;
+        }
+        if let Some(style) = table.get("search_style") {
+            settings.search_style = style
+                .as_str()
+                .and_then(SearchStyle::from_name)
+                .ok_or("search_style in the config file takes jump or filter.")?;
+        }
+        if let Some(seconds) = table.get("gap_threshold") {
+            settings.gap_threshold = seconds
+                .as_integer()
+                .and_then(GapThreshold::from_seconds)
+                .ok_or("gap_threshold in the config file takes a number of seconds above 0.")?;
+        }
+        if let Some(rows) = table.get("scrolloff") {
+            settings.scrolloff = rows
+                .as_integer()
+                .and_then(|rows| usize::try_from(rows).ok())
+                .ok_or("scrolloff in the config file takes a number of rows.")?;
+        }
+        if let Some(extract) = table.get("extract") {
+            settings.extractors = extract
+                .as_array()
+                .ok_or("extract in the config file takes a list of regexes.")?
+                .iter()
+                .map(|pattern| {
+                    pattern
+                        .as_str()
+                        .ok_or("extract in the config file takes a list of regexes.")
+                        .and_then(FieldExtractor::new)
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(levels) = table.get("levels") {
+            settings.level_aliases = parse_levels(levels)?;
+        }
+        if let Some(presets) = table.get("presets") {
+            settings.presets = parse_presets(presets)?;
+        }
+        Ok(settings)
+    }
+}
+
+/// Where the config file lives: `$XDG_CONFIG_HOME/log_viewer`, or
+/// `~/.config/log_viewer`.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("log_viewer"))
+}
+
+/// Where what the viewer keeps between runs, like the history, lives:
+/// `$XDG_DATA_HOME/log_viewer`, or `~/.local/share/log_viewer`.
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(base.join("log_viewer"))
+}
+
+/// Reads the `[levels]` section, a list of aliases for each level.
+fn parse_levels(levels: &Value) -> Result<LevelAliases, &'static str> {
+    let levels = levels
+        .as_table()
+        .ok_or("[levels] in the config file must be a table.")?;
+
+    let mut aliases = LevelAliases::default();
+    for (key, names) in levels {
+        let level = Level::ALL
+            .into_iter()
+            .find(|level| level.key() == key)
+            .ok_or("[levels] takes debug, info, warning, error and critical.")?;
+        let names = names
+            .as_array()
+            .ok_or("Each level in [levels] takes a list of aliases.")?;
+
+        for name in names {
+            let name = name
+                .as_str()
+                .ok_or("Level aliases in the config file must be strings.")?;
+            aliases
+                .add(name, level)
+                .map_err(|_| "A /regex/ level alias in the config file isn't valid.")?;
+        }
+    }
+    Ok(aliases)
+}
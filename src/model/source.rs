@@ -0,0 +1,461 @@
+use color_eyre::Result;
+use std::{
+    fmt::Debug,
+    fs::{self, File, Metadata},
+    io::{Read, Seek, SeekFrom},
+};
+
+use encoding_rs::{Encoding, UTF_8};
+
+use crate::{
+    LineStream, LogEntry, LogWatcher, decompress, delimiter_for, guess_encoding, is_compressed,
+    is_w3c, parse_delimited, parse_delimited_rows, parse_w3c, parse_w3c_rows, w3c_fields,
+};
+
+/// Lines handed over by a source since it was last polled.
+#[derive(Debug)]
+pub enum NewLines {
+    /// Lines that come after the ones already read.
+    Appended(Vec<LogEntry>),
+    /// Everything the source holds, replacing what was read before. For
+    /// sources that can change underneath us, like a truncated file.
+    Reloaded(Vec<LogEntry>),
+}
+
+/// What a source tells the viewer about itself.
+#[derive(Debug, Default)]
+pub struct SourceMetadata {
+    /// One name per log the source reads, indexed by `LogEntry::source`.
+    pub names: Vec<String>,
+    /// What the source is busy with, like a download's progress.
+    pub status: Option<String>,
+    /// Set for sources that only ever append, whose lines can be cleared
+    /// without coming back on the next poll.
+    pub streaming: bool,
+    /// Column names, for sources whose lines are rows of a table. Each line's
+    /// cells are its structured fields.
+    pub columns: Option<Vec<String>>,
+}
+
+/// Somewhere the lines of a tab come from.
+///
+/// The viewer opens a source once, then asks it whether it has anything new
+/// on every pass of the event loop and polls it when it does.
+pub trait LogSource: Debug {
+    /// Gets the source ready to be polled. Errors here keep the viewer from
+    /// starting, so they should be for things the user needs to fix.
+    fn open(&mut self) -> Result<()>;
+
+    /// Returns true if the next poll would return something. This is called
+    /// often, so it shouldn't block.
+    fn has_new_lines(&mut self) -> bool;
+
+    /// Takes whatever the source received since it was last polled.
+    fn poll_new_lines(&mut self) -> NewLines;
+
+    fn metadata(&self) -> SourceMetadata;
+
+    /// Drops what the source has received so far, for sources that only
+    /// ever append. Sources holding a buffer of their own, like a device's
+    /// logcat, can clear that too.
+    fn clear(&mut self) {}
+}
+
+/// Log files on disk. Several files are read one after another, it's up to
+/// the model to merge them.
+///
+/// Once read, only what's appended to a file is read when it changes. The
+/// files are read again in full when one of them shrinks or is replaced, like
+/// when it's rotated, or when what's appended can't be read on its own.
+#[derive(Debug)]
+pub(crate) struct FileSource {
+    paths: Vec<String>,
+    /// Column that gives the level of each row of a CSV or TSV file.
+    level_column: Option<String>,
+    /// Set when the encoding of the files was given instead of guessed.
+    encoding: Option<&'static Encoding>,
+    /// The header of a CSV or TSV file, once it's been read.
+    columns: Option<Vec<String>>,
+    layout: Layout,
+    /// How far each file has been read, empty until they've been read.
+    read_to: Vec<ReadTo>,
+    watcher: Option<LogWatcher>,
+}
+
+/// How the lines of the files are laid out.
+#[derive(Debug, Default)]
+enum Layout {
+    #[default]
+    Lines,
+    /// A CSV or TSV file, split on this delimiter.
+    Delimited(char),
+    /// W3C extended logs, with the fields the rows at the end of each file
+    /// have.
+    W3c(Vec<Vec<String>>),
+}
+
+/// How far a file has been read, to read only what's appended to it next.
+#[derive(Debug)]
+struct ReadTo {
+    len: u64,
+    /// Set when the file didn't end on a newline, so what's appended next
+    /// carries on a line that was already read.
+    mid_line: bool,
+    /// Archives can't be read from partway through.
+    compressed: bool,
+    encoding: &'static Encoding,
+    /// Tells when the file was replaced by another one at the same path.
+    id: Option<u64>,
+}
+
+impl FileSource {
+    pub(crate) fn new(
+        paths: &[String],
+        level_column: Option<String>,
+        encoding: Option<&'static Encoding>,
+    ) -> Self {
+        FileSource {
+            paths: paths.to_vec(),
+            level_column,
+            encoding,
+            columns: None,
+            layout: Layout::default(),
+            read_to: vec![],
+            watcher: None,
+        }
+    }
+
+    /// Reads every file from the start.
+    fn read_all(&mut self) -> Vec<LogEntry> {
+        self.read_to.clear();
+        let mut texts = vec![];
+        for path in &self.paths {
+            let id = fs::metadata(path).ok().and_then(|meta| file_id(&meta));
+            let bytes = fs::read(path).unwrap_or_default();
+            let mut read_to = ReadTo {
+                len: bytes.len() as u64,
+                mid_line: false,
+                compressed: is_compressed(&bytes),
+                encoding: UTF_8,
+                id,
+            };
+            let decoded = decompress(bytes).unwrap_or_default();
+            read_to.encoding = self.encoding.unwrap_or_else(|| guess_encoding(&decoded));
+            // A byte order mark wins over the encoding, and is dropped from
+            // the text.
+            let (text, _, _) = read_to.encoding.decode(&decoded);
+            read_to.mid_line = !text.is_empty() && !text.ends_with('\n');
+            texts.push(text.into_owned());
+            self.read_to.push(read_to);
+        }
+
+        // Delimited files are shown as a table, which only works when there's
+        // a single header.
+        if let [path] = self.paths.as_slice()
+            && let Some(delimiter) = delimiter_for(path)
+        {
+            let (columns, logs) =
+                parse_delimited(&texts[0], delimiter, self.level_column.as_deref(), 0);
+            self.columns = Some(columns);
+            self.layout = Layout::Delimited(delimiter);
+            return logs;
+        }
+
+        // W3C logs, like the ones IIS writes, name their columns in a
+        // header of their own.
+        if !texts.is_empty() && texts.iter().all(|text| is_w3c(text)) {
+            let (columns, logs) = parse_w3c(&texts);
+            self.columns = Some(columns);
+            self.layout = Layout::W3c(texts.iter().map(|text| w3c_fields(text)).collect());
+            return logs;
+        }
+
+        self.layout = Layout::Lines;
+        let mut logs: Vec<LogEntry> = vec![];
+        for (source, lines) in texts.iter().enumerate() {
+            logs.extend(
+                lines
+                    .lines()
+                    .map(|line| LogEntry::new(line.to_string(), source)),
+            );
+        }
+        logs
+    }
+
+    /// Reads what was appended to the files since they were last read, or
+    /// returns `None` when they have to be read again from the start.
+    fn read_appended(&mut self) -> Option<Vec<LogEntry>> {
+        let mut logs = vec![];
+        for (source, path) in self.paths.iter().enumerate() {
+            let meta = fs::metadata(path).ok()?;
+            let read_to = &mut self.read_to[source];
+            if file_id(&meta) != read_to.id || meta.len() < read_to.len {
+                return None;
+            }
+            if meta.len() == read_to.len {
+                continue;
+            }
+            if read_to.compressed || read_to.mid_line {
+                return None;
+            }
+
+            let mut file = File::open(path).ok()?;
+            file.seek(SeekFrom::Start(read_to.len)).ok()?;
+            let mut bytes = vec![];
+            file.read_to_end(&mut bytes).ok()?;
+            read_to.len += bytes.len() as u64;
+            let (text, _) = read_to.encoding.decode_without_bom_handling(&bytes);
+            read_to.mid_line = !text.ends_with('\n');
+
+            match &self.layout {
+                Layout::Lines => logs.extend(
+                    text.lines()
+                        .map(|line| LogEntry::new(line.to_string(), source)),
+                ),
+                Layout::Delimited(delimiter) => logs.extend(parse_delimited_rows(
+                    self.columns.as_deref().unwrap_or_default(),
+                    &text,
+                    *delimiter,
+                    self.level_column.as_deref(),
+                    source,
+                )),
+                Layout::W3c(fields) => logs.extend(parse_w3c_rows(
+                    self.columns.as_deref().unwrap_or_default(),
+                    &fields[source],
+                    &text,
+                    source,
+                )?),
+            }
+        }
+        Some(logs)
+    }
+}
+
+/// The file's inode, which changes when it's replaced by another file.
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(meta.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &Metadata) -> Option<u64> {
+    None
+}
+
+impl LogSource for FileSource {
+    fn open(&mut self) -> Result<()> {
+        // Following is a nice to have, so a file we can't watch still opens,
+        // it just won't refresh on its own.
+        self.watcher = LogWatcher::new(&self.paths).ok();
+        Ok(())
+    }
+
+    fn has_new_lines(&mut self) -> bool {
+        self.watcher
+            .as_ref()
+            .is_some_and(|watcher| watcher.has_changes())
+    }
+
+    fn poll_new_lines(&mut self) -> NewLines {
+        if !self.read_to.is_empty()
+            && let Some(logs) = self.read_appended()
+        {
+            return NewLines::Appended(logs);
+        }
+        NewLines::Reloaded(self.read_all())
+    }
+
+    fn metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            names: self.paths.clone(),
+            columns: self.columns.clone(),
+            ..SourceMetadata::default()
+        }
+    }
+}
+
+/// Lines pushed to us by a background stream, like a subprocess or a socket.
+#[derive(Debug)]
+pub(crate) struct StreamSource {
+    name: String,
+    stream: LineStream,
+}
+
+impl StreamSource {
+    pub(crate) fn new(name: String, stream: LineStream) -> Self {
+        StreamSource { name, stream }
+    }
+}
+
+impl LogSource for StreamSource {
+    fn open(&mut self) -> Result<()> {
+        // Streams start when they're spawned so errors show up right away.
+        Ok(())
+    }
+
+    fn has_new_lines(&mut self) -> bool {
+        self.stream.has_lines()
+    }
+
+    fn poll_new_lines(&mut self) -> NewLines {
+        let lines = self.stream.take_lines();
+        NewLines::Appended(
+            lines
+                .into_iter()
+                .map(|line| LogEntry::new(line, 0))
+                .collect(),
+        )
+    }
+
+    fn metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            names: vec![self.name.clone()],
+            status: self.stream.status(),
+            streaming: true,
+            columns: None,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.stream.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs::OpenOptions, io::Write};
+
+    /// A file of its own in the temp directory for each test.
+    fn log_file(name: &str, text: &str) -> String {
+        let path = env::temp_dir().join(format!("log_viewer-{}-{name}", std::process::id()));
+        fs::write(&path, text).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn source_of(path: &str) -> FileSource {
+        FileSource::new(&[path.to_string()], None, None)
+    }
+
+    fn append(path: &str, text: &str) {
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn lines(new_lines: NewLines) -> (bool, Vec<String>) {
+        match new_lines {
+            NewLines::Appended(logs) => (true, logs.into_iter().map(|log| log.line).collect()),
+            NewLines::Reloaded(logs) => (false, logs.into_iter().map(|log| log.line).collect()),
+        }
+    }
+
+    #[test]
+    fn reads_only_what_was_appended() {
+        let path = log_file("appended.log", "a\nb\n");
+        let mut source = source_of(&path);
+        assert_eq!(
+            lines(source.poll_new_lines()),
+            (false, vec!["a".into(), "b".into()])
+        );
+
+        append(&path, "c\nd\n");
+        assert_eq!(
+            lines(source.poll_new_lines()),
+            (true, vec!["c".into(), "d".into()])
+        );
+        assert_eq!(lines(source.poll_new_lines()), (true, vec![]));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reads_again_from_the_start_when_the_file_shrinks() {
+        let path = log_file("truncated.log", "a\nb\n");
+        let mut source = source_of(&path);
+        source.poll_new_lines();
+
+        fs::write(&path, "c\n").unwrap();
+        assert_eq!(lines(source.poll_new_lines()), (false, vec!["c".into()]));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reads_again_from_the_start_when_the_file_is_replaced() {
+        let path = log_file("rotated.log", "a\n");
+        let mut source = source_of(&path);
+        source.poll_new_lines();
+
+        let rotated = format!("{path}.1");
+        fs::rename(&path, &rotated).unwrap();
+        fs::write(&path, "b\nc\n").unwrap();
+        let (appended, _) = lines(source.poll_new_lines());
+        assert_eq!(appended, !cfg!(unix));
+        fs::remove_file(path).unwrap();
+        fs::remove_file(rotated).unwrap();
+    }
+
+    #[test]
+    fn reads_again_from_the_start_when_a_line_was_cut_short() {
+        let path = log_file("partial.log", "a\nb");
+        let mut source = source_of(&path);
+        assert_eq!(
+            lines(source.poll_new_lines()),
+            (false, vec!["a".into(), "b".into()])
+        );
+
+        append(&path, "c\nd\n");
+        assert_eq!(
+            lines(source.poll_new_lines()),
+            (false, vec!["a".into(), "bc".into(), "d".into()])
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reads_rows_appended_to_a_table() {
+        let path = log_file("appended.csv", "level,message\nINFO,started\n");
+        let mut source = source_of(&path);
+        source.poll_new_lines();
+
+        append(&path, "ERROR,failed\n");
+        let NewLines::Appended(logs) = source.poll_new_lines() else {
+            panic!("the row should have been appended");
+        };
+        assert_eq!(logs.len(), 1);
+        let level = logs[0]
+            .structured
+            .as_ref()
+            .and_then(|row| row.level.clone());
+        assert_eq!(level.as_deref(), Some("ERROR"));
+        assert_eq!(
+            source.metadata().columns,
+            Some(vec!["level".into(), "message".into()])
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reads_a_w3c_log_again_when_its_fields_change() {
+        let path = log_file(
+            "w3c.log",
+            "#Fields: date time sc-status\n2024-05-03 14:02:10 200\n",
+        );
+        let mut source = source_of(&path);
+        source.poll_new_lines();
+
+        append(&path, "2024-05-03 14:02:11 500\n");
+        assert!(matches!(source.poll_new_lines(), NewLines::Appended(logs) if logs.len() == 1));
+
+        append(
+            &path,
+            "#Fields: date time cs-uri-stem sc-status\n2024-05-03 14:02:12 / 200\n",
+        );
+        assert!(matches!(source.poll_new_lines(), NewLines::Reloaded(logs) if logs.len() == 3));
+        assert_eq!(
+            source.metadata().columns.map(|columns| columns.len()),
+            Some(4)
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
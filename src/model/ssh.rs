@@ -0,0 +1,164 @@
+use color_eyre::Result;
+use std::{mem, process::Command};
+
+use crate::{LineStream, Resume, decode_line};
+
+/// How many lines of history to load before following new ones.
+const SSH_HISTORY: &str = "10000";
+
+/// A log file on a remote host, written as `ssh://[user@]host[:port]:/path`.
+#[derive(Debug, Clone)]
+pub(crate) struct SshTarget {
+    host: String,
+    port: Option<String>,
+    path: String,
+}
+
+impl SshTarget {
+    /// Returns true if the argument should be opened over ssh.
+    pub(crate) fn is_ssh(arg: &str) -> bool {
+        arg.starts_with("ssh://")
+    }
+
+    pub(crate) fn parse(url: &str) -> Result<Self, &'static str> {
+        let usage = "ssh sources look like ssh://[user@]host[:port]:/path/to/log";
+        let rest = url.strip_prefix("ssh://").ok_or(usage)?;
+        let (host, path) = rest.split_once(":/").ok_or(usage)?;
+
+        let (host, port) = match host.rsplit_once(':') {
+            Some((host, port)) if port.parse::<u16>().is_ok() => (host, Some(port.to_string())),
+            _ => (host, None),
+        };
+
+        if host.is_empty() || path.is_empty() {
+            return Err(usage);
+        }
+
+        Ok(SshTarget {
+            host: host.to_string(),
+            port,
+            path: format!("/{path}"),
+        })
+    }
+
+    pub(crate) fn name(&self) -> String {
+        format!("{}:{}", self.host, self.path)
+    }
+
+    /// Builds the ssh command. The remote shell prints the byte offset it
+    /// reads the file from, then follows it from there: from the start of
+    /// the lines of history the first time, and from `offset` on a reconnect
+    /// so no line is lost or read twice.
+    fn command(&self, offset: Option<u64>) -> Command {
+        let mut command = Command::new("ssh");
+        // There's no terminal to type a password into, so fail instead of
+        // prompting, and notice dead connections so we can reconnect.
+        command.args(["-o", "BatchMode=yes", "-o", "ServerAliveInterval=15"]);
+        if let Some(port) = &self.port {
+            command.args(["-p", port]);
+        }
+
+        let start = match offset {
+            // A file that shrank in the meantime was rotated or truncated,
+            // and is read from the start.
+            Some(offset) => format!("n={offset}; [ \"$s\" -ge \"$n\" ] || n=0"),
+            None => format!("n=$((s - $(head -c \"$s\" \"$f\" | tail -n {SSH_HISTORY} | wc -c)))"),
+        };
+        // It's run by sh since the login shell may not take this syntax.
+        let script = format!(
+            "f={}; s=$(wc -c < \"$f\") || s=0; {start}; echo \"$n\"; \
+             exec tail -c +$((n + 1)) -F \"$f\"",
+            shell_quote(&self.path)
+        );
+        command.arg(&self.host);
+        command.arg(format!("sh -c {}", shell_quote(&script)));
+        command
+    }
+}
+
+/// Follows the remote file with `tail -F`, reconnecting when the connection
+/// drops.
+pub(crate) fn ssh_stream(target: SshTarget) -> Result<LineStream> {
+    LineStream::spawn_resuming(SshResume {
+        target,
+        offset: None,
+        starting: false,
+    })
+}
+
+/// Keeps count of the bytes of the remote file read, to reconnect from.
+struct SshResume {
+    target: SshTarget,
+    /// Where the next line starts in the file, once a run has said where it
+    /// started reading.
+    offset: Option<u64>,
+    /// Whether the next line is the offset a run starts with.
+    starting: bool,
+}
+
+impl Resume for SshResume {
+    fn command(&mut self) -> Command {
+        self.starting = true;
+        self.target.command(self.offset)
+    }
+
+    fn take(&mut self, raw: &[u8]) -> Option<String> {
+        if mem::take(&mut self.starting)
+            && let Some(offset) = decode_line(raw).parse().ok()
+        {
+            self.offset = Some(offset);
+            return None;
+        }
+        // A line cut short by the connection dropping is read again whole.
+        if !raw.ends_with(b"\n") {
+            return None;
+        }
+        if let Some(offset) = &mut self.offset {
+            *offset += raw.len() as u64;
+        }
+        Some(decode_line(raw))
+    }
+}
+
+/// Quotes the path for the remote shell.
+fn shell_quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resume() -> SshResume {
+        SshResume {
+            target: SshTarget::parse("ssh://web-1:/var/log/app.log").unwrap(),
+            offset: None,
+            starting: false,
+        }
+    }
+
+    #[test]
+    fn counts_bytes_from_the_offset_a_run_starts_at() {
+        let mut resume = resume();
+        resume.command();
+        assert_eq!(resume.take(b"120\n"), None);
+        assert_eq!(resume.take(b"first\n").as_deref(), Some("first"));
+        assert_eq!(resume.take(b"120\n").as_deref(), Some("120"));
+        assert_eq!(resume.offset, Some(130));
+    }
+
+    #[test]
+    fn reads_a_line_cut_short_again() {
+        let mut resume = resume();
+        resume.command();
+        resume.take(b"0\n");
+        resume.take(b"whole\r\n");
+        assert_eq!(resume.take(b"cut sh"), None);
+        assert_eq!(resume.offset, Some(7));
+
+        resume.command();
+        assert_eq!(resume.take(b"7\n"), None);
+        assert_eq!(resume.take(b"cut short\n").as_deref(), Some("cut short"));
+        assert_eq!(resume.offset, Some(17));
+    }
+}
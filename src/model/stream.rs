@@ -0,0 +1,235 @@
+use color_eyre::Result;
+use std::{
+    io::{BufRead, BufReader, Read},
+    mem,
+    process::{Child, ChildStdout, Command, Stdio},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+/// How long to wait before restarting a command that exited.
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// Status shown with the logs while a restarting stream is without its
+/// command.
+const RECONNECT_STATUS: &str = "log stream ended, reconnecting";
+
+/// Lines collected on a background thread from a source that keeps producing
+/// them, like a subprocess following a log.
+#[derive(Debug)]
+pub(crate) struct LineStream {
+    pending: Arc<Mutex<Vec<String>>>,
+    // Shared with the reconnect thread, which swaps in a new child each time
+    // it restarts the command.
+    child: Arc<Mutex<Option<Child>>>,
+    stopped: Arc<AtomicBool>,
+    /// What the source is busy with, like a download's progress.
+    status: Arc<Mutex<Option<String>>>,
+    /// Clears what the source holds, for sources that keep a buffer of their
+    /// own, like a device's logcat.
+    clear: Option<Command>,
+}
+
+impl LineStream {
+    fn new(child: Option<Child>) -> Self {
+        LineStream {
+            pending: Arc::new(Mutex::new(vec![])),
+            child: Arc::new(Mutex::new(child)),
+            stopped: Arc::new(AtomicBool::new(false)),
+            status: Arc::new(Mutex::new(None)),
+            clear: None,
+        }
+    }
+
+    /// Runs `command` whenever the stream is cleared.
+    pub(crate) fn clearing_with(mut self, command: Command) -> Self {
+        self.clear = Some(command);
+        self
+    }
+
+    /// Runs the command and streams its stdout, passing each line through
+    /// `format` and dropping the ones it returns `None` for.
+    pub(crate) fn spawn(command: Command, format: fn(&str) -> Option<String>) -> Result<Self> {
+        let (child, stdout) = spawn_piped(command)?;
+        let stream = LineStream::new(Some(child));
+        read_lines(stdout, Arc::clone(&stream.pending), format);
+        Ok(stream)
+    }
+
+    /// Like `spawn`, but runs the command again whenever it exits, for sources
+    /// that drop the connection like a restarted pod or a flaky network.
+    ///
+    /// `resume` builds each run's command from what was taken so far, so it
+    /// can pick up where the last run left off instead of replaying the
+    /// history.
+    pub(crate) fn spawn_resuming(mut resume: impl Resume) -> Result<Self> {
+        // Start the first run here so a missing program is reported up front.
+        let (child, stdout) = spawn_piped(resume.command())?;
+        let stream = LineStream::new(Some(child));
+
+        let pending = Arc::clone(&stream.pending);
+        let child = Arc::clone(&stream.child);
+        let stopped = Arc::clone(&stream.stopped);
+        let status = Arc::clone(&stream.status);
+
+        thread::spawn(move || {
+            let mut stdout = Some(stdout);
+
+            while !stopped.load(Ordering::Relaxed) {
+                if let Some(stdout) = stdout.take() {
+                    let mut reader = BufReader::new(stdout);
+                    let mut raw = vec![];
+                    while matches!(reader.read_until(b'\n', &mut raw), Ok(read) if read > 0) {
+                        if let Some(line) = resume.take(&raw) {
+                            pending.lock().unwrap().push(line);
+                        }
+                        raw.clear();
+                    }
+
+                    // Take the child out before waiting so dropping the
+                    // stream never blocks on the lock.
+                    let finished = child.lock().unwrap().take();
+                    if let Some(mut finished) = finished {
+                        let _ = finished.wait();
+                    }
+                    if stopped.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    // Kept out of the log, where it would pass for a line the
+                    // source wrote.
+                    *status.lock().unwrap() = Some(RECONNECT_STATUS.to_string());
+                }
+
+                thread::sleep(RECONNECT_DELAY);
+                if let Ok((new_child, new_stdout)) = spawn_piped(resume.command()) {
+                    *child.lock().unwrap() = Some(new_child);
+                    stdout = Some(new_stdout);
+                    *status.lock().unwrap() = None;
+                }
+            }
+        });
+
+        Ok(stream)
+    }
+
+    /// Runs `produce` on a background thread, handing it a sink to push lines
+    /// into. For sources that aren't a subprocess, like a socket or a URL.
+    pub(crate) fn spawn_thread(produce: impl FnOnce(LineSink) + Send + 'static) -> Self {
+        let stream = LineStream::new(None);
+        let sink = LineSink {
+            pending: Arc::clone(&stream.pending),
+            stopped: Arc::clone(&stream.stopped),
+            status: Arc::clone(&stream.status),
+        };
+        thread::spawn(move || produce(sink));
+        stream
+    }
+
+    /// Returns true if there are lines waiting to be taken.
+    pub(crate) fn has_lines(&self) -> bool {
+        !self.pending.lock().unwrap().is_empty()
+    }
+
+    /// Takes every line received since the last call.
+    pub(crate) fn take_lines(&self) -> Vec<String> {
+        mem::take(&mut *self.pending.lock().unwrap())
+    }
+
+    pub(crate) fn status(&self) -> Option<String> {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Drops the lines not taken yet, and clears the source if it can be.
+    pub(crate) fn clear(&mut self) {
+        self.pending.lock().unwrap().clear();
+        if let Some(command) = &mut self.clear {
+            // Nothing to do about a device that's gone, the lines are
+            // cleared from the tab either way.
+            let _ = command.status();
+        }
+    }
+}
+
+/// How a stream that runs its command again whenever it exits picks up where
+/// the last run left off, instead of replaying the history.
+pub(crate) trait Resume: Send + 'static {
+    /// Builds the command for the next run, from what was taken so far.
+    fn command(&mut self) -> Command;
+
+    /// Takes a line the command printed, with its newline unless the run
+    /// ended partway through it, and returns what to add to the log.
+    fn take(&mut self, raw: &[u8]) -> Option<String>;
+}
+
+/// The text of a line read as bytes, without its line ending.
+pub(crate) fn decode_line(raw: &[u8]) -> String {
+    let text = String::from_utf8_lossy(raw);
+    text.trim_end_matches(['\n', '\r']).to_string()
+}
+
+/// Handle a producer thread uses to add lines to its stream.
+#[derive(Debug, Clone)]
+pub(crate) struct LineSink {
+    pending: Arc<Mutex<Vec<String>>>,
+    stopped: Arc<AtomicBool>,
+    status: Arc<Mutex<Option<String>>>,
+}
+
+impl LineSink {
+    pub(crate) fn push(&self, line: String) {
+        self.pending.lock().unwrap().push(line);
+    }
+
+    /// Sets the status shown with the logs, or clears it with `None`.
+    pub(crate) fn set_status(&self, status: Option<String>) {
+        *self.status.lock().unwrap() = status;
+    }
+
+    /// Returns true once the stream has been dropped and the producer should
+    /// return.
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+}
+
+/// Spawns the command with its stdout piped back to us.
+fn spawn_piped(mut command: Command) -> Result<(Child, ChildStdout)> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        // Anything written to stderr would draw over the TUI.
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    Ok((child, stdout))
+}
+
+/// Reads lines on a background thread until the reader closes.
+fn read_lines(
+    reader: impl Read + Send + 'static,
+    pending: Arc<Mutex<Vec<String>>>,
+    format: fn(&str) -> Option<String>,
+) {
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(|line| line.ok()) {
+            if let Some(line) = format(&line) {
+                pending.lock().unwrap().push(line);
+            }
+        }
+    });
+}
+
+impl Drop for LineStream {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(child) = self.child.lock().unwrap().as_mut() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use encoding_rs::Encoding;
+
+use crate::{FieldExtractor, LevelAliases, LevelRule, LineFormat, LogEntry, LogFormat};
+
+/// A line from a structured format like JSON, split into the parts the viewer
+/// cares about.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct StructuredLine {
+    pub(crate) timestamp: Option<String>,
+    /// One of the level names the filters look for when it could be mapped,
+    /// otherwise the level as written.
+    pub(crate) level: Option<String>,
+    pub(crate) message: Option<String>,
+    /// Everything else, in the order it appeared in the line.
+    pub(crate) fields: Vec<(String, String)>,
+}
+
+impl StructuredLine {
+    /// Renders the line for the list, leading with the timestamp and level so
+    /// it reads like a plain log line.
+    pub(crate) fn display(&self) -> String {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(key, value)| format!("{key}={}", quote_value(value)));
+
+        [&self.timestamp, &self.level, &self.message]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .chain(fields)
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// Lists every part of the line on its own row, for the preview.
+    pub(crate) fn rows(&self) -> Vec<(String, String)> {
+        [
+            ("timestamp", &self.timestamp),
+            ("level", &self.level),
+            ("message", &self.message),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value.clone()?)))
+        .chain(self.fields.iter().cloned())
+        .collect()
+    }
+}
+
+/// How logs are read and their lines split into parts, set from the command
+/// line.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ParseOptions {
+    /// The encoding of log files, guessed from their contents when unset.
+    pub(crate) encoding: Option<&'static Encoding>,
+    /// The column giving the level of each row in CSV and TSV files.
+    pub(crate) level_column: Option<String>,
+    /// A format given by the user, tried before the built in ones.
+    pub(crate) line_format: Option<LineFormat>,
+    /// The format lines are read as. Every structured format is tried on
+    /// each line when it isn't set.
+    pub(crate) format: Option<LogFormat>,
+    /// Where to find the level of lines that aren't in a structured format.
+    pub(crate) level_rule: LevelRule,
+    /// Extra names for the levels, from the config file.
+    pub(crate) level_aliases: LevelAliases,
+    /// Regexes pulling fields out of the lines they match.
+    pub(crate) extractors: Vec<FieldExtractor>,
+    /// Keep one in this many of the lines streamed in, to start with.
+    pub(crate) sample_rate: Option<usize>,
+}
+
+impl ParseOptions {
+    /// Parses an entry that came in as plain text, leaving it as is if it
+    /// isn't in any format we know, and works out its level.
+    pub(crate) fn apply(&self, entry: LogEntry) -> LogEntry {
+        let mut entry = self.parse(entry);
+        entry.level = self.level_rule.level_of(&entry, &self.level_aliases);
+        entry.extracted.clear();
+        for extractor in &self.extractors {
+            extractor.extract(&entry.line, &mut entry.extracted);
+        }
+        entry
+    }
+
+    fn parse(&self, entry: LogEntry) -> LogEntry {
+        if entry.structured.is_some() {
+            return entry;
+        }
+
+        let structured = self
+            .line_format
+            .as_ref()
+            .and_then(|format| format.parse_line(&entry.line))
+            .or_else(|| match self.format {
+                Some(format) => format.parse(&entry.line),
+                None => LogFormat::STRUCTURED
+                    .into_iter()
+                    .find_map(|format| format.parse(&entry.line)),
+            });
+        match structured {
+            Some(structured) => LogEntry {
+                original: Some(entry.line),
+                ..LogEntry::from_structured(structured, entry.source)
+            },
+            None => entry,
+        }
+    }
+}
+
+/// Keys commonly used for each part of a structured line.
+pub(crate) const TIMESTAMP_KEYS: [&str; 6] =
+    ["timestamp", "time", "ts", "@timestamp", "datetime", "date"];
+pub(crate) const LEVEL_KEYS: [&str; 6] = [
+    "level",
+    "lvl",
+    "severity",
+    "loglevel",
+    "levelname",
+    "log.level",
+];
+pub(crate) const MESSAGE_KEYS: [&str; 5] = ["message", "msg", "@message", "text", "event"];
+
+/// Maps the level names used by common logging libraries onto the ones the
+/// filters look for.
+pub(crate) fn normalize_level(level: &str) -> Option<&'static str> {
+    match level.to_ascii_lowercase().as_str() {
+        "trace" | "debug" | "dbug" | "verbose" | "finer" | "finest" | "fine" => Some("DEBUG"),
+        "info" | "information" | "informational" | "notice" => Some("INFO"),
+        "warn" | "warning" => Some("WARNING"),
+        "error" | "err" | "eror" => Some("ERROR"),
+        "critical" | "crit" | "fatal" | "panic" | "alert" | "emerg" | "emergency" => {
+            Some("CRITICAL")
+        }
+        _ => None,
+    }
+}
+
+/// Quotes values that would otherwise run into the next field.
+fn quote_value(value: &str) -> String {
+    if value.is_empty() || value.contains(char::is_whitespace) {
+        format!("{value:?}")
+    } else {
+        value.to_string()
+    }
+}
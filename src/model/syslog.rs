@@ -0,0 +1,374 @@
+use chrono::Local;
+use color_eyre::Result;
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    net::{TcpListener, TcpStream, UdpSocket},
+};
+
+use crate::{LineSink, LineStream, SOCKET_POLL, StructuredLine, accept_loop};
+
+/// Largest syslog datagram we accept.
+const MAX_DATAGRAM: usize = 64 * 1024;
+
+/// Transport a listener accepts messages on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Transport {
+    Udp,
+    Tcp,
+}
+
+/// Parses a listen address like `udp://0.0.0.0:5514`, defaulting to udp when
+/// there's no scheme.
+pub(crate) fn parse_listen_addr(addr: &str) -> Result<(Transport, String), &'static str> {
+    let (transport, addr) = match addr.split_once("://") {
+        Some(("udp", addr)) => (Transport::Udp, addr),
+        Some(("tcp", addr)) => (Transport::Tcp, addr),
+        Some(_) => return Err("Listen addresses must use udp:// or tcp://."),
+        None => (Transport::Udp, addr),
+    };
+
+    if addr.is_empty() {
+        return Err("Listen addresses need a host and port.");
+    }
+    Ok((transport, addr.to_string()))
+}
+
+/// Maps a syslog severity onto the level names used by the filters.
+pub(crate) fn severity_level(severity: u8) -> &'static str {
+    match severity {
+        0..=2 => "CRITICAL",
+        3 => "ERROR",
+        4 => "WARNING",
+        5 | 6 => "INFO",
+        _ => "DEBUG",
+    }
+}
+
+/// Listens for syslog messages and streams them in as they arrive.
+///
+/// The socket is bound here so a port that's in use fails before the TUI
+/// starts.
+pub(crate) fn syslog_stream(transport: &Transport, addr: &str) -> Result<LineStream> {
+    match transport {
+        Transport::Udp => {
+            let socket = UdpSocket::bind(addr)?;
+            socket.set_read_timeout(Some(SOCKET_POLL))?;
+            Ok(LineStream::spawn_thread(move |sink| {
+                receive_udp(socket, sink)
+            }))
+        }
+        Transport::Tcp => {
+            let listener = TcpListener::bind(addr)?;
+            listener.set_nonblocking(true)?;
+            Ok(LineStream::spawn_thread(move |sink| {
+                accept_loop(listener, sink, receive_tcp)
+            }))
+        }
+    }
+}
+
+fn receive_udp(socket: UdpSocket, sink: LineSink) {
+    let mut buf = vec![0; MAX_DATAGRAM];
+    while !sink.is_stopped() {
+        if let Ok((len, _)) = socket.recv_from(&mut buf) {
+            sink.push(format_message(&String::from_utf8_lossy(&buf[..len])));
+        }
+    }
+}
+
+/// Reads messages off a tcp connection, which frames them either with a
+/// trailing newline or with a leading byte count (RFC 6587).
+fn receive_tcp(stream: TcpStream, sink: LineSink) {
+    let mut reader = BufReader::new(stream);
+
+    while !sink.is_stopped() {
+        match read_frame(&mut reader) {
+            Ok(Some(frame)) => sink.push(format_message(&frame)),
+            _ => return,
+        }
+    }
+}
+
+fn read_frame(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let octet_counted = reader.fill_buf()?.first().is_some_and(u8::is_ascii_digit);
+
+    let mut frame = vec![];
+    if octet_counted {
+        reader.read_until(b' ', &mut frame)?;
+        let len: u64 = String::from_utf8_lossy(&frame)
+            .trim()
+            .parse()
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+        frame.clear();
+        reader.take(len).read_to_end(&mut frame)?;
+    } else {
+        reader.read_until(b'\n', &mut frame)?;
+    }
+
+    if frame.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&frame).into_owned()))
+}
+
+/// Turns a raw syslog message into a log line. Messages with a `<PRI>` header
+/// are kept as they are so the parser can read their severity and facility,
+/// anything else is stamped with the time we got it.
+fn format_message(message: &str) -> String {
+    let message = message.trim_end_matches(['\n', '\r', '\0']);
+    if split_priority(message).is_some() {
+        return message.to_string();
+    }
+    let received = Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z");
+    format!("{received} {message}")
+}
+
+/// Parses a syslog line, either with a `<PRI>` header in the RFC 5424 or
+/// RFC 3164 format, or as written to `/var/log/syslog` without one:
+///
+/// ```text
+/// <34>1 2003-10-11T22:14:15.003Z host su - ID47 - 'su root' failed
+/// <34>Oct 11 22:14:15 host su[12]: 'su root' failed
+/// Oct 11 22:14:15 host su[12]: 'su root' failed
+/// ```
+pub(crate) fn parse_syslog_line(line: &str) -> Option<StructuredLine> {
+    let (priority, rest) = match split_priority(line) {
+        Some((priority, rest)) => (Some(priority), rest),
+        None => (None, line),
+    };
+
+    let mut structured = match rest.strip_prefix("1 ") {
+        Some(rest) if priority.is_some() => parse_rfc5424(rest)?,
+        _ => parse_rfc3164(rest, priority.is_some())?,
+    };
+
+    if let Some(priority) = priority {
+        structured.level = Some(severity_level(priority % 8).to_string());
+        let facility = FACILITIES
+            .get(usize::from(priority / 8))
+            .map_or((priority / 8).to_string(), |name| name.to_string());
+        structured
+            .fields
+            .insert(0, ("facility".to_string(), facility));
+    }
+    Some(structured)
+}
+
+/// Facility names, indexed by facility code.
+const FACILITIES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+fn split_priority(message: &str) -> Option<(u8, &str)> {
+    let (priority, rest) = message.strip_prefix('<')?.split_once('>')?;
+    Some((priority.parse().ok().filter(|pri| *pri < 192)?, rest))
+}
+
+/// `TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA MSG`, with `-`
+/// for the values that aren't set.
+fn parse_rfc5424(rest: &str) -> Option<StructuredLine> {
+    let mut parts = rest.splitn(6, ' ');
+    let timestamp = parts.next()?;
+    let header = [
+        ("host", parts.next()?),
+        ("app", parts.next()?),
+        ("pid", parts.next()?),
+        ("msgid", parts.next()?),
+    ];
+    let (data, message) = split_structured_data(parts.next().unwrap_or("-"));
+
+    let fields = header
+        .into_iter()
+        .chain([("data", data)])
+        .filter(|(_, value)| *value != "-" && !value.is_empty())
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    Some(StructuredLine {
+        timestamp: (timestamp != "-").then(|| timestamp.to_string()),
+        message: Some(message.trim_start_matches('\u{feff}').to_string()),
+        fields,
+        ..StructuredLine::default()
+    })
+}
+
+/// Splits the structured data elements, `[id key="value"]...` or `-`, from
+/// the message after them.
+fn split_structured_data(text: &str) -> (&str, &str) {
+    if !text.starts_with('[') {
+        let (data, message) = text.split_once(' ').unwrap_or((text, ""));
+        return (data, message);
+    }
+
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (idx, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            ']' if !in_quotes && !text[idx + 1..].starts_with('[') => {
+                return (&text[..=idx], text[idx + 1..].trim_start());
+            }
+            _ => {}
+        }
+    }
+    (text, "")
+}
+
+/// `Mmm dd hh:mm:ss HOSTNAME TAG[PID]: MSG`. Lines without a `<PRI>` header
+/// have to have all of it to count as syslog, the header only needs a
+/// message.
+fn parse_rfc3164(rest: &str, has_priority: bool) -> Option<StructuredLine> {
+    let mut tokens = rest.split_whitespace();
+    let (month, day, time) = (tokens.next(), tokens.next(), tokens.next());
+    let timestamp = match (month, day, time) {
+        (Some(month), Some(day), Some(time))
+            if month.len() == 3 && day.len() <= 2 && time.matches(':').count() == 2 =>
+        {
+            Some(format!("{month} {day} {time}"))
+        }
+        _ if has_priority => None,
+        _ => return None,
+    };
+
+    // Skip past the timestamp without losing the spacing of the message.
+    let body = match &timestamp {
+        Some(_) => {
+            let time = time?;
+            rest[rest.find(time)? + time.len()..].trim_start()
+        }
+        None => rest,
+    };
+
+    let (host, tagged) = match timestamp {
+        Some(_) => body.split_once(' ')?,
+        None => ("", body),
+    };
+    let Some((tag, message)) = tagged
+        .split_once(": ")
+        .filter(|(tag, _)| !tag.contains(' '))
+    else {
+        if !has_priority {
+            return None;
+        }
+        return Some(StructuredLine {
+            timestamp,
+            message: Some(body.to_string()),
+            ..StructuredLine::default()
+        });
+    };
+
+    let (app, pid) = match tag.strip_suffix(']').and_then(|tag| tag.split_once('[')) {
+        Some((app, pid)) => (app, Some(pid)),
+        None => (tag, None),
+    };
+    let fields = [("host", Some(host)), ("app", Some(app)), ("pid", pid)]
+        .into_iter()
+        .filter_map(|(key, value)| {
+            Some((
+                key.to_string(),
+                value.filter(|v| !v.is_empty())?.to_string(),
+            ))
+        })
+        .collect();
+
+    Some(StructuredLine {
+        timestamp,
+        message: Some(message.to_string()),
+        fields,
+        ..StructuredLine::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(line: &StructuredLine) -> Vec<(&str, &str)> {
+        line.fields
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_rfc5424_messages() {
+        let line = parse_syslog_line(concat!(
+            r#"<165>1 2003-10-11T22:14:15.003Z mymachine evntslog - ID47 "#,
+            r#"[exampleSDID@32473 iut="3" eventSource="App]"] An application event"#,
+        ))
+        .unwrap();
+        assert_eq!(line.timestamp.as_deref(), Some("2003-10-11T22:14:15.003Z"));
+        assert_eq!(line.level.as_deref(), Some("INFO"));
+        assert_eq!(line.message.as_deref(), Some("An application event"));
+        assert_eq!(
+            fields(&line),
+            [
+                ("facility", "local4"),
+                ("host", "mymachine"),
+                ("app", "evntslog"),
+                ("msgid", "ID47"),
+                ("data", r#"[exampleSDID@32473 iut="3" eventSource="App]"]"#),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_rfc3164_messages() {
+        let line =
+            parse_syslog_line("<34>Oct 11 22:14:15 mymachine su[12]: 'su root' failed").unwrap();
+        assert_eq!(line.timestamp.as_deref(), Some("Oct 11 22:14:15"));
+        assert_eq!(line.level.as_deref(), Some("CRITICAL"));
+        assert_eq!(line.message.as_deref(), Some("'su root' failed"));
+        assert_eq!(
+            fields(&line),
+            [
+                ("facility", "auth"),
+                ("host", "mymachine"),
+                ("app", "su"),
+                ("pid", "12")
+            ]
+        );
+
+        // Without a header, the whole of it has to be there.
+        let line = parse_syslog_line("Oct  3 09:00:01 host CRON[881]: (root) CMD (run-parts)");
+        assert_eq!(line.unwrap().level, None);
+        assert!(parse_syslog_line("Oct 3 09:00:01 starting up").is_none());
+        assert!(parse_syslog_line("<13>starting up").is_some());
+    }
+
+    #[test]
+    fn maps_severities_onto_levels() {
+        let levels: Vec<&str> = (0..8).map(severity_level).collect();
+        assert_eq!(
+            levels,
+            [
+                "CRITICAL", "CRITICAL", "CRITICAL", "ERROR", "WARNING", "INFO", "INFO", "DEBUG"
+            ]
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{LogEntry, Matcher, Model};
+
+/// Most lines listed from each tab, so searching every tab for something
+/// common stays quick to go through.
+const MAX_TAB_MATCHES: usize = 500;
+
+/// A line found by searching every tab.
+#[derive(Debug)]
+pub(crate) struct TabMatch {
+    pub(crate) tab: usize,
+    pub(crate) entry: LogEntry,
+}
+
+/// A search run across every open tab, with the lines it found listed by
+/// tab.
+#[derive(Debug)]
+pub(crate) struct TabSearch {
+    pub(crate) search: String,
+    /// In the order of the tabs, and then of the lines in each.
+    pub(crate) matches: Vec<TabMatch>,
+    /// How many lines each tab matched, including the ones not listed.
+    pub(crate) counts: Vec<usize>,
+    pub(crate) selected: usize,
+}
+
+impl TabSearch {
+    /// Searches the lines each tab shows through its filters.
+    pub(crate) fn run(search: &str, matcher: &Matcher, tabs: &mut [Model]) -> TabSearch {
+        let mut matches = vec![];
+        let mut counts = vec![];
+        for (tab, model) in tabs.iter_mut().enumerate() {
+            let found = model.matching_lines(matcher);
+            counts.push(found.len());
+            matches.extend(
+                found
+                    .into_iter()
+                    .take(MAX_TAB_MATCHES)
+                    .map(|entry| TabMatch { tab, entry }),
+            );
+        }
+        TabSearch {
+            search: search.to_string(),
+            matches,
+            counts,
+            selected: 0,
+        }
+    }
+
+    pub(crate) fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub(crate) fn move_down(&mut self) {
+        self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1));
+    }
+
+    pub(crate) fn move_top(&mut self) {
+        self.selected = 0;
+    }
+
+    pub(crate) fn move_bottom(&mut self) {
+        self.selected = self.matches.len().saturating_sub(1);
+    }
+
+    /// How many of the lines the tab matched are listed.
+    pub(crate) fn listed(&self, tab: usize) -> usize {
+        self.counts[tab].min(MAX_TAB_MATCHES)
+    }
+
+    pub(crate) fn selected(&self) -> Option<&TabMatch> {
+        self.matches.get(self.selected)
+    }
+}
@@ -0,0 +1,216 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+
+/// An interval of time lines have to be logged in to be shown, written as
+/// `:from 14:02 :to 14:07`. Either end can be left out.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TimeRange {
+    from: Option<NaiveDateTime>,
+    /// The first moment past the range, so `:to 14:07` takes in all of that
+    /// minute.
+    until: Option<NaiveDateTime>,
+    /// What was typed, to show it and to edit it again.
+    text: String,
+}
+
+impl TimeRange {
+    /// Parses a range. Times without a date, like `14:02` or `14:02:30`, are
+    /// taken to be on the given date, the one the log starts on.
+    pub(crate) fn parse(text: &str, date: Option<NaiveDate>) -> Result<TimeRange, &'static str> {
+        let mut from = None;
+        let mut until = None;
+        let mut words = text.split_whitespace().peekable();
+
+        while let Some(keyword) = words.next() {
+            // The time runs until the next keyword, since it can hold a space
+            // between the date and the time.
+            let mut time = vec![];
+            while let Some(word) = words.next_if(|word| !word.starts_with(':')) {
+                time.push(word);
+            }
+            let (start, length) = parse_time(&time.join(" "), date)?;
+            match keyword {
+                ":from" => from = Some(start),
+                ":to" => until = Some(start + length),
+                _ => return Err("expected :from and :to"),
+            }
+        }
+
+        if from.is_none() && until.is_none() {
+            return Err("expected :from and :to");
+        }
+        if let (Some(from), Some(until)) = (from, until)
+            && until <= from
+        {
+            return Err(":to is before :from");
+        }
+        Ok(TimeRange {
+            from,
+            until,
+            text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+        })
+    }
+
+    /// Parses the window a search is kept to, like `14:00-14:10` in
+    /// `timeout @14:00-14:10`. Either end can be left out, like `14:00-`,
+    /// and a single time, like `14:05`, stands for all of that minute.
+    pub(crate) fn parse_window(
+        text: &str,
+        date: Option<NaiveDate>,
+    ) -> Result<TimeRange, &'static str> {
+        let parse = |time: &str| match time.is_empty() {
+            true => Ok(None),
+            false => parse_time(time, date).map(Some),
+        };
+        // Dates hold dashes too, so each one is tried as the one between the
+        // ends.
+        for (idx, _) in text.match_indices('-') {
+            let (Ok(from), Ok(to)) = (parse(&text[..idx]), parse(&text[idx + 1..])) else {
+                continue;
+            };
+            let from = from.map(|(start, _)| start);
+            let until = to.map(|(start, length)| start + length);
+            if from.is_none() && until.is_none() {
+                return Err("expected a time");
+            }
+            if let (Some(from), Some(until)) = (from, until)
+                && until <= from
+            {
+                return Err("the window ends before it starts");
+            }
+            return Ok(TimeRange {
+                from,
+                until,
+                text: text.to_string(),
+            });
+        }
+        let (start, length) = parse_time(text, date)?;
+        Ok(TimeRange {
+            from: Some(start),
+            until: Some(start + length),
+            text: text.to_string(),
+        })
+    }
+
+    /// Parses a single moment, like `14:02` or `2024-05-03T14:02:10`, taking
+    /// it to be at the start of the span it names.
+    pub(crate) fn parse_moment(
+        text: &str,
+        date: Option<NaiveDate>,
+    ) -> Result<NaiveDateTime, &'static str> {
+        parse_time(text, date).map(|(start, _)| start)
+    }
+
+    pub(crate) fn contains(&self, timestamp: NaiveDateTime) -> bool {
+        self.from.is_none_or(|from| timestamp >= from)
+            && self.until.is_none_or(|until| timestamp < until)
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+/// Parses a time and how long a span it names, which depends on whether the
+/// seconds are written.
+fn parse_time(
+    text: &str,
+    date: Option<NaiveDate>,
+) -> Result<(NaiveDateTime, TimeDelta), &'static str> {
+    let (date, time) = match text.split_once([' ', 'T']) {
+        Some((day, time)) => (
+            NaiveDate::parse_from_str(day, "%Y-%m-%d").map_err(|_| "invalid date")?,
+            time,
+        ),
+        None => (date.ok_or("no timestamps to go by, add a date")?, text),
+    };
+
+    let (time, length) = match NaiveTime::parse_from_str(time, "%H:%M:%S%.f") {
+        Ok(time) => (time, TimeDelta::seconds(1)),
+        Err(_) => (
+            NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| "invalid time")?,
+            TimeDelta::minutes(1),
+        ),
+    };
+    Ok((date.and_time(time), length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f").unwrap()
+    }
+
+    fn may_3() -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(2024, 5, 3)
+    }
+
+    #[test]
+    fn takes_in_all_of_the_last_minute() {
+        let range = TimeRange::parse(":from 14:02   :to 14:07", may_3()).unwrap();
+        assert_eq!(range.as_str(), ":from 14:02 :to 14:07");
+        assert!(!range.contains(at("2024-05-03 14:01:59.999")));
+        assert!(range.contains(at("2024-05-03 14:02:00")));
+        assert!(range.contains(at("2024-05-03 14:07:59.999")));
+        assert!(!range.contains(at("2024-05-03 14:08:00")));
+        assert!(!range.contains(at("2024-05-04 14:03:00")));
+    }
+
+    #[test]
+    fn leaves_either_end_open() {
+        let range = TimeRange::parse(":to 2024-05-02 23:59:30", None).unwrap();
+        assert!(range.contains(at("2020-01-01 00:00:00")));
+        assert!(range.contains(at("2024-05-02 23:59:30.5")));
+        assert!(!range.contains(at("2024-05-02 23:59:31")));
+
+        let range = TimeRange::parse(":from 2024-05-03T14:02:10", None).unwrap();
+        assert!(range.contains(at("2030-01-01 00:00:00")));
+    }
+
+    #[test]
+    fn explains_what_it_cant_read() {
+        assert_eq!(
+            TimeRange::parse(":from 14:07 :to 14:02", may_3()),
+            Err(":to is before :from")
+        );
+        assert_eq!(
+            TimeRange::parse(":from 14:02", None),
+            Err("no timestamps to go by, add a date")
+        );
+        assert_eq!(TimeRange::parse(":from 2pm", may_3()), Err("invalid time"));
+        assert_eq!(
+            TimeRange::parse(":since 14:02", may_3()),
+            Err("expected :from and :to")
+        );
+        assert_eq!(TimeRange::parse("", may_3()), Err("expected :from and :to"));
+    }
+
+    #[test]
+    fn reads_the_window_of_a_search() {
+        let window = TimeRange::parse_window("14:00-14:10", may_3()).unwrap();
+        assert!(window.contains(at("2024-05-03 14:10:59")));
+        assert!(!window.contains(at("2024-05-03 14:11:00")));
+
+        let minute = TimeRange::parse_window("14:05", may_3()).unwrap();
+        assert!(minute.contains(at("2024-05-03 14:05:30")));
+        assert!(!minute.contains(at("2024-05-03 14:06:00")));
+
+        let open = TimeRange::parse_window("-14:00", may_3()).unwrap();
+        assert!(open.contains(at("2024-05-03 09:00:00")));
+
+        // The dashes of a date aren't taken for the one between the ends.
+        let dated = TimeRange::parse_window("2024-05-02T23:00-2024-05-03T01:00", None).unwrap();
+        assert!(dated.contains(at("2024-05-03 00:30:00")));
+        assert!(!dated.contains(at("2024-05-03 01:01:00")));
+
+        assert_eq!(
+            TimeRange::parse_window("14:10-14:00", may_3()),
+            Err("the window ends before it starts")
+        );
+        assert_eq!(
+            TimeRange::parse_window("-", may_3()),
+            Err("expected a time")
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use chrono::{DateTime, Datelike, Local, NaiveDateTime};
+
+use crate::LogEntry;
+
+/// Formats that fit in a single whitespace separated token.
+const SINGLE_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S"];
+
+/// Formats where the date and the time are separated by a space.
+const PAIR_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y/%m/%d %H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S",
+];
+
+/// The ways a log line can start with, or hold, its timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TimestampFormat {
+    /// RFC3339 and the ISO 8601 variants loggers write, with or without a
+    /// `T` between the date and the time.
+    Iso,
+    /// `May  3 14:02:10`, as written by syslog. The year is taken to be the
+    /// current one.
+    Syslog,
+    /// Seconds since the epoch, optionally with a fraction.
+    EpochSeconds,
+    /// Milliseconds since the epoch.
+    EpochMillis,
+    /// `[10/Oct/2000:13:55:36 -0700]`, as written to apache and nginx access
+    /// logs after the client's address.
+    Clf,
+}
+
+impl TimestampFormat {
+    const ALL: [TimestampFormat; 5] = [
+        TimestampFormat::Iso,
+        TimestampFormat::Clf,
+        TimestampFormat::Syslog,
+        TimestampFormat::EpochMillis,
+        TimestampFormat::EpochSeconds,
+    ];
+
+    /// Parses the timestamp in the text, if it's written in this format.
+    /// Timestamps with an offset are converted to UTC.
+    pub(crate) fn parse(self, text: &str) -> Option<NaiveDateTime> {
+        let text = text.trim_start();
+        match self {
+            TimestampFormat::Iso => parse_iso(text),
+            TimestampFormat::Syslog => parse_syslog(text),
+            TimestampFormat::EpochSeconds => {
+                let token = first_token(text);
+                let (secs, fraction) = token.split_once('.').unwrap_or((token, ""));
+                if secs.len() != 10 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                let nanos = format!("{fraction:0<9}").get(..9)?.parse().ok()?;
+                DateTime::from_timestamp(secs.parse().ok()?, nanos).map(|ts| ts.naive_utc())
+            }
+            TimestampFormat::EpochMillis => {
+                let token = first_token(text);
+                if token.len() != 13 {
+                    return None;
+                }
+                DateTime::from_timestamp_millis(token.parse().ok()?).map(|ts| ts.naive_utc())
+            }
+            TimestampFormat::Clf => {
+                // Parsed access log lines hand over the date without brackets.
+                let date = match text.find('[') {
+                    Some(start) => {
+                        let date = &text[start + 1..];
+                        &date[..date.find(']')?]
+                    }
+                    None => text,
+                };
+                DateTime::parse_from_str(date, "%d/%b/%Y:%H:%M:%S %z")
+                    .ok()
+                    .map(|ts| ts.naive_utc())
+            }
+        }
+    }
+}
+
+/// Parses the timestamp at the start of a log line, or in an access log's
+/// brackets, in whichever format it's written.
+fn detect_timestamp(text: &str) -> Option<(TimestampFormat, NaiveDateTime)> {
+    TimestampFormat::ALL
+        .into_iter()
+        .find_map(|format| Some((format, format.parse(text)?)))
+}
+
+/// Works out the timestamp format of each file once, then sticks with it so
+/// every line doesn't have to be tried against every format.
+#[derive(Debug, Default)]
+pub(crate) struct TimestampDetector {
+    /// Indexed by `LogEntry::source`.
+    formats: Vec<Option<TimestampFormat>>,
+}
+
+impl TimestampDetector {
+    /// Sets the entry's timestamp from its structured timestamp field, or
+    /// else from the start of the line.
+    pub(crate) fn stamp(&mut self, entry: &mut LogEntry) {
+        let text = match entry.structured.as_ref().and_then(|s| s.timestamp.as_ref()) {
+            Some(timestamp) => timestamp.as_str(),
+            None => entry.line.as_str(),
+        };
+
+        if self.formats.len() <= entry.source {
+            self.formats.resize(entry.source + 1, None);
+        }
+        let known = self.formats[entry.source];
+        if let Some(ts) = known.and_then(|format| format.parse(text)) {
+            entry.timestamp = Some(ts);
+            return;
+        }
+
+        // The format may change, like when a service logs in a different way
+        // after being updated, so look again when the known one fails.
+        if let Some((format, ts)) = detect_timestamp(text) {
+            self.formats[entry.source] = Some(format);
+            entry.timestamp = Some(ts);
+        }
+    }
+}
+
+/// The timestamp may be wrapped in brackets, and a comma is accepted before
+/// the fractional seconds since that's what python's logging module writes.
+fn parse_iso(text: &str) -> Option<NaiveDateTime> {
+    let text = text.trim_start_matches('[');
+    let mut tokens = text.split_whitespace();
+
+    let first = clean_token(tokens.next()?);
+    if let Ok(ts) = DateTime::parse_from_rfc3339(&first) {
+        return Some(ts.naive_utc());
+    }
+    if let Some(ts) = parse_with(&first, &SINGLE_FORMATS) {
+        return Some(ts);
+    }
+
+    let second = clean_token(tokens.next()?);
+    parse_with(&format!("{first} {second}"), &PAIR_FORMATS)
+}
+
+fn parse_syslog(text: &str) -> Option<NaiveDateTime> {
+    let mut tokens = text.split_whitespace();
+    let (month, day, time) = (tokens.next()?, tokens.next()?, tokens.next()?);
+    let year = Local::now().year();
+    NaiveDateTime::parse_from_str(&format!("{year} {month} {day} {time}"), "%Y %b %d %H:%M:%S").ok()
+}
+
+fn first_token(text: &str) -> &str {
+    text.split_whitespace().next().unwrap_or_default()
+}
+
+fn clean_token(token: &str) -> String {
+    token.trim_end_matches([']', ',']).replace(',', ".")
+}
+
+fn parse_with(input: &str, formats: &[&str]) -> Option<NaiveDateTime> {
+    formats
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(input, fmt).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(format: TimestampFormat, text: &str) -> Option<String> {
+        format.parse(text).map(|ts| ts.to_string())
+    }
+
+    #[test]
+    fn parses_iso_timestamps() {
+        let iso = TimestampFormat::Iso;
+        assert_eq!(
+            parsed(iso, "2024-05-03T16:02:10.5+02:00 ready").as_deref(),
+            Some("2024-05-03 14:02:10.500")
+        );
+        assert_eq!(
+            parsed(iso, "[2024-05-03 14:02:10,250] INFO ready").as_deref(),
+            Some("2024-05-03 14:02:10.250")
+        );
+        assert_eq!(
+            parsed(iso, "2024/05/03 14:02:10 ready").as_deref(),
+            Some("2024-05-03 14:02:10")
+        );
+        assert_eq!(parsed(iso, "ready at 14:02:10"), None);
+    }
+
+    #[test]
+    fn parses_syslog_and_epoch_timestamps() {
+        let year = Local::now().year();
+        assert_eq!(
+            parsed(TimestampFormat::Syslog, "May  3 14:02:10 host sshd[1]: ok"),
+            Some(format!("{year}-05-03 14:02:10"))
+        );
+        assert_eq!(
+            parsed(TimestampFormat::EpochSeconds, "1714744930.25 ready").as_deref(),
+            Some("2024-05-03 14:02:10.250")
+        );
+        assert_eq!(
+            parsed(TimestampFormat::EpochMillis, "1714744930250 ready").as_deref(),
+            Some("2024-05-03 14:02:10.250")
+        );
+        // Ten digits of seconds, or thirteen of milliseconds.
+        assert_eq!(parsed(TimestampFormat::EpochSeconds, "200 OK"), None);
+        assert_eq!(
+            parsed(TimestampFormat::EpochMillis, "1714744930 ready"),
+            None
+        );
+    }
+
+    #[test]
+    fn looks_again_when_the_format_changes() {
+        let mut detector = TimestampDetector::default();
+        let mut stamp = |line: &str| {
+            let mut entry = LogEntry::new(line.to_string(), 1);
+            detector.stamp(&mut entry);
+            entry.timestamp.map(|ts| ts.to_string())
+        };
+        assert_eq!(
+            stamp("2024-05-03T14:02:10Z started").as_deref(),
+            Some("2024-05-03 14:02:10")
+        );
+        assert_eq!(
+            stamp("1714744931 updated").as_deref(),
+            Some("2024-05-03 14:02:11")
+        );
+        assert_eq!(stamp("    at main"), None);
+    }
+}
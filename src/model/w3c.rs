@@ -0,0 +1,193 @@
+use crate::{LogEntry, StructuredLine, status_level};
+
+/// Returns true if the text is a W3C extended log, like the ones IIS writes,
+/// which opens with directives such as `#Software:` and `#Fields:`.
+pub(crate) fn is_w3c(text: &str) -> bool {
+    text.lines()
+        .find(|line| !line.trim().is_empty())
+        .is_some_and(|line| line.starts_with('#'))
+        && text.lines().any(|line| line.starts_with("#Fields:"))
+}
+
+/// Reads W3C extended logs into a table whose columns come from their
+/// `#Fields:` directives, one text per file.
+///
+/// A new directive is written whenever the server restarts, possibly with
+/// other fields, so the columns are every field seen in the order they first
+/// appear and each row is laid out to match. Its level comes from the status
+/// like for access logs.
+pub(crate) fn parse_w3c(texts: &[String]) -> (Vec<String>, Vec<LogEntry>) {
+    let mut columns: Vec<String> = vec![];
+    let mut rows = vec![];
+
+    for (source, text) in texts.iter().enumerate() {
+        let mut fields: Vec<String> = vec![];
+        for line in text.lines() {
+            if let Some(directive) = line.strip_prefix("#Fields:") {
+                fields = directive.split_whitespace().map(String::from).collect();
+                for field in &fields {
+                    if !columns.contains(field) {
+                        columns.push(field.clone());
+                    }
+                }
+                continue;
+            }
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+
+            let cells: Vec<(String, String)> =
+                fields.iter().cloned().zip(split_values(line)).collect();
+            rows.push((source, cells));
+        }
+    }
+
+    let entries = rows
+        .into_iter()
+        .map(|(source, cells)| w3c_entry(&columns, &cells, source))
+        .collect();
+
+    (columns, entries)
+}
+
+/// The fields of the rows at the end of a W3C log, from its last `#Fields:`
+/// directive.
+pub(crate) fn w3c_fields(text: &str) -> Vec<String> {
+    text.lines()
+        .rev()
+        .find_map(|line| line.strip_prefix("#Fields:"))
+        .map(|directive| directive.split_whitespace().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Reads rows appended to a W3C log, laid out like the ones already read.
+/// Returns `None` when a new `#Fields:` directive comes with them, which may
+/// add columns that every row has to be laid out again for.
+pub(crate) fn parse_w3c_rows(
+    columns: &[String],
+    fields: &[String],
+    text: &str,
+    source: usize,
+) -> Option<Vec<LogEntry>> {
+    let mut entries = vec![];
+    for line in text.lines() {
+        if line.starts_with("#Fields:") {
+            return None;
+        }
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        let cells: Vec<(String, String)> = fields.iter().cloned().zip(split_values(line)).collect();
+        entries.push(w3c_entry(columns, &cells, source));
+    }
+    Some(entries)
+}
+
+/// Lays a row out in the table's columns, with a `-` for the ones it lacks.
+fn w3c_entry(columns: &[String], cells: &[(String, String)], source: usize) -> LogEntry {
+    let cell = |name: &str| {
+        cells
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let timestamp = match (cell("date"), cell("time")) {
+        (Some(date), Some(time)) => Some(format!("{date} {time}")),
+        _ => None,
+    };
+    let level = cell("sc-status").map(|status| status_level(status).to_string());
+    let fields = columns
+        .iter()
+        .map(|column| (column.clone(), cell(column).unwrap_or("-").to_string()))
+        .collect();
+
+    LogEntry::from_structured(
+        StructuredLine {
+            timestamp,
+            level,
+            fields,
+            ..StructuredLine::default()
+        },
+        source,
+    )
+}
+
+/// Splits a row on whitespace, keeping double quoted values, which W3C logs
+/// use for strings holding spaces, in one piece.
+fn split_values(line: &str) -> Vec<String> {
+    let mut values = vec![];
+    let mut value = String::new();
+    let mut quoted = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !value.is_empty() {
+                    values.push(std::mem::take(&mut value));
+                }
+            }
+            c => value.push(c),
+        }
+    }
+    if !value.is_empty() {
+        values.push(value);
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(entry: &LogEntry) -> Vec<&str> {
+        let structured = entry.structured.as_ref().unwrap();
+        structured
+            .fields
+            .iter()
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn tells_w3c_logs_apart() {
+        assert!(is_w3c(
+            "\n#Software: IIS\n#Fields: date time\n2024-05-03 14:02:10\n"
+        ));
+        assert!(!is_w3c("2024-05-03 14:02:10 #Fields: date\n"));
+        assert!(!is_w3c("# notes\nready\n"));
+    }
+
+    #[test]
+    fn lays_rows_out_in_every_column_seen() {
+        let first = "#Software: Microsoft Internet Information Services 10.0\n\
+                     #Fields: date time cs-uri-stem sc-status\n\
+                     2024-05-03 14:02:10 /orders 500\n";
+        let second = "#Fields: date time cs(User-Agent) sc-status\n\
+                      2024-05-03 14:02:11 \"Mozilla/5.0 (Windows)\" 404\n";
+        let (columns, entries) = parse_w3c(&[first.to_string(), second.to_string()]);
+        assert_eq!(
+            columns,
+            ["date", "time", "cs-uri-stem", "sc-status", "cs(User-Agent)"]
+        );
+        assert_eq!(
+            cells(&entries[0]),
+            ["2024-05-03", "14:02:10", "/orders", "500", "-"]
+        );
+        assert_eq!(
+            cells(&entries[1]),
+            [
+                "2024-05-03",
+                "14:02:11",
+                "-",
+                "404",
+                "Mozilla/5.0 (Windows)"
+            ]
+        );
+        assert_eq!(entries[1].source, 1);
+
+        let structured = entries[0].structured.as_ref().unwrap();
+        assert_eq!(structured.timestamp.as_deref(), Some("2024-05-03 14:02:10"));
+        assert_eq!(structured.level.as_deref(), Some("ERROR"));
+    }
+}
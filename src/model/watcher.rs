@@ -0,0 +1,69 @@
+use color_eyre::Result;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    path::Path,
+    sync::mpsc::{self, Receiver},
+};
+
+/// Watches the log files in the background so the event loop knows when new
+/// lines have been written to them.
+#[derive(Debug)]
+pub(crate) struct LogWatcher {
+    // The watcher stops sending events once it's dropped, so it has to live
+    // as long as the receiver.
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+}
+
+impl LogWatcher {
+    pub(crate) fn new(paths: &[String]) -> Result<LogWatcher> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        for path in paths {
+            watcher.watch(Path::new(path), RecursiveMode::NonRecursive)?;
+        }
+
+        Ok(LogWatcher {
+            _watcher: watcher,
+            events: rx,
+        })
+    }
+
+    /// Drains every pending event and returns true if any of them modified a
+    /// file.
+    pub(crate) fn has_changes(&self) -> bool {
+        let mut changed = false;
+        while let Ok(event) = self.events.try_recv() {
+            if let Ok(event) = event
+                && matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_))
+            {
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, io::Write, thread, time::Duration};
+
+    #[test]
+    fn notices_lines_written_to_a_file() {
+        let path = std::env::temp_dir().join(format!("log_view_watch_{}.log", std::process::id()));
+        fs::write(&path, "first\n").unwrap();
+        let watcher = LogWatcher::new(&[path.display().to_string()]).unwrap();
+        assert!(!watcher.has_changes());
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "second").unwrap();
+        // The events come in from another thread, so give them a moment.
+        let changed = (0..50).any(|_| {
+            thread::sleep(Duration::from_millis(20));
+            watcher.has_changes()
+        });
+        fs::remove_file(&path).unwrap();
+        assert!(changed);
+    }
+}
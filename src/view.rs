@@ -1,2 +1,4 @@
+pub mod ansi;
 pub mod log_view;
+pub mod picker_view;
 pub(crate) mod tui;
@@ -0,0 +1,209 @@
+use ratatui::prelude::*;
+
+/// The eight basic colors, in the order of their SGR codes.
+const BASE_COLORS: [Color; 8] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+];
+
+/// The bright versions of the basic colors, as set by codes 90 to 97.
+const BRIGHT_COLORS: [Color; 8] = [
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// Turns text holding ANSI escape sequences into spans styled the way a
+/// terminal would show them. Sequences other than colors and text attributes,
+/// like cursor movement or window titles, are dropped.
+///
+/// Spans only carry what the escapes set, so text after a reset picks up the
+/// style of the row it's drawn in, like the color of its level.
+pub(crate) fn ansi_line(text: &str) -> Line<'static> {
+    let mut spans = vec![];
+    let mut style = Style::default();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            current.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('[') => {
+                let mut params = String::new();
+                let mut command = None;
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        command = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if command == Some('m') {
+                    if !current.is_empty() {
+                        spans.push(Span::styled(std::mem::take(&mut current), style));
+                    }
+                    style = apply_sgr(style, &params);
+                }
+            }
+            // Operating system commands end with a BEL or with ESC \.
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' {
+                        chars.next_if_eq(&'\\');
+                        break;
+                    }
+                }
+            }
+            // Character set selection takes one more character.
+            Some('(' | ')') => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+
+    if !current.is_empty() {
+        spans.push(Span::styled(current, style));
+    }
+    Line::from(spans)
+}
+
+/// Applies the parameters of a Select Graphic Rendition sequence, the `1;31`
+/// in `ESC[1;31m`, to the style.
+fn apply_sgr(style: Style, params: &str) -> Style {
+    // An empty parameter, like in `ESC[m`, means a reset. Colon separated
+    // sub parameters are read the same as semicolon separated ones.
+    let mut codes = params
+        .split([';', ':'])
+        .map(|code| code.parse::<u16>().unwrap_or(0));
+
+    let mut style = style;
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => without(style, Modifier::BOLD | Modifier::DIM),
+            23 => without(style, Modifier::ITALIC),
+            24 => without(style, Modifier::UNDERLINED),
+            25 => without(style, Modifier::SLOW_BLINK),
+            27 => without(style, Modifier::REVERSED),
+            29 => without(style, Modifier::CROSSED_OUT),
+            30..=37 => style.fg(BASE_COLORS[usize::from(code - 30)]),
+            38 => match extended_color(&mut codes) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            39 => Style { fg: None, ..style },
+            40..=47 => style.bg(BASE_COLORS[usize::from(code - 40)]),
+            48 => match extended_color(&mut codes) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            49 => Style { bg: None, ..style },
+            90..=97 => style.fg(BRIGHT_COLORS[usize::from(code - 90)]),
+            100..=107 => style.bg(BRIGHT_COLORS[usize::from(code - 100)]),
+            _ => style,
+        };
+    }
+    style
+}
+
+/// Drops modifiers the escapes added. Unlike `Style::remove_modifier` this
+/// doesn't take them off the row the text is drawn in.
+fn without(style: Style, modifier: Modifier) -> Style {
+    Style {
+        add_modifier: style.add_modifier.difference(modifier),
+        ..style
+    }
+}
+
+/// Reads the rest of a 256 color (`38;5;n`) or true color (`38;2;r;g;b`)
+/// parameter.
+fn extended_color(codes: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    let kind = codes.next()?;
+    let mut component = || u8::try_from(codes.next()?).ok();
+    match kind {
+        5 => Some(Color::Indexed(component()?)),
+        2 => Some(Color::Rgb(component()?, component()?, component()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(text: &str) -> Vec<(String, Style)> {
+        ansi_line(text)
+            .spans
+            .into_iter()
+            .map(|span| (span.content.to_string(), span.style))
+            .collect()
+    }
+
+    #[test]
+    fn styles_the_text_between_escapes() {
+        assert_eq!(
+            spans("\x1b[1;31merror\x1b[0m: \x1b[38;5;208mfailed\x1b[39m to build"),
+            [
+                (
+                    String::from("error"),
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                ),
+                (String::from(": "), Style::default()),
+                (
+                    String::from("failed"),
+                    Style::default().fg(Color::Indexed(208))
+                ),
+                (String::from(" to build"), Style::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_true_colors_and_drops_their_attributes() {
+        assert_eq!(
+            spans("\x1b[48:2:10:20:30;4mbg\x1b[24;49mplain"),
+            [
+                (
+                    String::from("bg"),
+                    Style::default()
+                        .bg(Color::Rgb(10, 20, 30))
+                        .add_modifier(Modifier::UNDERLINED)
+                ),
+                (String::from("plain"), Style::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_escapes_that_arent_colors() {
+        let line = ansi_line("\x1b]0;build\x07\x1b(B\x1b[2Kdone\x1b[m");
+        assert_eq!(line.to_string(), "done");
+        assert_eq!(line.spans[0].style, Style::default());
+    }
+}
@@ -1,216 +1,1478 @@
-use crate::{Filter, Message, Model, SearchMode, get_filtered_logs};
+use crate::{
+    App, Areas, Gutter, Heat, Jump, Level, LogEntry, LogFormat, LoggerPicker, MAX_PINS,
+    MINIMAP_WIDTH, MarkKey, Message, Minimap, Model, Preset, Prompt, ScreenPlace, SearchKind,
+    SearchMode, SearchPosition, SearchStyle, TabSearch, ansi_line, get_filtered_logs,
+    handle_picker_key, view_picker,
+};
 use color_eyre::eyre::Ok;
-use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::event::{
+    self, Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use ratatui::Frame;
 use ratatui::{prelude::*, widgets::*};
+use regex::Regex;
+use std::ops::Range;
+use std::path::Path;
 use std::time::Duration;
 use strip_ansi_escapes::strip;
 
-pub(crate) fn view(frame: &mut Frame, model: &mut Model) {
+/// Colors used to tell the files in a merged view apart.
+const BADGE_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::LightRed,
+];
+
+/// A regex marking what it finds in a line in its style.
+type Highlight = (Regex, Style);
+
+/// Colors of the pinned highlights, in the order they're pinned.
+const PIN_COLORS: [Color; MAX_PINS] = [
+    Color::LightGreen,
+    Color::LightMagenta,
+    Color::LightBlue,
+    Color::LightCyan,
+    Color::Green,
+    Color::Magenta,
+];
+
+/// Longest file name shown in a source badge before it gets cut off.
+const MAX_BADGE_WIDTH: usize = 20;
+
+/// Widest a table column gets before its cells are cut off.
+const MAX_COLUMN_WIDTH: usize = 40;
+
+/// Most pinned lines shown above the others before the rest are left out.
+const PINNED_ROWS: usize = 5;
+
+/// What a line grouped under an entry is indented by, when it's a row of
+/// its own.
+const CONTINUATION_INDENT: &str = "    ";
+
+/// How much of the width the list takes beside the preview, in percent,
+/// until the divider between them is dragged.
+const LIST_PERCENT: u16 = 60;
+
+pub(crate) fn view(frame: &mut Frame, app: &mut App) {
+    if app.show_picker
+        && let Some(picker) = &app.picker
+    {
+        view_picker(frame, frame.area(), picker);
+        return;
+    }
+
+    // Only spend a line on the tab bar when there's more than one file open.
+    let model_area = match app.tabs.len() < 2 {
+        true => frame.area(),
+        false => view_tabs(frame, app),
+    };
+    view_model(frame, model_area, app.model_mut());
+
+    if let Some(selected) = app.preset_menu {
+        view_preset_menu(frame, model_area, &app.presets, selected);
+    }
+    if let Some(search) = &app.tab_search {
+        view_tab_search(frame, model_area, &app.tabs, search);
+    }
+}
+
+/// Draws the tab bar and returns the area left for the active tab.
+fn view_tabs(frame: &mut Frame, app: &App) -> Rect {
+    let [tabs_area, model_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .areas(frame.area());
+
+    // The filters tell apart the tabs of the same log.
+    let titles = app
+        .tabs
+        .iter()
+        .map(|tab| match tab.filter_stages.is_empty() {
+            true => tab_name(tab),
+            false => format!("{} {}", tab_name(tab), filter_chain(tab)),
+        });
+    let tabs = Tabs::new(titles)
+        .select(app.active_tab)
+        .highlight_style(Style::default().black().on_cyan())
+        .divider("|");
+
+    frame.render_widget(tabs, tabs_area);
+    model_area
+}
+
+/// Draws the list of saved presets over the middle of the logs.
+fn view_preset_menu(frame: &mut Frame, area: Rect, presets: &[Preset], selected: usize) {
+    let items: Vec<ListItem> = match presets.is_empty() {
+        true => vec![ListItem::new(" no presets saved yet").dark_gray()],
+        false => presets
+            .iter()
+            .map(|preset| ListItem::new(format!(" {}", preset.name)))
+            .collect(),
+    };
+
+    let height = items.len().min(area.height.saturating_sub(2) as usize) as u16 + 2;
+    let [popup] = Layout::vertical([Constraint::Length(height)])
+        .flex(layout::Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Length(44)])
+        .flex(layout::Flex::Center)
+        .areas(popup);
+
+    let list = List::new(items)
+        .highlight_style(Style::default().black().on_cyan())
+        .block(
+            Block::bordered()
+                .border_type(BorderType::Rounded)
+                .title("presets")
+                .title_alignment(Alignment::Center)
+                .title_bottom(" apply: Enter  save current: s  cancel: Esc "),
+        );
+    let selected = (!presets.is_empty()).then_some(selected);
+    let mut state = ListState::default().with_selected(selected);
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut state);
+}
+
+/// The names of the files the tab reads, like `api.log+worker.log`.
+fn tab_name(tab: &Model) -> String {
+    tab.log_paths
+        .iter()
+        .map(|path| file_name(path))
+        .collect::<Vec<String>>()
+        .join("+")
+}
+
+/// Draws the lines found by searching every tab over the logs, under the
+/// name of the tab each was found in.
+fn view_tab_search(frame: &mut Frame, area: Rect, tabs: &[Model], search: &TabSearch) {
+    let mut items = vec![];
+    let mut selected = None;
+    let mut last_tab = None;
+    for (idx, found) in search.matches.iter().enumerate() {
+        if last_tab != Some(found.tab) {
+            last_tab = Some(found.tab);
+            let name = tab_name(&tabs[found.tab]);
+            let (count, listed) = (search.counts[found.tab], search.listed(found.tab));
+            let header = match count > listed {
+                true => format!(" {name} ({count}, the first {listed} listed)"),
+                false => format!(" {name} ({count})"),
+            };
+            items.push(ListItem::new(header).bold().cyan());
+        }
+        if idx == search.selected {
+            selected = Some(items.len());
+        }
+        let number = format!("{:>9}", thousands(found.entry.number)).dark_gray();
+        items.push(ListItem::new(Line::from(vec![
+            number,
+            Span::raw(format!("  {}", found.entry.line)),
+        ])));
+    }
+    if items.is_empty() {
+        items.push(ListItem::new(" no lines match in any tab").dark_gray());
+    }
+
+    let [popup] = Layout::vertical([Constraint::Percentage(80)])
+        .flex(layout::Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Percentage(90)])
+        .flex(layout::Flex::Center)
+        .areas(popup);
+
+    let list = List::new(items)
+        .highlight_style(Style::default().black().on_cyan())
+        .block(
+            Block::bordered()
+                .border_type(BorderType::Rounded)
+                .title(format!("every tab: {}", search.search))
+                .title_alignment(Alignment::Center)
+                .title_bottom(" jump to line: Enter  cancel: Esc "),
+        );
+    let mut state = ListState::default().with_selected(selected);
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut state);
+}
+
+/// Draws a row of the minimap for each stretch of lines, filled in as far
+/// as the worst of them are common and colored by their level, with a bar
+/// beside the rows in view.
+fn view_minimap(frame: &mut Frame, area: Rect, minimap: &Minimap) {
+    let rows = minimap.rows.iter().enumerate().map(|(row, heat)| {
+        let in_view = match minimap.in_view.contains(&row) {
+            true => "▐",
+            false => " ",
+        };
+        let glyph = heat_glyph(*heat);
+        let color = match heat.level {
+            Some(Level::Critical) => Color::Magenta,
+            Some(Level::Error) => Color::Red,
+            Some(Level::Warning) => Color::Yellow,
+            _ => Color::DarkGray,
+        };
+        Line::from(vec![
+            Span::raw(in_view),
+            Span::styled(glyph.repeat(2), Style::default().fg(color)),
+        ])
+    });
+    frame.render_widget(Paragraph::new(rows.collect::<Vec<_>>()), area);
+}
+
+/// How full a row of the minimap is drawn. Only warnings and worse are
+/// worth telling apart by how common they are.
+fn heat_glyph(heat: Heat) -> &'static str {
+    match heat.share {
+        _ if heat.level < Some(Level::Warning) => "░",
+        share if share >= 0.5 => "█",
+        share if share >= 0.2 => "▓",
+        share if share >= 0.05 => "▒",
+        _ => "░",
+    }
+}
+
+/// Shows the pinned lines above the others, numbered to tell where they are.
+fn view_pinned_lines(frame: &mut Frame, area: Rect, model: &Model) {
+    let pinned = &model.pinned_lines;
+    let rows = pinned.iter().take(PINNED_ROWS).map(|entry| {
+        get_formatted_row(
+            entry,
+            vec![line_number(entry)],
+            false,
+            false,
+            !model.strip_colors,
+            &[],
+            0,
+        )
+    });
+    let widest = pinned.iter().map(|entry| entry.number).max().unwrap_or(0);
+    let widths = [
+        Constraint::Length(thousands(widest).len() as u16),
+        Constraint::Fill(1),
+    ];
+    let title = match pinned.len() {
+        count if count > PINNED_ROWS => format!("pinned ({count}, the first {PINNED_ROWS} shown)"),
+        count => format!("pinned ({count})"),
+    };
+    let table = Table::new(rows, widths).block(
+        Block::bordered()
+            .border_type(BorderType::Rounded)
+            .title(title)
+            .title_alignment(Alignment::Center)
+            .title_bottom(" unpin: p on the line "),
+    );
+    frame.render_widget(table, area);
+}
+
+/// Returns the file name of the log, falling back to the full path.
+fn file_name(log_path: &str) -> String {
+    Path::new(log_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or(log_path.to_string())
+}
+
+fn view_model(frame: &mut Frame, area: Rect, model: &mut Model) {
     let opts_height = 3;
     let filter_height = 1;
 
-    let [log_area, search_area, opts_area] = Layout::default()
+    let pinned_height = match model.pinned_lines.len() {
+        0 => 0,
+        count => count.min(PINNED_ROWS) as u16 + 2,
+    };
+    let [pinned_area, log_area, search_area, opts_area] = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
+            Constraint::Length(pinned_height),
             Constraint::Percentage(100),
             Constraint::Length(opts_height),
             Constraint::Length(filter_height),
         ])
-        .areas(frame.area());
+        .areas(area);
+    if pinned_height > 0 {
+        view_pinned_lines(frame, pinned_area, model);
+    }
 
-    model.set_view_height((log_area.height - 2) as usize);
+    // Tables spend a row on their header.
+    let header_height = u16::from(model.columns.is_some());
+    let view_height = log_area.height.saturating_sub(2 + header_height);
+    model.set_view_height(view_height as usize);
 
+    let minimap_width = match model.show_minimap {
+        true => MINIMAP_WIDTH,
+        false => 0,
+    };
+    let [log_area, minimap_area] = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Fill(1), Constraint::Length(minimap_width)])
+        .areas(log_area);
+    // Its rows line up with the rows of lines.
+    let minimap_area = Rect::new(
+        minimap_area.x,
+        minimap_area.y + 1 + header_height,
+        minimap_area.width,
+        view_height,
+    );
+
+    let list_percent = model.list_percent.unwrap_or(LIST_PERCENT);
     let [log_list, log_preview] = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .constraints([
+            Constraint::Percentage(list_percent),
+            Constraint::Percentage(100 - list_percent),
+        ])
         .areas(log_area);
+    model.areas = Areas {
+        panes: log_area,
+        rows: Rect::new(
+            log_list.x + 1,
+            log_list.y + 1 + header_height,
+            log_list.width.saturating_sub(2),
+            view_height,
+        ),
+        divider: log_preview.x,
+        search: search_area,
+        minimap: minimap_area,
+    };
 
+    // Filtered first, so the counts under the logs are for these lines.
+    let filtered_logs = get_filtered_logs(model);
+
+    // Lines the search picked out are numbered, to tell where they are.
+    let gutter = shows_line_numbers(model);
+    let indexed = shows_list_index(model);
+    let mut widths = if let Some(columns) = &model.columns {
+        column_widths(columns, &filtered_logs)
+    } else if model.is_merged() {
+        let badge_width = model
+            .log_paths
+            .iter()
+            .map(|path| file_name(path).chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(MAX_BADGE_WIDTH);
+        vec![Constraint::Length(badge_width as u16), Constraint::Fill(1)]
+    } else {
+        vec![Constraint::Fill(1)]
+    };
+    if gutter {
+        let widest = filtered_logs.iter().map(|entry| entry.number).max();
+        let width = thousands(widest.unwrap_or(0)).len();
+        widths.insert(0, Constraint::Length(width as u16));
+    }
+    if indexed {
+        let last = model.first_shown + filtered_logs.len();
+        let width = list_index(last).len();
+        widths.insert(usize::from(gutter), Constraint::Length(width as u16));
+    }
+    // How far the widest line runs past the logs is how far they scroll.
+    // Tables fit their columns to the logs instead.
+    let mut overflow = 0;
+    if model.columns.is_none() {
+        let lead: usize = widths
+            .iter()
+            .map(|width| match width {
+                Constraint::Length(len) => *len as usize + 1,
+                _ => 0,
+            })
+            .sum();
+        let text_width = (log_list.width as usize).saturating_sub(2 + lead);
+        let widest = filtered_logs.iter().map(line_width).max().unwrap_or(0);
+        overflow = widest.saturating_sub(text_width);
+    }
+    model.set_overflow(overflow);
+
+    let mut logs_title = String::from("logs");
+    let sampling = model.sampler.as_ref().map(|sampler| sampler.describe());
+    let dedup = model.dedup.describe().map(String::from);
+    let scrolled = (model.scroll_x > 0).then(|| format!("from column {}", model.scroll_x + 1));
+    let paused = model
+        .follow_paused
+        .then(|| String::from("following paused, G resumes"));
+    for note in model
+        .source_status()
+        .into_iter()
+        .chain(sampling)
+        .chain(model.context.describe())
+        .chain(dedup)
+        .chain(scrolled)
+        .chain(paused)
+    {
+        logs_title.push_str(&format!(" - {note}"));
+    }
     let block = Block::bordered()
         .border_type(BorderType::Rounded)
-        .title("logs")
-        .title_alignment(Alignment::Center);
-
-    let filtered_logs = get_filtered_logs(model);
+        .title(logs_title)
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(match_counts(model)).right_aligned());
 
-    let lines = filtered_logs
+    // The search is marked over the pins, so it stands out where they meet.
+    let pins = model
+        .pinned
         .iter()
-        .enumerate()
-        .map(|(idx, l)| get_formatted_row(l, model.line_idx == idx))
-        .collect();
+        .zip(PIN_COLORS)
+        .map(|(regex, color)| (regex.clone(), Style::default().black().bg(color)));
+    let search = model
+        .highlights()
+        .into_iter()
+        .map(|regex| (regex, Style::default().black().on_yellow()));
+    let highlights: Vec<Highlight> = pins.chain(search).collect();
+
+    let lines = filtered_logs.iter().enumerate().map(|(idx, entry)| {
+        let number = gutter.then(|| line_number(entry));
+        let index = indexed.then(|| {
+            let index = list_index(model.first_shown + idx + 1);
+            Cell::from(Line::from(index).right_aligned()).dark_gray()
+        });
+        let badge = model
+            .is_merged()
+            .then(|| source_badge(&model.log_paths, entry.source));
+        get_formatted_row(
+            entry,
+            number.into_iter().chain(index).chain(badge).collect(),
+            model.columns.is_some(),
+            model.line_idx == idx,
+            !model.strip_colors,
+            &highlights,
+            model.scroll_x,
+        )
+    });
 
-    let default = String::new();
+    let mut line_paragraph = Table::new(lines, widths).block(block);
+    if let Some(columns) = &model.columns {
+        let header = gutter
+            .then(String::new)
+            .into_iter()
+            .chain(indexed.then(String::new))
+            .chain(columns.clone());
+        line_paragraph = line_paragraph.header(Row::new(header).bold().underlined());
+    }
+
+    let default = LogEntry::default();
     let curr_log = filtered_logs.get(model.line_idx).unwrap_or(&default);
-    let preview_paragraph = Paragraph::new(String::from_utf8(strip(curr_log.as_bytes())).unwrap())
+    let mut preview_title = match model.is_merged() && !filtered_logs.is_empty() {
+        true => format!("preview - {}", file_name(&model.log_paths[curr_log.source])),
+        false => String::from("preview"),
+    };
+    let mut preview = preview_text(curr_log, !model.strip_colors, &highlights);
+    let mut preview_scroll = 0;
+    if let Some(search) = model.preview_search() {
+        let (count, scroll) =
+            search_preview(&mut preview, &search, model.preview_match, log_preview);
+        model.preview_matches = count;
+        model.preview_match = model.preview_match.min(count.saturating_sub(1));
+        preview_scroll = scroll;
+        preview_title = match count {
+            0 => format!("{preview_title} - no matches"),
+            _ => format!("{preview_title} - {} of {count}", model.preview_match + 1),
+        };
+    }
+    let preview_paragraph = Paragraph::new(preview)
+        .scroll((preview_scroll, 0))
         .wrap(Wrap { trim: false })
         .block(
             Block::bordered()
                 .border_type(BorderType::Rounded)
-                .title("preview")
+                .title(preview_title)
                 .title_alignment(Alignment::Center),
         );
 
-    let line_paragraph = Table::from(lines).block(block);
-
-    let search = Paragraph::new(model.search_input.as_str())
-        .style(match model.search_mode {
-            SearchMode::None => Style::default(),
-            SearchMode::Search => Style::default().fg(Color::Cyan),
-        })
-        .block(
-            Block::bordered()
-                .border_type(BorderType::Rounded)
-                .title("search"),
-        );
+    let search_name = format!(
+        "{} ({}, {}{})",
+        match model.search_backward {
+            true => "search backward",
+            false => "search",
+        },
+        match model.search_kind {
+            SearchKind::Fuzzy => model.fuzzy.describe(),
+            kind => kind.name().to_string(),
+        },
+        model.case.name(),
+        match model.search_style {
+            SearchStyle::Jump => "",
+            SearchStyle::Filter => ", filtering",
+        }
+    );
+    let search_name = match model.narrowed.is_empty() {
+        true => search_name,
+        false => {
+            let narrowed: Vec<&str> = model
+                .narrowed
+                .iter()
+                .map(|(search, _)| search.as_str())
+                .collect();
+            format!("{search_name} within {}", narrowed.join(" ▸ "))
+        }
+    };
+    let search_name = match (&model.search_error, &model.search_position) {
+        (Some(error), _) => format!("{search_name} - {error}"),
+        (None, Some(position)) => format!("{search_name} - {}", match_position(position)),
+        (None, None) => search_name,
+    };
+    let (search_title, search_style) = match model.search_mode {
+        SearchMode::None => (search_name, Style::default()),
+        SearchMode::Search => (search_name, Style::default().fg(Color::Cyan)),
+        SearchMode::Prompt(prompt) => {
+            let title = match &model.prompt_error {
+                Some(error) => format!("{} - {error}", prompt.title()),
+                None => prompt.title().to_string(),
+            };
+            (title, Style::default().fg(Color::Yellow))
+        }
+    };
+    let chain_title = match model.filter_stages.is_empty() {
+        true => String::new(),
+        false => format!(" {} (undo: u) ", filter_chain(model)),
+    };
+    let search = Paragraph::new(model.input()).style(search_style).block(
+        Block::bordered()
+            .border_type(BorderType::Rounded)
+            .title(search_title)
+            .title(Line::from(chain_title).right_aligned()),
+    );
 
     render_opts(model, frame, opts_area);
     frame.render_widget(line_paragraph, log_list);
     frame.render_widget(preview_paragraph, log_preview);
     frame.render_widget(search, search_area);
+    if model.show_minimap {
+        view_minimap(frame, minimap_area, &model.minimap);
+    }
 
     set_cursor_pos(model, frame, search_area);
+
+    if let Some(selected) = model.mark_list {
+        view_mark_list(frame, log_area, selected, model);
+    }
+    if let Some(selected) = model.format_picker {
+        view_format_picker(frame, log_area, selected, model.detected_format);
+    }
+    if let Some(picker) = &model.logger_picker {
+        view_logger_picker(frame, log_area, picker, model);
+    }
+}
+
+/// Draws the loggers found in the log over the middle of it, marking the
+/// ones shown only with `+` and the muted ones with `-`.
+fn view_logger_picker(frame: &mut Frame, area: Rect, picker: &LoggerPicker, model: &Model) {
+    let items: Vec<ListItem> = match picker.loggers.is_empty() {
+        true => vec![ListItem::new(" no logger field found").dark_gray()],
+        false => picker
+            .loggers
+            .iter()
+            .map(|(logger, count)| {
+                let mark = match (
+                    model.filters.logger_include.contains(logger),
+                    model.filters.logger_exclude.contains(logger),
+                ) {
+                    (true, _) => '+',
+                    (_, true) => '-',
+                    _ => ' ',
+                };
+                ListItem::new(format!(" {mark} {logger} ({count})"))
+            })
+            .collect(),
+    };
+
+    let height = items.len().min(area.height.saturating_sub(2) as usize) as u16 + 2;
+    let [popup] = Layout::vertical([Constraint::Length(height)])
+        .flex(layout::Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Length(52)])
+        .flex(layout::Flex::Center)
+        .areas(popup);
+
+    let list = List::new(items)
+        .highlight_style(Style::default().black().on_cyan())
+        .block(
+            Block::bordered()
+                .border_type(BorderType::Rounded)
+                .title("loggers")
+                .title_alignment(Alignment::Center)
+                .title_bottom(" only: i  mute: x  clear: c  done: Esc "),
+        );
+    let selected = (!picker.loggers.is_empty()).then_some(picker.selected);
+    let mut state = ListState::default().with_selected(selected);
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut state);
+}
+
+/// Draws the marked lines over the middle of the logs, each after its
+/// letter and number.
+fn view_mark_list(frame: &mut Frame, area: Rect, selected: usize, model: &Model) {
+    let marked = model.marked_lines();
+    let items: Vec<ListItem> = match marked.is_empty() {
+        true => vec![ListItem::new(" no marks yet, set one with m and a letter").dark_gray()],
+        false => marked
+            .iter()
+            .map(|(letter, entry)| {
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!(" {letter} ")).bold(),
+                    format!("{:>9}", thousands(entry.number)).dark_gray(),
+                    Span::raw(format!("  {}", entry.line)),
+                ]))
+            })
+            .collect(),
+    };
+
+    let height = items.len().min(area.height.saturating_sub(2) as usize) as u16 + 2;
+    let [popup] = Layout::vertical([Constraint::Length(height)])
+        .flex(layout::Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Percentage(90)])
+        .flex(layout::Flex::Center)
+        .areas(popup);
+
+    let list = List::new(items)
+        .highlight_style(Style::default().black().on_cyan())
+        .block(
+            Block::bordered()
+                .border_type(BorderType::Rounded)
+                .title("marks")
+                .title_alignment(Alignment::Center)
+                .title_bottom(" go to: Enter  delete: d  close: Esc "),
+        );
+    let selected = (!marked.is_empty()).then_some(selected);
+    let mut state = ListState::default().with_selected(selected);
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut state);
 }
 
-pub(crate) fn handle_event(m: &mut Model) -> color_eyre::Result<Option<Message>> {
-    if event::poll(Duration::from_millis(400))? {
-        if let Event::Key(key) = event::read()? {
-            if key.kind == event::KeyEventKind::Press {
-                return Ok(handle_key(key, m));
+/// Draws the list of formats over the middle of the logs.
+fn view_format_picker(frame: &mut Frame, area: Rect, selected: usize, detected: Option<LogFormat>) {
+    let items = LogFormat::CHOICES.iter().map(|choice| {
+        let label = match choice {
+            Some(format) => format.label(),
+            None => "auto (try every format)",
+        };
+        match *choice == detected && choice.is_some() {
+            true => ListItem::new(format!(" {label} (detected)")),
+            false => ListItem::new(format!(" {label}")),
+        }
+    });
+
+    let height = LogFormat::CHOICES.len() as u16 + 2;
+    let [popup] = Layout::vertical([Constraint::Length(height)])
+        .flex(layout::Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Length(36)])
+        .flex(layout::Flex::Center)
+        .areas(popup);
+
+    let list = List::new(items)
+        .highlight_style(Style::default().black().on_cyan())
+        .block(
+            Block::bordered()
+                .border_type(BorderType::Rounded)
+                .title("log format")
+                .title_alignment(Alignment::Center)
+                .title_bottom(" choose: Enter  cancel: Esc "),
+        );
+    let mut state = ListState::default().with_selected(Some(selected));
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut state);
+}
+
+pub(crate) fn handle_event(app: &mut App) -> color_eyre::Result<Option<Message>> {
+    if app.show_picker {
+        if event::poll(Duration::from_millis(400))?
+            && let Event::Key(key) = event::read()?
+            && key.kind == event::KeyEventKind::Press
+        {
+            return Ok(handle_picker_key(key));
+        }
+        return Ok(None);
+    }
+
+    // The logs keep refreshing behind the tab search too.
+    if app.model_mut().logs_changed() {
+        return Ok(Some(Message::RefreshLogs));
+    }
+
+    if app.tab_search.is_some() {
+        if event::poll(Duration::from_millis(400))?
+            && let Event::Key(key) = event::read()?
+            && key.kind == event::KeyEventKind::Press
+        {
+            return Ok(handle_tab_search_key(key));
+        }
+        return Ok(None);
+    }
+
+    let m = app.model_mut();
+    // Wakes up to run the search once typing into it pauses.
+    let timeout = m.search_settles_in().unwrap_or(Duration::from_millis(400));
+    if !event::poll(timeout)? {
+        return Ok(None);
+    }
+    match event::read()? {
+        Event::Key(key) if key.kind == event::KeyEventKind::Press => match app.preset_menu {
+            Some(_) => Ok(handle_preset_key(key)),
+            None => Ok(handle_key(key, app.model_mut())),
+        },
+        // Menus and prompts are left to the keys.
+        Event::Mouse(mouse)
+            if app.preset_menu.is_none()
+                && !matches!(app.model_mut().search_mode, SearchMode::Prompt(_)) =>
+        {
+            let model = app.model_mut();
+            match model.format_picker.is_none()
+                && model.logger_picker.is_none()
+                && model.mark_list.is_none()
+            {
+                true => Ok(handle_mouse(mouse, model)),
+                false => Ok(None),
             }
         }
+        // Only what's being typed takes pasted text.
+        Event::Paste(text) if app.model_mut().search_mode != SearchMode::None => {
+            Ok(Some(Message::Paste(text)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Scrolls with the wheel, selects the line clicked, focuses the search when
+/// it's clicked, and resizes the panes as the divider between them is
+/// dragged.
+fn handle_mouse(mouse: MouseEvent, model: &Model) -> Option<Message> {
+    let areas = model.areas;
+    let at = Position::new(mouse.column, mouse.row);
+    match mouse.kind {
+        MouseEventKind::ScrollUp => Some(Message::WheelUp),
+        MouseEventKind::ScrollDown => Some(Message::WheelDown),
+        // The borders either side of the divider both pick it up.
+        MouseEventKind::Down(MouseButton::Left)
+            if areas.panes.contains(at) && mouse.column.abs_diff(areas.divider) <= 1 =>
+        {
+            Some(Message::Resizing(true))
+        }
+        MouseEventKind::Down(MouseButton::Left) if areas.minimap.contains(at) => Some(
+            Message::SelectMinimapRow((mouse.row - areas.minimap.y) as usize),
+        ),
+        MouseEventKind::Down(MouseButton::Left) if areas.rows.contains(at) => {
+            Some(Message::SelectRow((mouse.row - areas.rows.y) as usize))
+        }
+        MouseEventKind::Down(MouseButton::Left)
+            if areas.search.contains(at) && model.search_mode == SearchMode::None =>
+        {
+            Some(Message::ToggleSearch)
+        }
+        MouseEventKind::Drag(MouseButton::Left) if model.resizing => {
+            let column = mouse.column.saturating_sub(areas.panes.x);
+            let percent = column as u32 * 100 / areas.panes.width.max(1) as u32;
+            Some(Message::ResizePanes(percent as u16))
+        }
+        MouseEventKind::Up(MouseButton::Left) if model.resizing => Some(Message::Resizing(false)),
+        _ => None,
+    }
+}
+
+/// Handles the keys of the preset menu.
+fn handle_preset_key(key: event::KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => Some(Message::MoveDown),
+        KeyCode::Char('k') | KeyCode::Up => Some(Message::MoveUp),
+        KeyCode::Enter => Some(Message::PickPreset),
+        KeyCode::Char('s') => Some(Message::OpenPrompt(Prompt::PresetName)),
+        KeyCode::Esc => Some(Message::ClosePresets),
+        KeyCode::Char('q') => Some(Message::Quit),
+        _ => None,
+    }
+}
+
+/// Handles the keys of the lines found by searching every tab.
+fn handle_tab_search_key(key: event::KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => Some(Message::MoveDown),
+        KeyCode::Char('k') | KeyCode::Up => Some(Message::MoveUp),
+        KeyCode::Char('g') | KeyCode::Home => Some(Message::MoveTop),
+        KeyCode::Char('G') | KeyCode::End => Some(Message::MoveBottom),
+        KeyCode::Enter => Some(Message::OpenTabMatch),
+        KeyCode::Esc => Some(Message::CloseTabSearch),
+        KeyCode::Char('q') => Some(Message::Quit),
+        _ => None,
+    }
+}
+
+/// Keys editing what's typed into the search or a prompt, the way readline
+/// does.
+fn edit_key(key: event::KeyEvent) -> Option<Message> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    match key.code {
+        KeyCode::Char('w') if ctrl => Some(Message::DeleteWord),
+        KeyCode::Char('u') if ctrl => Some(Message::DeleteToStart),
+        KeyCode::Char('b') if alt => Some(Message::MoveCursorWordLeft),
+        KeyCode::Char('f') if alt => Some(Message::MoveCursorWordRight),
+        KeyCode::Left if ctrl => Some(Message::MoveCursorWordLeft),
+        KeyCode::Right if ctrl => Some(Message::MoveCursorWordRight),
+        KeyCode::Home => Some(Message::MoveCursorStart),
+        KeyCode::End => Some(Message::MoveCursorEnd),
+        KeyCode::Delete => Some(Message::DeleteForward),
+        _ => None,
     }
-    Ok(None)
 }
 
 fn handle_key(key: event::KeyEvent, model: &mut Model) -> Option<Message> {
+    if let SearchMode::Prompt(_) = model.search_mode {
+        if let Some(msg) = edit_key(key) {
+            return Some(msg);
+        }
+        return match key.code {
+            KeyCode::Enter => Some(Message::SubmitPrompt),
+            KeyCode::Esc => Some(Message::CancelPrompt),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Message::CancelPrompt)
+            }
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Message::HistoryMatching)
+            }
+            KeyCode::Char(insert_char) => Some(Message::AddChar(insert_char)),
+            KeyCode::Backspace => Some(Message::Delete),
+            KeyCode::Left => Some(Message::MoveCursorLeft),
+            KeyCode::Right => Some(Message::MoveCursorRight),
+            KeyCode::Up => Some(Message::HistoryOlder),
+            KeyCode::Down => Some(Message::HistoryNewer),
+            _ => None,
+        };
+    }
+
+    if model.logger_picker.is_some() {
+        return match key.code {
+            KeyCode::Char('j') | KeyCode::Down => Some(Message::MoveDown),
+            KeyCode::Char('k') | KeyCode::Up => Some(Message::MoveUp),
+            KeyCode::Char('i') | KeyCode::Char(' ') => Some(Message::IncludeLogger),
+            KeyCode::Char('x') => Some(Message::MuteLogger),
+            KeyCode::Char('c') => Some(Message::ClearLoggers),
+            KeyCode::Esc | KeyCode::Enter => Some(Message::CloseLoggerPicker),
+            KeyCode::Char('q') => Some(Message::Quit),
+            _ => None,
+        };
+    }
+
+    if model.mark_list.is_some() {
+        return match key.code {
+            KeyCode::Char('j') | KeyCode::Down => Some(Message::MoveDown),
+            KeyCode::Char('k') | KeyCode::Up => Some(Message::MoveUp),
+            KeyCode::Enter => Some(Message::PickMark),
+            KeyCode::Char('d') => Some(Message::DeleteMark),
+            KeyCode::Esc => Some(Message::CloseMarks),
+            KeyCode::Char('q') => Some(Message::Quit),
+            _ => None,
+        };
+    }
+
+    // The letter of a mark, after `m` or `'`.
+    if let Some(mark) = model.pending_mark {
+        return match (key.code, mark) {
+            (KeyCode::Char(letter), MarkKey::Set) if letter.is_ascii_alphabetic() => {
+                Some(Message::SetMark(letter))
+            }
+            (KeyCode::Char(letter), MarkKey::Jump) if letter.is_ascii_alphabetic() => {
+                Some(Message::JumpToMark(letter))
+            }
+            _ => Some(Message::CancelPendingKeys),
+        };
+    }
+
+    // Where to scroll the selected line to, after `z`.
+    if model.pending_align {
+        return match key.code {
+            KeyCode::Char('t') => Some(Message::AlignSelection(ScreenPlace::Top)),
+            KeyCode::Char('z') => Some(Message::AlignSelection(ScreenPlace::Middle)),
+            KeyCode::Char('b') => Some(Message::AlignSelection(ScreenPlace::Bottom)),
+            _ => Some(Message::CancelPendingKeys),
+        };
+    }
+
+    // The level or gap to jump to, after `]` or `[`.
+    if let Some(jump) = model.pending_jump {
+        let level = match key.code {
+            KeyCode::Char('g') => return Some(Message::JumpToGap(jump)),
+            KeyCode::Char('h') => return Some(Message::JumpToHot(jump)),
+            KeyCode::Char('e') => Level::Error,
+            KeyCode::Char('w') => Level::Warning,
+            KeyCode::Char('c') => Level::Critical,
+            _ => return Some(Message::CancelPendingKeys),
+        };
+        return Some(Message::JumpToLevel(level, jump));
+    }
+
+    if model.format_picker.is_some() {
+        return match key.code {
+            KeyCode::Char('j') | KeyCode::Down => Some(Message::MoveDown),
+            KeyCode::Char('k') | KeyCode::Up => Some(Message::MoveUp),
+            KeyCode::Enter => Some(Message::PickFormat),
+            KeyCode::Esc => Some(Message::CloseFormatPicker),
+            KeyCode::Char('q') => Some(Message::Quit),
+            _ => None,
+        };
+    }
+
     if model.search_mode == SearchMode::Search {
+        if let Some(msg) = edit_key(key) {
+            return Some(msg);
+        }
         return match key.code {
-            KeyCode::Enter | KeyCode::Esc => Some(Message::ToggleSearch),
+            KeyCode::Enter => Some(Message::SubmitSearch),
+            KeyCode::Esc => Some(Message::ToggleSearch),
             // Ctrl-c can exit search mode
             KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 Some(Message::ToggleSearch)
             }
+            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Message::CycleSearchKind)
+            }
+            KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Message::CycleFuzzyScorer)
+            }
+            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Message::ToggleSearchStyle)
+            }
+            KeyCode::Up if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Message::TightenFuzzy)
+            }
+            KeyCode::Down if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Message::LoosenFuzzy)
+            }
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Message::HistoryMatching)
+            }
             KeyCode::Char(insert_char) => Some(Message::AddChar(insert_char)),
+            KeyCode::Backspace if model.search_input.is_empty() && !model.narrowed.is_empty() => {
+                Some(Message::PopNarrowing)
+            }
             KeyCode::Backspace => Some(Message::Delete),
             KeyCode::Left => Some(Message::MoveCursorLeft),
             KeyCode::Right => Some(Message::MoveCursorRight),
+            KeyCode::Up => Some(Message::HistoryOlder),
+            KeyCode::Down => Some(Message::HistoryNewer),
             _ => None,
         };
     }
 
+    if model.filter_menu
+        && let Some(msg) = filter_menu_key(key)
+    {
+        return Some(msg);
+    }
+
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
     match key.code {
+        KeyCode::Char('u') if ctrl => Some(Message::MoveUpHalfPage),
+        KeyCode::Char('d') if ctrl => Some(Message::MoveDownHalfPage),
+        KeyCode::Char('b') if ctrl => Some(Message::MoveUpPage),
+        KeyCode::Char('f') if ctrl => Some(Message::MoveDownPage),
+        KeyCode::Char('o') if ctrl => Some(Message::JumpBack),
+        // Terminals send Ctrl-i as Tab, so it's only taken for folding on
+        // the lines of a group.
+        KeyCode::Tab if model.selects_group() => Some(Message::ToggleFold),
+        KeyCode::Tab => Some(Message::JumpForward),
+        KeyCode::Char('i') if ctrl => Some(Message::JumpForward),
+        KeyCode::PageUp => Some(Message::MoveUpPage),
+        KeyCode::PageDown => Some(Message::MoveDownPage),
+        KeyCode::Char('L') if model.g_modifier => Some(Message::OpenPresets),
+        KeyCode::Char('M') if model.g_modifier => Some(Message::OpenLoggerPicker),
+        KeyCode::Char('H') => Some(Message::SelectOnScreen(ScreenPlace::Top)),
+        KeyCode::Char('M') => Some(Message::SelectOnScreen(ScreenPlace::Middle)),
+        KeyCode::Char('L') => Some(Message::SelectOnScreen(ScreenPlace::Bottom)),
+        // A 0 on its own is left for keys of its own.
+        KeyCode::Char(digit @ '0'..='9') if digit != '0' || model.pending_count.is_some() => {
+            Some(Message::AddCountDigit(digit as usize - '0' as usize))
+        }
+        KeyCode::Char('%') | KeyCode::Char('p') if model.pending_count.is_some() => {
+            Some(Message::GotoPercent)
+        }
+        KeyCode::Char('0') => Some(Message::ScrollStart),
+        KeyCode::Char('$') => Some(Message::ScrollEnd),
+        KeyCode::Char('h') if model.g_modifier => Some(Message::PinSearch),
+        KeyCode::Char('h') | KeyCode::Left => Some(Message::ScrollLeft),
+        KeyCode::Char('l') if model.g_modifier => Some(Message::CycleGutter),
+        KeyCode::Char('l') | KeyCode::Right => Some(Message::ScrollRight),
+        KeyCode::Char('t') if model.g_modifier => Some(Message::NextTab),
+        KeyCode::Char('T') if model.g_modifier => Some(Message::PrevTab),
+        KeyCode::Char('n') if model.g_modifier => Some(Message::DuplicateTab),
+        KeyCode::Char('/') if model.g_modifier => Some(Message::OpenPrompt(Prompt::TabSearch)),
+        KeyCode::Char('p') if model.g_modifier => Some(Message::OpenPrompt(Prompt::PreviewSearch)),
+        KeyCode::Char('m') if model.g_modifier => Some(Message::ToggleMinimap),
         KeyCode::Char('j') | KeyCode::Down => Some(Message::MoveDown),
         KeyCode::Char('k') | KeyCode::Up => Some(Message::MoveUp),
         KeyCode::Char('q') => Some(Message::Quit),
+        KeyCode::Char('n') => Some(Message::NextMatch),
+        KeyCode::Char('N') => Some(Message::PrevMatch),
+        KeyCode::Char('p') => Some(Message::TogglePinLine),
+        KeyCode::Char('*') => Some(Message::SearchWord(Jump::Next)),
+        KeyCode::Char('#') => Some(Message::SearchWord(Jump::Previous)),
+        KeyCode::Char('.') => Some(Message::RepeatAction),
+        KeyCode::Char(';') => Some(Message::RepeatMotion { reverse: false }),
+        KeyCode::Char(',') => Some(Message::RepeatMotion { reverse: true }),
+        KeyCode::Char('m') => Some(Message::AwaitMark(MarkKey::Set)),
+        KeyCode::Char('z') => Some(Message::AwaitAlign),
+        KeyCode::Char(']') => Some(Message::AwaitJump(Jump::Next)),
+        KeyCode::Char('[') => Some(Message::AwaitJump(Jump::Previous)),
+        KeyCode::Char('\'') => Some(Message::AwaitMark(MarkKey::Jump)),
+        KeyCode::Char('>') => Some(Message::NextPreviewMatch),
+        KeyCode::Char('<') => Some(Message::PrevPreviewMatch),
         KeyCode::Char('g') => Some(Message::MoveTop),
         KeyCode::Char('G') => Some(Message::MoveBottom),
-        KeyCode::Char('s') | KeyCode::Char('/') => Some(Message::ToggleSearch),
-        KeyCode::Char('f') => {
-            if model.log_filter == Filter::SELECT {
-                Some(Message::ApplyFilter(Filter::NONE))
-            } else {
-                Some(Message::ApplyFilter(Filter::SELECT))
-            }
-        }
-        KeyCode::Char('i') => {
-            if model.log_filter == Filter::SELECT {
-                Some(Message::ApplyFilter(Filter::INFO))
-            } else {
-                None
-            }
-        }
-        KeyCode::Char('w') => {
-            if model.log_filter == Filter::SELECT {
-                Some(Message::ApplyFilter(Filter::WARNING))
-            } else {
-                None
-            }
+        KeyCode::Char('F') => Some(Message::ToggleFollow),
+        KeyCode::Char('C') => Some(Message::ClearLogs),
+        KeyCode::Char('E') => Some(Message::ToggleGroups),
+        KeyCode::Char('A') => Some(Message::ToggleColors),
+        KeyCode::Char('P') => Some(Message::OpenFormatPicker),
+        KeyCode::Char('w') => Some(Message::QuickFilter),
+        KeyCode::Char('W') => Some(Message::ClearQuickFilter),
+        KeyCode::Char('I') => Some(Message::ToggleCase),
+        KeyCode::Char('S') => Some(Message::CycleSampling),
+        KeyCode::Char('D') => Some(Message::CycleDedup),
+        KeyCode::Enter => Some(Message::ToggleFold),
+        KeyCode::Char('x') => Some(Message::HideLine),
+        KeyCode::Char('X') => Some(Message::HideLikeLine),
+        KeyCode::Char('u') | KeyCode::Backspace
+            if !key.modifiers.contains(KeyModifiers::CONTROL) =>
+        {
+            Some(Message::PopFilter)
         }
-        KeyCode::Char('e') => {
-            if model.log_filter == Filter::SELECT {
-                Some(Message::ApplyFilter(Filter::ERROR))
-            } else {
-                None
-            }
-        }
-        KeyCode::Char('c') => {
-            if model.log_filter == Filter::SELECT {
-                Some(Message::ApplyFilter(Filter::CRITICAL))
-            } else {
-                None
-            }
-        }
-        KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-            Some(Message::MoveUpPage)
-        }
-        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-            Some(Message::MoveDownPage)
-        }
-        KeyCode::Char('d') => {
-            if model.log_filter == Filter::SELECT {
-                Some(Message::ApplyFilter(Filter::DEBUG))
-            } else {
-                None
-            }
+        KeyCode::Char('+') | KeyCode::Char('=') => Some(Message::RaiseMinLevel),
+        KeyCode::Char('-') => Some(Message::LowerMinLevel),
+        KeyCode::Char('o') => Some(Message::OpenPicker),
+        // A search that's kept is narrowed down with another.
+        KeyCode::Char('/') if !model.search_input.is_empty() => Some(Message::NarrowSearch),
+        KeyCode::Char('s') | KeyCode::Char('/') => Some(Message::ToggleSearch),
+        KeyCode::Char('?') => Some(Message::SearchBackward),
+        KeyCode::Char('f') => Some(Message::ToggleFilterMenu),
+        KeyCode::Char('!') => Some(Message::OpenPrompt(Prompt::Exclude)),
+        KeyCode::Char(':') => Some(Message::OpenPrompt(Prompt::Command)),
+        _ => None,
+    }
+}
+
+/// Keys of the filter menu. Anything else works as it does outside of it.
+fn filter_menu_key(key: event::KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Char('i') => Some(Message::ToggleLevel(Level::Info)),
+        KeyCode::Char('w') => Some(Message::ToggleLevel(Level::Warning)),
+        KeyCode::Char('e') => Some(Message::ToggleLevel(Level::Error)),
+        KeyCode::Char('c') => Some(Message::ToggleLevel(Level::Critical)),
+        KeyCode::Char('d') if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::ToggleLevel(Level::Debug))
         }
+        KeyCode::Char('a') => Some(Message::ClearLevels),
+        KeyCode::Char('r') => Some(Message::OpenPrompt(Prompt::Regex)),
+        KeyCode::Char('x') => Some(Message::OpenPrompt(Prompt::Exclude)),
+        KeyCode::Char('t') => Some(Message::OpenPrompt(Prompt::TimeRange)),
+        KeyCode::Char('p') => Some(Message::OpenPrompt(Prompt::Expression)),
+        KeyCode::Char('k') => Some(Message::OpenPrompt(Prompt::Field)),
+        KeyCode::Char('f') | KeyCode::Enter | KeyCode::Esc => Some(Message::ToggleFilterMenu),
         _ => None,
     }
 }
 
 fn render_opts(model: &Model, frame: &mut Frame, opts_area: Rect) {
     if model.search_mode == SearchMode::Search {
+        let submit = match model.search_style {
+            SearchStyle::Jump => " Enter jumps (Ctrl-t)",
+            SearchStyle::Filter => " Enter filters (Ctrl-t)",
+        };
         let opts = Table::default()
-            .rows([Row::new(vec![" Exit Search: Esc/Ctrl-c"])])
+            .rows([Row::new(vec![
+                submit,
+                "Exit Search: Esc/Ctrl-c",
+                "History: Up/Down/Ctrl-r",
+                "Fuzzy/exact/regex: Ctrl-e",
+                "Fuzziness: Ctrl-Up/Down/f",
+            ])])
+            .cyan()
+            .bold();
+        frame.render_widget(opts, opts_area);
+        return;
+    }
+    if let SearchMode::Prompt(_) = model.search_mode {
+        let opts = Table::default()
+            .rows([Row::new(vec![
+                " Apply: Enter",
+                "Cancel: Esc/Ctrl-c",
+                "History: Up/Down/Ctrl-r",
+            ])])
             .cyan()
             .bold();
         frame.render_widget(opts, opts_area);
         return;
     }
 
-    match model.log_filter {
-        Filter::SELECT => {
-            let opts = Table::default()
-                .rows([Row::new(vec![
-                    " quit: q",
-                    "info: i",
-                    "warning: w",
-                    "error: e",
-                    "critical: c",
-                    "debug: d",
-                ])])
-                .cyan()
-                .bold();
+    match model.filter_menu {
+        true => {
+            // Levels that are on are highlighted, since several can be. The
+            // keys don't fit in columns, so they're only spaced apart.
+            let levels = [
+                (Level::Info, "info: i"),
+                (Level::Warning, "warning: w"),
+                (Level::Error, "error: e"),
+                (Level::Critical, "critical: c"),
+                (Level::Debug, "debug: d"),
+            ]
+            .map(|(level, key)| match model.filters.levels.contains(&level) {
+                true => Span::from(key).reversed(),
+                false => Span::from(key),
+            });
+            let others = [
+                "all: a",
+                "regex: r",
+                "exclude: x",
+                "time: t",
+                "expr: p",
+                "field: k",
+            ]
+            .map(Span::from);
+            let keys = [Span::from(" done: f")]
+                .into_iter()
+                .chain(levels)
+                .chain(others);
+            let line: Vec<Span> = keys.flat_map(|key| [key, Span::from("  ")]).collect();
+            let opts = Paragraph::new(Line::from(line)).cyan().bold();
             frame.render_widget(opts, opts_area);
         }
-        _ => {
-            let opts = Table::default()
-                .rows([Row::new(vec![" quit: q", "filter: f", "search: s or /"])])
-                .cyan()
-                .bold();
+        false => {
+            let min_level = match model.filters.min_level {
+                Some(level) => format!("min: {}+ (+/-)", level.name()),
+                None => String::from("min level: +/-"),
+            };
+            let mut opts = vec![
+                String::from(" quit: q"),
+                String::from("filter: f"),
+                String::from("presets: gL"),
+                String::from("search: s or /"),
+                String::from(match (model.follow, model.follow_paused) {
+                    (true, false) => "follow: F (on)",
+                    (true, true) => "follow: F (paused)",
+                    (false, _) => "follow: F",
+                }),
+                min_level,
+            ];
+            if !model.search_input.is_empty() {
+                opts.push(String::from("matches: n/N"));
+            }
+            if !model.pinned.is_empty() {
+                opts.push(format!("pins: {} (gh)", model.pinned.len()));
+            }
+            if model.preview_search.is_some() {
+                opts.push(String::from("in preview: >/<"));
+            }
+            if model.hidden_count > 0 {
+                opts.push(format!("hidden: {}", model.hidden_count));
+            }
+            let opts = Table::default().rows([Row::new(opts)]).cyan().bold();
             frame.render_widget(opts, opts_area);
         }
     };
 }
 
-fn get_formatted_row(log: &String, current_log: bool) -> Row {
+/// Shows structured lines one field per row, and anything else as is,
+/// followed by the lines grouped under it.
+fn preview_text(entry: &LogEntry, colors: bool, highlights: &[Highlight]) -> Text<'static> {
+    let mut text: Text = match &entry.structured {
+        Some(structured) => structured
+            .rows()
+            .into_iter()
+            .flat_map(|(key, value)| {
+                // Values like a GELF full message can span several lines.
+                let mut lines = value.lines().map(|line| Line::from(line.to_string()));
+                let first = lines.next().unwrap_or_default();
+                let mut spans = vec![format!("{key}: ").cyan()];
+                spans.extend(first.spans);
+                [Line::from(spans)]
+                    .into_iter()
+                    .chain(lines)
+                    .collect::<Vec<_>>()
+            })
+            .collect(),
+        None => entry
+            .line
+            .lines()
+            .map(|line| styled_line(line, colors))
+            .collect(),
+    };
+    text.extend(
+        entry
+            .extracted
+            .iter()
+            .map(|(key, value)| Line::from(vec![format!("{key}: ").cyan(), value.clone().into()])),
+    );
+    text.extend(
+        entry
+            .continuation
+            .iter()
+            .map(|line| styled_line(line, colors)),
+    );
+    text.lines = text
+        .lines
+        .into_iter()
+        .map(|line| highlight_line(line, highlights))
+        .collect();
+    text
+}
+
+/// Marks where the preview search occurs in the preview, the selected
+/// occurrence apart from the rest. Returns how many there are, along with
+/// how far to scroll to show the selected one a third of the way down.
+fn search_preview(
+    text: &mut Text<'static>,
+    search: &Regex,
+    selected: usize,
+    area: Rect,
+) -> (usize, u16) {
+    let mut count = 0;
+    let mut selected_line = None;
+    for (idx, line) in text.lines.iter_mut().enumerate() {
+        let plain = plain_text(line);
+        let found: Vec<Range<usize>> = search
+            .find_iter(&plain)
+            .map(|found| found.range())
+            .collect();
+        let current = found.get(selected.wrapping_sub(count)).cloned();
+        count += found.len();
+        *line = mark_ranges(
+            std::mem::take(line),
+            found,
+            Style::default().black().on_yellow(),
+        );
+        if let Some(range) = current {
+            selected_line = Some(idx);
+            *line = mark_ranges(
+                std::mem::take(line),
+                vec![range],
+                Style::default().black().on_light_red(),
+            );
+        }
+    }
+
+    // Long lines wrap, so they take up more than a row each.
+    let width = area.width.saturating_sub(2).max(1) as usize;
+    let rows_above: usize = text.lines[..selected_line.unwrap_or(0)]
+        .iter()
+        .map(|line| line.width().div_ceil(width).max(1))
+        .sum();
+    let scroll = rows_above.saturating_sub(area.height.saturating_sub(2) as usize / 3);
+    (count, scroll.min(u16::MAX as usize) as u16)
+}
+
+/// Builds the colored tag showing which file a merged line came from.
+fn source_badge(log_paths: &[String], source: usize) -> Cell<'static> {
+    let color = BADGE_COLORS[source % BADGE_COLORS.len()];
+    Cell::from(file_name(&log_paths[source])).style(Style::default().black().bg(color))
+}
+
+/// Sizes each column to its widest cell on screen, letting the last one take
+/// whatever room is left.
+fn column_widths(columns: &[String], logs: &[LogEntry]) -> Vec<Constraint> {
+    (0..columns.len())
+        .map(|idx| {
+            if idx + 1 == columns.len() {
+                return Constraint::Fill(1);
+            }
+            let widest = logs
+                .iter()
+                .filter_map(|entry| entry.structured.as_ref()?.fields.get(idx))
+                .map(|(_, value)| value.chars().count())
+                .chain([columns[idx].chars().count()])
+                .max()
+                .unwrap_or(0);
+            Constraint::Length(widest.min(MAX_COLUMN_WIDTH) as u16)
+        })
+        .collect()
+}
+
+/// Whether the rows start with the number each line has in its file.
+fn shows_line_numbers(model: &Model) -> bool {
+    model.gutter != Gutter::Auto || model.search_filters()
+}
+
+/// Whether the rows are also numbered by where they are among the lines
+/// listed, which only tells anything while some are filtered out.
+fn shows_list_index(model: &Model) -> bool {
+    model.gutter == Gutter::NumbersAndIndex && model.counts.matched < model.counts.total
+}
+
+/// Where a line is among the lines listed, like `#1,204`, for the gutter.
+fn list_index(index: usize) -> String {
+    format!("#{}", thousands(index))
+}
+
+/// The number the line has in its file, like `48,120`, for the gutter.
+fn line_number(entry: &LogEntry) -> Cell<'static> {
+    let number = match entry.number {
+        0 => String::new(),
+        number => thousands(number),
+    };
+    Cell::from(Line::from(number).right_aligned()).dark_gray()
+}
+
+/// Builds the row of a line, after the cells leading it like the number and
+/// the file it came from.
+fn get_formatted_row(
+    entry: &LogEntry,
+    lead: Vec<Cell<'static>>,
+    as_columns: bool,
+    current_log: bool,
+    colors: bool,
+    highlights: &[Highlight],
+    scroll: usize,
+) -> Row<'static> {
+    let log = entry.line.as_str();
+    let cells: Vec<Cell> = match &entry.structured {
+        Some(structured) if as_columns => structured
+            .fields
+            .iter()
+            .map(|(_, value)| Cell::from(highlight_line(Line::from(value.clone()), highlights)))
+            .collect(),
+        _ => {
+            // The highlight of the current row wouldn't be readable over
+            // the line's own colors.
+            let mut line = highlight_line(styled_line(log, colors && !current_log), highlights);
+            if entry.continued {
+                line.spans.insert(0, Span::raw(CONTINUATION_INDENT));
+            }
+            line.spans.extend(line_notes(entry));
+            vec![Cell::from(scroll_line(line, scroll))]
+        }
+    };
+    let row = Row::new(lead.into_iter().chain(cells));
+
     if current_log {
-        Row::new(vec![String::from_utf8(strip(log.as_bytes())).unwrap()])
-            .black()
-            .on_cyan()
-    } else if log.contains("INFO") {
-        Row::new(vec![String::from_utf8(strip(log.as_bytes())).unwrap()]).cyan()
-    } else if log.contains("WARNING") {
-        Row::new(vec![String::from_utf8(strip(log.as_bytes())).unwrap()]).yellow()
-    } else if log.contains("ERROR") {
-        Row::new(vec![String::from_utf8(strip(log.as_bytes())).unwrap()]).red()
-    } else if log.contains("CRITICAL") {
-        Row::new(vec![String::from_utf8(strip(log.as_bytes())).unwrap()])
-            .bold()
-            .black()
-            .on_red()
-    } else {
-        Row::new(vec![String::from_utf8(strip(log.as_bytes())).unwrap()])
+        return row.black().on_cyan();
+    }
+    if entry.context || entry.continued {
+        return row.dark_gray();
+    }
+    match entry.level {
+        Some(Level::Info) => row.cyan(),
+        Some(Level::Warning) => row.yellow(),
+        Some(Level::Error) => row.red(),
+        Some(Level::Critical) => row.bold().black().on_red(),
+        _ => row,
+    }
+}
+
+/// What's said after a line about the lines folded into it.
+fn line_notes(entry: &LogEntry) -> Vec<Span<'static>> {
+    let mut notes = vec![];
+    if !entry.continuation.is_empty() {
+        let lines = entry.continuation.len();
+        notes.push(format!(" (+{lines} lines)").dark_gray());
     }
+    if entry.repeats > 1 {
+        notes.push(format!(" ×{}", entry.repeats).bold());
+    }
+    notes
+}
+
+/// How many columns the line takes up in the logs, without its escape
+/// sequences.
+fn line_width(entry: &LogEntry) -> usize {
+    let text = String::from_utf8_lossy(&strip(entry.line.as_bytes()))
+        .chars()
+        .count();
+    let notes: usize = line_notes(entry)
+        .iter()
+        .map(|note| note.content.chars().count())
+        .sum();
+    let indent = match entry.continued {
+        true => CONTINUATION_INDENT.len(),
+        false => 0,
+    };
+    indent + text + notes
+}
+
+/// Drops the first columns of the line, to scroll it to the left.
+fn scroll_line(line: Line<'static>, columns: usize) -> Line<'static> {
+    if columns == 0 {
+        return line;
+    }
+    let style = line.style;
+    let mut skip = columns;
+    let spans: Vec<Span> = line
+        .spans
+        .into_iter()
+        .filter_map(|span| {
+            let len = span.content.chars().count();
+            if skip >= len {
+                skip -= len;
+                return None;
+            }
+            let content: String = span.content.chars().skip(skip).collect();
+            skip = 0;
+            Some(Span::styled(content, span.style))
+        })
+        .collect();
+    Line::from(spans).style(style)
+}
+
+/// Shows the line in the colors of its escape sequences, or with them
+/// stripped when colors are turned off.
+fn styled_line(text: &str, colors: bool) -> Line<'static> {
+    match colors {
+        true => ansi_line(text),
+        false => Line::from(String::from_utf8(strip(text.as_bytes())).unwrap()),
+    }
+}
+
+/// Marks what the regexes find in the line in their styles, to show what the
+/// search and filters matched and the pinned highlights. The line's own colors are kept around it.
+fn highlight_line(mut line: Line<'static>, highlights: &[Highlight]) -> Line<'static> {
+    let text = plain_text(&line);
+    for (regex, style) in highlights {
+        let found = regex.find_iter(&text).map(|found| found.range()).collect();
+        line = mark_ranges(line, found, *style);
+    }
+    line
+}
+
+fn plain_text(line: &Line) -> String {
+    line.spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect()
+}
+
+/// Patches the style onto the byte ranges of the line's text, splitting its
+/// spans where the ranges start and end.
+fn mark_ranges(line: Line<'static>, mut found: Vec<Range<usize>>, style: Style) -> Line<'static> {
+    found.retain(|range| !range.is_empty());
+    if found.is_empty() {
+        return line;
+    }
+    found.sort_by_key(|range| range.start);
+
+    let mut spans = vec![];
+    let mut offset = 0;
+    for span in line.spans {
+        let end = offset + span.content.len();
+        let mut pos = offset;
+        // Split the span where a match starts or ends within it.
+        while pos < end {
+            let inside = found.iter().find(|range| range.contains(&pos));
+            let next = match inside {
+                Some(range) => range.end,
+                None => found
+                    .iter()
+                    .map(|range| range.start)
+                    .find(|start| *start > pos)
+                    .unwrap_or(end),
+            }
+            .min(end);
+            let part = span.content[pos - offset..next - offset].to_string();
+            let style = match inside {
+                Some(_) => span.style.patch(style),
+                None => span.style,
+            };
+            spans.push(Span::styled(part, style));
+            pos = next;
+        }
+        offset = end;
+    }
+    Line { spans, ..line }
 }
 
 fn set_cursor_pos(model: &mut Model, frame: &mut Frame, input_area: Rect) {
@@ -223,6 +1485,174 @@ fn set_cursor_pos(model: &mut Model, frame: &mut Frame, input_area: Rect) {
             // Move one line down, from the border to the input line
             input_area.y + 1,
         )),
+        SearchMode::Prompt(_) => frame.set_cursor_position(Position::new(
+            input_area.x + model.cursor_pos as u16 + 1,
+            input_area.y + 1,
+        )),
         SearchMode::None => {}
     }
 }
+
+/// Counts the lines let through, like
+/// `1,248 / 3,402 lines match  ERROR 12  45%`, for the bottom of the logs,
+/// ending with how far through them the view is.
+fn match_counts(model: &Model) -> String {
+    let counts = &model.counts;
+    let levels: String = counts
+        .levels
+        .iter()
+        .rev()
+        .map(|(level, count)| format!("  {} {}", level.name(), thousands(*count)))
+        .collect();
+    let percent = model
+        .percent_through
+        .map(|percent| format!("  {percent}%"))
+        .unwrap_or_default();
+    format!(
+        " {} / {} lines match{levels}{percent} ",
+        thousands(counts.matched),
+        thousands(counts.total)
+    )
+}
+
+/// Tells which match is selected, like `match 12 of 87`, or how many there
+/// are when the selected line isn't one.
+fn match_position(position: &SearchPosition) -> String {
+    match (position.current, position.total) {
+        (_, 0) => String::from("no matches"),
+        (Some(current), total) => format!("match {} of {}", thousands(current), thousands(total)),
+        (None, 1) => String::from("1 match"),
+        (None, total) => format!("{} matches", thousands(total)),
+    }
+}
+
+/// Writes the number with commas between the thousands, like `3,402,119`.
+fn thousands(number: usize) -> String {
+    let digits = number.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Shows the changes made to the filters as a chain, like
+/// `ERROR ▸ /timeout/ ▸ [svc=payments]`.
+fn filter_chain(model: &Model) -> String {
+    let labels: Vec<&str> = model
+        .filter_stages
+        .iter()
+        .map(|stage| stage.label.as_str())
+        .collect();
+    labels.join(" ▸ ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// The keys of the log and what each does, written like `gL`, `^u` for
+    /// Ctrl-u or `5p` after a count.
+    const BINDINGS: &[(&str, &str)] = &[
+        ("^u", "MoveUpHalfPage"),
+        ("^d", "MoveDownHalfPage"),
+        ("^b", "MoveUpPage"),
+        ("^f", "MoveDownPage"),
+        ("^o", "JumpBack"),
+        ("^i", "JumpForward"),
+        ("gL", "OpenPresets"),
+        ("gM", "OpenLoggerPicker"),
+        ("H", "SelectOnScreen(Top)"),
+        ("M", "SelectOnScreen(Middle)"),
+        ("L", "SelectOnScreen(Bottom)"),
+        ("5", "AddCountDigit(5)"),
+        ("50", "AddCountDigit(0)"),
+        ("5%", "GotoPercent"),
+        ("5p", "GotoPercent"),
+        ("0", "ScrollStart"),
+        ("$", "ScrollEnd"),
+        ("gh", "PinSearch"),
+        ("h", "ScrollLeft"),
+        ("gl", "CycleGutter"),
+        ("l", "ScrollRight"),
+        ("gt", "NextTab"),
+        ("gT", "PrevTab"),
+        ("gn", "DuplicateTab"),
+        ("g/", "OpenPrompt(TabSearch)"),
+        ("gp", "OpenPrompt(PreviewSearch)"),
+        ("gm", "ToggleMinimap"),
+        ("j", "MoveDown"),
+        ("k", "MoveUp"),
+        ("q", "Quit"),
+        ("n", "NextMatch"),
+        ("N", "PrevMatch"),
+        ("p", "TogglePinLine"),
+        ("*", "SearchWord(Next)"),
+        ("#", "SearchWord(Previous)"),
+        (".", "RepeatAction"),
+        (";", "RepeatMotion { reverse: false }"),
+        (",", "RepeatMotion { reverse: true }"),
+        ("m", "AwaitMark(Set)"),
+        ("z", "AwaitAlign"),
+        ("]", "AwaitJump(Next)"),
+        ("[", "AwaitJump(Previous)"),
+        ("'", "AwaitMark(Jump)"),
+        (">", "NextPreviewMatch"),
+        ("<", "PrevPreviewMatch"),
+        ("g", "MoveTop"),
+        ("G", "MoveBottom"),
+        ("F", "ToggleFollow"),
+        ("C", "ClearLogs"),
+        ("E", "ToggleGroups"),
+        ("A", "ToggleColors"),
+        ("P", "OpenFormatPicker"),
+        ("w", "QuickFilter"),
+        ("W", "ClearQuickFilter"),
+        ("I", "ToggleCase"),
+        ("S", "CycleSampling"),
+        ("D", "CycleDedup"),
+        ("x", "HideLine"),
+        ("X", "HideLikeLine"),
+        ("u", "PopFilter"),
+        ("+", "RaiseMinLevel"),
+        ("=", "RaiseMinLevel"),
+        ("-", "LowerMinLevel"),
+        ("o", "OpenPicker"),
+        ("s", "ToggleSearch"),
+        ("/", "ToggleSearch"),
+        ("?", "SearchBackward"),
+        ("f", "ToggleFilterMenu"),
+        ("!", "OpenPrompt(Exclude)"),
+        (":", "OpenPrompt(Command)"),
+    ];
+
+    /// What the last key of `keys` does, with the count or `g` before it
+    /// pending.
+    fn press(keys: &str) -> String {
+        let mut model = Model::default();
+        let mut chars: Vec<char> = keys.chars().collect();
+        let last = chars.pop().unwrap();
+        let mut modifiers = KeyModifiers::NONE;
+        match chars.as_slice() {
+            [] => {}
+            ['^'] => modifiers = KeyModifiers::CONTROL,
+            ['g'] => model.g_modifier = true,
+            digits => model.pending_count = digits.iter().collect::<String>().parse().ok(),
+        }
+        let key = event::KeyEvent::new(KeyCode::Char(last), modifiers);
+        format!("{:?}", handle_key(key, &mut model))
+    }
+
+    #[test]
+    fn binds_each_key_to_one_thing() {
+        let mut bound = HashSet::new();
+        for (keys, action) in BINDINGS {
+            assert!(bound.insert(keys), "{keys} is bound twice");
+            assert_eq!(press(keys), format!("Some({action})"), "{keys}");
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{FilePicker, Message};
+use chrono::{DateTime, Local};
+use crossterm::event::{self, KeyCode};
+use ratatui::Frame;
+use ratatui::{prelude::*, widgets::*};
+use std::path::Path;
+
+pub(crate) fn view_picker(frame: &mut Frame, area: Rect, picker: &FilePicker) {
+    let [list_area, opts_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .areas(area);
+
+    let title = match &picker.error {
+        Some(error) => format!("{} - {error}", picker.dir),
+        None => picker.dir.clone(),
+    };
+    let block = Block::bordered()
+        .border_type(BorderType::Rounded)
+        .title(title)
+        .title_alignment(Alignment::Center);
+
+    if picker.entries.is_empty() {
+        let empty = Paragraph::new("no files in this directory").block(block);
+        frame.render_widget(empty, list_area);
+    } else {
+        let rows = picker.entries.iter().map(|entry| {
+            let name = Path::new(&entry.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or(entry.path.clone());
+            let modified: DateTime<Local> = entry.modified.into();
+            Row::new([
+                Cell::from(name),
+                Cell::from(Text::from(file_size(entry.size)).right_aligned()),
+                Cell::from(modified.format("%Y-%m-%d %H:%M").to_string()),
+            ])
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(10),
+                Constraint::Length(16),
+            ],
+        )
+        .column_spacing(2)
+        .row_highlight_style(Style::default().black().on_cyan())
+        .block(block);
+
+        // The state only lives for this frame, ratatui scrolls it so the
+        // selected row is visible.
+        let mut state = TableState::default().with_selected(Some(picker.selected));
+        frame.render_stateful_widget(table, list_area, &mut state);
+    }
+
+    let opts = Table::default()
+        .rows([Row::new(vec![" quit: q", "open: Enter", "back: Esc"])])
+        .cyan()
+        .bold();
+    frame.render_widget(opts, opts_area);
+}
+
+pub(crate) fn handle_picker_key(key: event::KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => Some(Message::MoveDown),
+        KeyCode::Char('k') | KeyCode::Up => Some(Message::MoveUp),
+        KeyCode::Char('g') | KeyCode::Home => Some(Message::MoveTop),
+        KeyCode::Char('G') | KeyCode::End => Some(Message::MoveBottom),
+        KeyCode::Enter => Some(Message::OpenPicked),
+        KeyCode::Esc => Some(Message::ClosePicker),
+        KeyCode::Char('q') => Some(Message::Quit),
+        _ => None,
+    }
+}
+
+/// Formats a size in bytes the way `ls -h` would.
+fn file_size(bytes: u64) -> String {
+    let units = ["B", "K", "M", "G", "T"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes}{}", units[0])
+    } else {
+        format!("{size:.1}{}", units[unit])
+    }
+}
@@ -3,6 +3,9 @@ use ratatui::{
     backend::{Backend, CrosstermBackend},
     crossterm::{
         ExecutableCommand,
+        event::{
+            DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        },
         terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
     },
 };
@@ -11,11 +14,16 @@ use std::{io::stdout, panic};
 pub(crate) fn init_terminal() -> color_eyre::Result<Terminal<impl Backend>> {
     enable_raw_mode()?;
     stdout().execute(EnterAlternateScreen)?;
+    // Pasted text comes in all at once instead of as keys.
+    stdout().execute(EnableBracketedPaste)?;
+    stdout().execute(EnableMouseCapture)?;
     let terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
     Ok(terminal)
 }
 
 pub(crate) fn restore_terminal() -> color_eyre::Result<()> {
+    stdout().execute(DisableMouseCapture)?;
+    stdout().execute(DisableBracketedPaste)?;
     stdout().execute(LeaveAlternateScreen)?;
     disable_raw_mode()?;
     Ok(())
@@ -24,6 +32,8 @@ pub(crate) fn restore_terminal() -> color_eyre::Result<()> {
 pub(crate) fn install_panic_hook() {
     let original_hook = panic::take_hook();
     panic::set_hook(Box::new(move |panic_info| {
+        stdout().execute(DisableMouseCapture).unwrap();
+        stdout().execute(DisableBracketedPaste).unwrap();
         stdout().execute(LeaveAlternateScreen).unwrap();
         disable_raw_mode().unwrap();
         original_hook(panic_info);